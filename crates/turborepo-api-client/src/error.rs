@@ -1,6 +1,6 @@
-use std::backtrace::Backtrace;
+use std::{backtrace::Backtrace, io};
 
-use reqwest::header::ToStrError;
+use reqwest::{header::ToStrError, StatusCode};
 use thiserror::Error;
 
 use crate::CachingStatus;
@@ -29,6 +29,22 @@ pub enum Error {
         status: CachingStatus,
         message: String,
     },
+    #[error("invalid header name: {0}")]
+    InvalidHeaderName(String),
+    #[error("invalid value for header {0}")]
+    InvalidHeaderValue(String),
+    #[error("unable to read OIDC token from {path}: {source}")]
+    OidcTokenFile { path: String, source: io::Error },
+    #[error("OIDC token response did not contain a token")]
+    MissingOidcToken,
+}
+
+impl Error {
+    /// Whether the server rejected our credentials, in which case a fresh
+    /// token may succeed
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, Error::ReqwestError(e) if e.status() == Some(StatusCode::UNAUTHORIZED))
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -8,7 +8,10 @@ use async_trait::async_trait;
 use lazy_static::lazy_static;
 use regex::Regex;
 pub use reqwest::Response;
-use reqwest::{Method, RequestBuilder, StatusCode};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    IntoUrl, Method, RequestBuilder, StatusCode,
+};
 use turborepo_ci::{is_ci, Vendor};
 use turborepo_vercel_api::{
     APIError, CachingStatus, CachingStatusResponse, PreflightResponse, SpacesResponse, Team,
@@ -16,9 +19,13 @@ use turborepo_vercel_api::{
 };
 use url::Url;
 
-pub use crate::error::{Error, Result};
+pub use crate::{
+    error::{Error, Result},
+    oidc::OidcTokenSource,
+};
 
 mod error;
+mod oidc;
 mod retry;
 mod spaces;
 
@@ -92,12 +99,16 @@ pub struct APIClient {
     base_url: String,
     user_agent: String,
     use_preflight: bool,
+    // configured headers that are sent along with every request
+    headers: HeaderMap,
 }
 
 pub struct APIAuth {
     pub team_id: String,
     pub token: String,
     pub team_slug: Option<String>,
+    /// Where to get a fresh token from when the current one is rejected
+    pub oidc: Option<OidcTokenSource>,
 }
 
 #[async_trait]
@@ -105,9 +116,7 @@ impl Client for APIClient {
     async fn get_user(&self, token: &str) -> Result<UserResponse> {
         let url = self.make_url("/v2/user");
         let request_builder = self
-            .client
-            .get(url)
-            .header("User-Agent", self.user_agent.clone())
+            .request(Method::GET, url)
             .header("Authorization", format!("Bearer {}", token))
             .header("Content-Type", "application/json");
         let response = retry::make_retryable_request(request_builder)
@@ -119,9 +128,7 @@ impl Client for APIClient {
 
     async fn get_teams(&self, token: &str) -> Result<TeamsResponse> {
         let request_builder = self
-            .client
-            .get(self.make_url("/v2/teams?limit=100"))
-            .header("User-Agent", self.user_agent.clone())
+            .request(Method::GET, self.make_url("/v2/teams?limit=100"))
             .header("Content-Type", "application/json")
             .header("Authorization", format!("Bearer {}", token));
 
@@ -134,10 +141,8 @@ impl Client for APIClient {
 
     async fn get_team(&self, token: &str, team_id: &str) -> Result<Option<Team>> {
         let response = self
-            .client
-            .get(self.make_url("/v2/team"))
+            .request(Method::GET, self.make_url("/v2/team"))
             .query(&[("teamId", team_id)])
-            .header("User-Agent", self.user_agent.clone())
             .header("Content-Type", "application/json")
             .header("Authorization", format!("Bearer {}", token))
             .send()
@@ -178,9 +183,7 @@ impl Client for APIClient {
         team_slug: Option<&str>,
     ) -> Result<CachingStatusResponse> {
         let request_builder = self
-            .client
-            .get(self.make_url("/v8/artifacts/status"))
-            .header("User-Agent", self.user_agent.clone())
+            .request(Method::GET, self.make_url("/v8/artifacts/status"))
             .header("Content-Type", "application/json")
             .header("Authorization", format!("Bearer {}", token));
 
@@ -201,9 +204,7 @@ impl Client for APIClient {
         };
 
         let request_builder = self
-            .client
-            .get(self.make_url(endpoint.as_str()))
-            .header("User-Agent", self.user_agent.clone())
+            .request(Method::GET, self.make_url(endpoint.as_str()))
             .header("Content-Type", "application/json")
             .header("Authorization", format!("Bearer {}", token));
 
@@ -216,10 +217,8 @@ impl Client for APIClient {
 
     async fn verify_sso_token(&self, token: &str, token_name: &str) -> Result<VerifiedSsoUser> {
         let request_builder = self
-            .client
-            .get(self.make_url("/registration/verify"))
-            .query(&[("token", token), ("tokenName", token_name)])
-            .header("User-Agent", self.user_agent.clone());
+            .request(Method::GET, self.make_url("/registration/verify"))
+            .query(&[("token", token), ("tokenName", token_name)]);
 
         let response = retry::make_retryable_request(request_builder)
             .await?
@@ -259,11 +258,9 @@ impl Client for APIClient {
         }
 
         let mut request_builder = self
-            .client
-            .put(&request_url)
+            .request(Method::PUT, &request_url)
             .header("Content-Type", "application/octet-stream")
             .header("x-artifact-duration", duration.to_string())
-            .header("User-Agent", self.user_agent.clone())
             .body(artifact_body.to_vec());
 
         if allow_auth {
@@ -361,10 +358,7 @@ impl Client for APIClient {
             request_url = preflight_response.location.to_string();
         };
 
-        let mut request_builder = self
-            .client
-            .request(method, request_url)
-            .header("User-Agent", self.user_agent.clone());
+        let mut request_builder = self.request(method, request_url);
 
         if allow_auth {
             request_builder = request_builder.header("Authorization", format!("Bearer {}", token));
@@ -389,9 +383,7 @@ impl Client for APIClient {
         request_headers: &str,
     ) -> Result<PreflightResponse> {
         let request_builder = self
-            .client
             .request(Method::OPTIONS, request_url)
-            .header("User-Agent", self.user_agent.clone())
             .header("Access-Control-Request-Method", request_method)
             .header("Access-Control-Request-Headers", request_headers)
             .header("Authorization", format!("Bearer {}", token));
@@ -457,16 +449,46 @@ impl APIClient {
             base_url: base_url.as_ref().to_string(),
             user_agent,
             use_preflight,
+            headers: HeaderMap::new(),
         })
     }
+
+    /// Sends `headers` along with every request, for example to get through
+    /// a proxy in front of a self-hosted cache
+    pub fn with_headers<K, V>(mut self, headers: impl IntoIterator<Item = (K, V)>) -> Result<Self>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (name, value) in headers {
+            let (name, value) = (name.as_ref(), value.as_ref());
+            let header_name = HeaderName::try_from(name)
+                .map_err(|_| Error::InvalidHeaderName(name.to_string()))?;
+            let mut header_value = HeaderValue::try_from(value)
+                .map_err(|_| Error::InvalidHeaderValue(name.to_string()))?;
+            // these often hold credentials, so keep them out of debug output
+            header_value.set_sensitive(true);
+            self.headers.append(header_name, header_value);
+        }
+        Ok(self)
+    }
+
+    // Every request carries our user agent and the configured headers
+    fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        self.client
+            .request(method, url)
+            .header("User-Agent", self.user_agent.clone())
+            .headers(self.headers.clone())
+    }
 }
 
 #[cfg(test)]
 mod test {
     use anyhow::Result;
+    use reqwest::Method;
     use turborepo_vercel_api_mock::start_test_server;
 
-    use crate::{APIClient, Client};
+    use crate::{APIClient, Client, Error};
 
     #[tokio::test]
     async fn test_do_preflight() -> Result<()> {
@@ -525,4 +547,24 @@ mod test {
         handle.abort();
         Ok(())
     }
+
+    #[test]
+    fn test_with_headers() -> Result<()> {
+        let client = APIClient::new("http://localhost", 200, "2.0.0", false)?
+            .with_headers([("x-cache-region", "eu"), ("cf-access-token", "secret")])?;
+
+        let request = client
+            .request(Method::GET, client.make_url("/v8/artifacts/status"))
+            .build()?;
+        assert_eq!(request.headers()["x-cache-region"], "eu");
+        assert_eq!(request.headers()["cf-access-token"], "secret");
+        assert!(request.headers().contains_key("user-agent"));
+
+        let err = APIClient::new("http://localhost", 200, "2.0.0", false)?
+            .with_headers([("bad header", "value")])
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::InvalidHeaderName(name) if name == "bad header"));
+        Ok(())
+    }
 }
@@ -0,0 +1,138 @@
+use std::{env, path::PathBuf};
+
+use reqwest::Method;
+use serde::Deserialize;
+
+use crate::{retry, APIClient, Error, Result};
+
+const GITHUB_REQUEST_URL: &str = "ACTIONS_ID_TOKEN_REQUEST_URL";
+const GITHUB_REQUEST_TOKEN: &str = "ACTIONS_ID_TOKEN_REQUEST_TOKEN";
+
+/// Somewhere to get short-lived OIDC tokens from, for authenticating to a
+/// cache that trusts the identity provider instead of using a long-lived
+/// token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OidcTokenSource {
+    /// A file that is kept up to date with a current token, such as a
+    /// projected service account token
+    File(PathBuf),
+    /// The token endpoint GitHub Actions provides to jobs that have the
+    /// `id-token: write` permission
+    GithubActions {
+        request_url: String,
+        request_token: String,
+        audience: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct GithubTokenResponse {
+    value: Option<String>,
+}
+
+impl OidcTokenSource {
+    /// Picks a token source from what's configured. A token file takes
+    /// precedence, otherwise an audience enables requesting tokens from
+    /// GitHub Actions when we're running there.
+    pub fn new(token_file: Option<&str>, audience: Option<&str>) -> Option<Self> {
+        if let Some(token_file) = token_file {
+            return Some(OidcTokenSource::File(PathBuf::from(token_file)));
+        }
+
+        let audience = audience?;
+        let request_url = env::var(GITHUB_REQUEST_URL).ok()?;
+        let request_token = env::var(GITHUB_REQUEST_TOKEN).ok()?;
+        Some(OidcTokenSource::GithubActions {
+            request_url,
+            request_token,
+            audience: audience.to_string(),
+        })
+    }
+}
+
+impl APIClient {
+    /// Gets a current token from `source`. Tokens are short-lived, so this
+    /// should be called again whenever a token is rejected.
+    pub async fn fetch_oidc_token(&self, source: &OidcTokenSource) -> Result<String> {
+        match source {
+            OidcTokenSource::File(path) => {
+                let token = tokio::fs::read_to_string(path).await.map_err(|source| {
+                    Error::OidcTokenFile {
+                        path: path.display().to_string(),
+                        source,
+                    }
+                })?;
+                let token = token.trim();
+                if token.is_empty() {
+                    return Err(Error::MissingOidcToken);
+                }
+                Ok(token.to_string())
+            }
+            OidcTokenSource::GithubActions {
+                request_url,
+                request_token,
+                audience,
+            } => {
+                // This goes to GitHub rather than the cache, so the
+                // configured headers aren't sent along
+                let request_builder = self
+                    .client
+                    .request(Method::GET, request_url)
+                    .query(&[("audience", audience)])
+                    .header("User-Agent", self.user_agent.clone())
+                    .header("Authorization", format!("Bearer {}", request_token));
+
+                let response = retry::make_retryable_request(request_builder)
+                    .await?
+                    .error_for_status()?;
+                let GithubTokenResponse { value } = response.json().await?;
+
+                value
+                    .filter(|token| !token.is_empty())
+                    .ok_or(Error::MissingOidcToken)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use tempfile::NamedTempFile;
+
+    use super::OidcTokenSource;
+    use crate::{APIClient, Error};
+
+    #[tokio::test]
+    async fn test_file_token() -> Result<()> {
+        let client = APIClient::new("http://localhost", 200, "2.0.0", false)?;
+        let file = NamedTempFile::new()?;
+        let source = OidcTokenSource::new(file.path().to_str(), Some("turbo"))
+            .expect("token file is configured");
+        assert_eq!(source, OidcTokenSource::File(file.path().to_path_buf()));
+
+        let err = client.fetch_oidc_token(&source).await.unwrap_err();
+        assert!(matches!(err, Error::MissingOidcToken));
+
+        // the token is read again each time, picking up rotations
+        std::fs::write(file.path(), "first-token\n")?;
+        assert_eq!(client.fetch_oidc_token(&source).await?, "first-token");
+        std::fs::write(file.path(), "second-token\n")?;
+        assert_eq!(client.fetch_oidc_token(&source).await?, "second-token");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_token_file() -> Result<()> {
+        let client = APIClient::new("http://localhost", 200, "2.0.0", false)?;
+        let source = OidcTokenSource::File("/does/not/exist".into());
+        let err = client.fetch_oidc_token(&source).await.unwrap_err();
+        assert!(matches!(err, Error::OidcTokenFile { .. }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unconfigured() {
+        assert_eq!(OidcTokenSource::new(None, None), None);
+    }
+}
@@ -197,6 +197,7 @@ mod tests {
             team_id: "my-team-id".to_string(),
             token: "my-token".to_string(),
             team_slug: None,
+            oidc: None,
         });
         let mut async_cache = AsyncCache::new(&opts, &repo_root_path, api_client, api_auth)?;
 
@@ -272,6 +273,7 @@ mod tests {
             team_id: "my-team-id".to_string(),
             token: "my-token".to_string(),
             team_slug: None,
+            oidc: None,
         });
         let mut async_cache = AsyncCache::new(&opts, &repo_root_path, api_client, api_auth)?;
 
@@ -353,6 +355,7 @@ mod tests {
             team_id: "my-team-id".to_string(),
             token: "my-token".to_string(),
             team_slug: None,
+            oidc: None,
         });
         let mut async_cache = AsyncCache::new(&opts, &repo_root_path, api_client, api_auth)?;
 
@@ -1,7 +1,8 @@
-use std::{backtrace::Backtrace, io::Write};
+use std::{backtrace::Backtrace, future::Future, io::Write, sync::Mutex};
 
+use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
-use turborepo_api_client::{APIAuth, APIClient, Client, Response};
+use turborepo_api_client::{APIAuth, APIClient, Client, OidcTokenSource, Response};
 
 use crate::{
     cache_archive::{CacheReader, CacheWriter},
@@ -13,7 +14,9 @@ pub struct HTTPCache {
     client: APIClient,
     signer_verifier: Option<ArtifactSignatureAuthenticator>,
     repo_root: AbsoluteSystemPathBuf,
-    token: String,
+    // replaced when the cache rejects it and we're able to get a new one
+    token: Mutex<String>,
+    oidc: Option<OidcTokenSource>,
     team_id: String,
     team_slug: Option<String>,
 }
@@ -41,18 +44,43 @@ impl HTTPCache {
             team_id,
             token,
             team_slug,
+            oidc,
         } = api_auth;
 
         HTTPCache {
             client,
             signer_verifier,
             repo_root,
-            token,
+            token: Mutex::new(token),
+            oidc,
             team_id,
             team_slug,
         }
     }
 
+    /// Makes a request with the current token. If the token is rejected and
+    /// it came from an OIDC provider, the request is retried once with a
+    /// fresh token.
+    async fn with_token<T, F, Fut>(&self, request: F) -> Result<T, turborepo_api_client::Error>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T, turborepo_api_client::Error>>,
+    {
+        let token = self.token.lock().expect("lock poisoned").clone();
+        match request(token).await {
+            Err(err) if err.is_unauthorized() => {
+                let Some(oidc) = &self.oidc else {
+                    return Err(err);
+                };
+                debug!("remote cache rejected token, fetching a new one");
+                let token = self.client.fetch_oidc_token(oidc).await?;
+                *self.token.lock().expect("lock poisoned") = token.clone();
+                request(token).await
+            }
+            result => result,
+        }
+    }
+
     pub async fn put(
         &self,
         anchor: &AbsoluteSystemPath,
@@ -69,9 +97,13 @@ impl HTTPCache {
             .map(|signer| signer.generate_tag(hash.as_bytes(), &artifact_body))
             .transpose()?;
 
-        self.client
-            .put_artifact(hash, &artifact_body, duration, tag.as_deref(), &self.token)
-            .await?;
+        let (artifact_body, tag) = (&artifact_body, tag.as_deref());
+        self.with_token(|token| async move {
+            self.client
+                .put_artifact(hash, artifact_body, duration, tag, &token)
+                .await
+        })
+        .await?;
 
         Ok(())
     }
@@ -92,8 +124,11 @@ impl HTTPCache {
 
     pub async fn exists(&self, hash: &str) -> Result<CacheResponse, CacheError> {
         let response = self
-            .client
-            .artifact_exists(hash, &self.token, &self.team_id, self.team_slug.as_deref())
+            .with_token(|token| async move {
+                self.client
+                    .artifact_exists(hash, &token, &self.team_id, self.team_slug.as_deref())
+                    .await
+            })
             .await?;
 
         let duration = Self::get_duration_from_response(&response)?;
@@ -123,8 +158,11 @@ impl HTTPCache {
         hash: &str,
     ) -> Result<(CacheResponse, Vec<AnchoredSystemPathBuf>), CacheError> {
         let response = self
-            .client
-            .fetch_artifact(hash, &self.token, &self.team_id, self.team_slug.as_deref())
+            .with_token(|token| async move {
+                self.client
+                    .fetch_artifact(hash, &token, &self.team_id, self.team_slug.as_deref())
+                    .await
+            })
             .await?;
 
         let duration = Self::get_duration_from_response(&response)?;
@@ -230,6 +268,7 @@ mod test {
             team_id: "my-team".to_string(),
             token: "my-token".to_string(),
             team_slug: None,
+            oidc: None,
         };
 
         let cache = HTTPCache::new(api_client, &opts, repo_root_path.to_owned(), api_auth);
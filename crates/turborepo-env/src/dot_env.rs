@@ -0,0 +1,166 @@
+//! Parsing for `.env` files.
+//!
+//! Supports `KEY=value` pairs, an optional leading `export`, `#` comments,
+//! and single, double or backtick quoted values which may span multiple
+//! lines. Escape sequences are only processed inside double quotes. Variable
+//! expansion (`${OTHER}`) is not supported and is left as-is.
+
+use crate::{EnvironmentVariableMap, Error};
+
+/// Parses the contents of a `.env` file. If a variable is defined more than
+/// once, the last definition wins.
+pub fn parse_dot_env(contents: &str) -> Result<EnvironmentVariableMap, Error> {
+    let mut vars = EnvironmentVariableMap::default();
+    let mut lines = contents.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let line_number = index + 1;
+        let syntax_error = |reason| Error::DotEnvSyntax {
+            line: line_number,
+            reason,
+        };
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line
+            .strip_prefix("export ")
+            .map(str::trim_start)
+            .unwrap_or(line);
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| syntax_error("expected KEY=value"))?;
+        let key = key.trim_end();
+        if !is_valid_key(key) {
+            return Err(syntax_error("invalid variable name"));
+        }
+
+        let value = value.trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'' | '`')) => {
+                // Quoted values may continue onto the following lines
+                let mut quoted = value[1..].to_string();
+                let end = loop {
+                    if let Some(end) = closing_quote(&quoted, quote) {
+                        break end;
+                    }
+                    let Some((_, next)) = lines.next() else {
+                        return Err(syntax_error("unterminated quoted value"));
+                    };
+                    quoted.push('\n');
+                    quoted.push_str(next);
+                };
+
+                let rest = quoted[end + 1..].trim_start();
+                if !rest.is_empty() && !rest.starts_with('#') {
+                    return Err(syntax_error("unexpected characters after quoted value"));
+                }
+                quoted.truncate(end);
+
+                if quote == '"' {
+                    unescape(&quoted)
+                } else {
+                    quoted
+                }
+            }
+            // Unquoted values end at the first comment
+            _ => match value.find(" #") {
+                Some(comment) => value[..comment].trim_end().to_string(),
+                None => value.trim_end().to_string(),
+            },
+        };
+
+        vars.insert(key.to_string(), value);
+    }
+
+    Ok(vars)
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+// Finds the index of the unescaped quote that closes a value
+fn closing_quote(value: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            '\\' if quote == '"' && !escaped => escaped = true,
+            c if c == quote && !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some(c @ ('"' | '\\' | '$')) => unescaped.push(c),
+            // Unknown escapes are kept verbatim
+            Some(c) => {
+                unescaped.push('\\');
+                unescaped.push(c);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("FOO=bar", "bar" ; "unquoted")]
+    #[test_case("FOO = bar  ", "bar" ; "surrounding whitespace")]
+    #[test_case("export FOO=bar", "bar" ; "export")]
+    #[test_case("FOO=bar # comment", "bar" ; "trailing comment")]
+    #[test_case("FOO=bar#baz", "bar#baz" ; "hash without whitespace")]
+    #[test_case("FOO=", "" ; "empty")]
+    #[test_case("FOO='a \\n # b'", "a \\n # b" ; "single quoted")]
+    #[test_case("FOO=\"a\\nb \\\"c\\\"\" # comment", "a\nb \"c\"" ; "double quoted")]
+    #[test_case("FOO=\"first\nsecond\"", "first\nsecond" ; "multiline")]
+    #[test_case("FOO=`${BAR}`", "${BAR}" ; "no expansion")]
+    fn test_parse_value(contents: &str, expected: &str) {
+        let vars = parse_dot_env(contents).unwrap();
+        assert_eq!(vars.get("FOO").map(String::as_str), Some(expected));
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let vars = parse_dot_env(
+            "# database\nDB_HOST=localhost\n\nDB_PASSWORD=\"hunter2\"\nDB_HOST=db.internal\n",
+        )
+        .unwrap();
+        assert_eq!(vars.len(), 2);
+        assert_eq!(vars["DB_HOST"], "db.internal");
+        assert_eq!(vars["DB_PASSWORD"], "hunter2");
+    }
+
+    #[test_case("FOO", 1 ; "missing equals")]
+    #[test_case("\n1FOO=bar", 2 ; "invalid name")]
+    #[test_case("FOO=\"bar\nBAZ=qux", 1 ; "unterminated quote")]
+    #[test_case("FOO='bar' baz", 1 ; "trailing characters")]
+    fn test_parse_errors(contents: &str, expected_line: usize) {
+        let Err(Error::DotEnvSyntax { line, .. }) = parse_dot_env(contents) else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(line, expected_line);
+    }
+}
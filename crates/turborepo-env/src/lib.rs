@@ -1,5 +1,7 @@
 #![deny(clippy::all)]
 
+mod dot_env;
+
 use std::{
     collections::HashMap,
     env,
@@ -12,6 +14,8 @@ use serde::Serialize;
 use sha2::{Digest, Sha256};
 use thiserror::Error;
 
+pub use crate::dot_env::parse_dot_env;
+
 const DEFAULT_ENV_VARS: [&str; 1] = ["VERCEL_ANALYTICS_ID"];
 
 /// Environment mode after we've resolved the `Infer` variant
@@ -25,6 +29,8 @@ pub enum ResolvedEnvMode {
 pub enum Error {
     #[error("Failed to parse regex: {0}")]
     Regex(#[from] regex::Error),
+    #[error("Invalid .env file on line {line}: {reason}")]
+    DotEnvSyntax { line: usize, reason: &'static str },
 }
 
 // TODO: Consider using immutable data structures here
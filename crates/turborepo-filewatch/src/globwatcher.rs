@@ -118,6 +118,18 @@ pub enum Query {
         candidates: HashSet<String>,
         resp: oneshot::Sender<Result<HashSet<String>, Error>>,
     },
+    Stats {
+        resp: oneshot::Sender<GlobWatcherStats>,
+    },
+}
+
+/// A snapshot of how much a `GlobWatcher` is currently tracking
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GlobWatcherStats {
+    /// hashes with at least one glob that hasn't changed
+    pub hashes: usize,
+    /// distinct globs being watched across all hashes
+    pub globs: usize,
 }
 
 struct GlobTracker {
@@ -184,6 +196,14 @@ impl GlobWatcher {
             .await?;
         rx.await?
     }
+
+    /// Reports how many hashes and globs are being tracked. Unlike other
+    /// queries, this doesn't wait for pending filesystem events.
+    pub async fn stats(&self) -> Result<GlobWatcherStats, Error> {
+        let (tx, rx) = oneshot::channel();
+        self.query_ch.send(Query::Stats { resp: tx }).await?;
+        Ok(rx.await?)
+    }
 }
 
 #[derive(Debug, Error)]
@@ -252,6 +272,12 @@ impl GlobTracker {
                 // If the client has gone away, we don't care about the error
                 let _ = resp.send(Ok(candidates));
             }
+            Query::Stats { resp } => {
+                let _ = resp.send(GlobWatcherStats {
+                    hashes: self.hash_globs.len(),
+                    globs: self.glob_statuses.len(),
+                });
+            }
         }
     }
 
@@ -351,7 +377,7 @@ mod test {
 
     use crate::{
         cookie_jar::CookieJar,
-        globwatcher::{GlobSet, GlobWatcher},
+        globwatcher::{GlobSet, GlobWatcher, GlobWatcherStats},
         FileSystemWatcher,
     };
 
@@ -479,6 +505,13 @@ mod test {
             .unwrap();
         let expected = HashSet::from_iter(["my-pkg/dist/**".to_string()]);
         assert_eq!(results, expected);
+        assert_eq!(
+            glob_watcher.stats().await.unwrap(),
+            GlobWatcherStats {
+                hashes: 1,
+                globs: 1
+            }
+        );
 
         // Change a file matching the other glob
         repo_root
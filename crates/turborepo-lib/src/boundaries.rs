@@ -0,0 +1,302 @@
+//! Architectural boundaries between workspaces.
+//!
+//! Workspaces declare `tags` in their turbo.json and the root turbo.json
+//! declares which tags a tagged workspace is allowed, or forbidden, to depend
+//! on:
+//!
+//! ```json
+//! {
+//!   "boundaries": {
+//!     "enforceOnRun": true,
+//!     "tags": {
+//!       "ui": { "deny": ["server"] },
+//!       "server": { "allow": ["server", "shared"], "transitive": true }
+//!     }
+//!   }
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::package_graph::{PackageGraph, WorkspaceName, WorkspaceNode};
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BoundariesConfig {
+    // Check boundaries before running any tasks
+    #[serde(default)]
+    pub enforce_on_run: bool,
+    // Rules keyed by the tag of the workspace being checked
+    #[serde(default)]
+    pub tags: BTreeMap<String, TagRule>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TagRule {
+    // If present, every dependency must have at least one of these tags
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Vec<String>>,
+    // Dependencies with any of these tags are forbidden
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    // Check all transitive dependencies instead of only direct ones
+    #[serde(default)]
+    pub transitive: bool,
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Violation {
+    #[error(
+        "{workspace} is tagged \"{tag}\" and cannot depend on {dependency}, which is tagged \
+         \"{dependency_tag}\""
+    )]
+    Denied {
+        workspace: WorkspaceName,
+        tag: String,
+        dependency: WorkspaceName,
+        dependency_tag: String,
+    },
+    #[error(
+        "{workspace} is tagged \"{tag}\" and can only depend on workspaces tagged {allowed}, but \
+         depends on {dependency}"
+    )]
+    NotAllowed {
+        workspace: WorkspaceName,
+        tag: String,
+        dependency: WorkspaceName,
+        allowed: String,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{} boundary violation(s) found:\n{}", .0.len(), format_violations(.0))]
+    Violations(Vec<Violation>),
+}
+
+fn format_violations(violations: &[Violation]) -> String {
+    violations
+        .iter()
+        .map(|violation| format!("  - {violation}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Checks every tagged workspace in the package graph against the rules for
+/// its tags and returns the violations, sorted by workspace.
+pub fn check(
+    package_graph: &PackageGraph,
+    workspace_tags: &HashMap<WorkspaceName, Vec<String>>,
+    config: &BoundariesConfig,
+) -> Vec<Violation> {
+    let no_tags = Vec::new();
+    let mut violations = Vec::new();
+
+    for (workspace, tags) in workspace_tags {
+        for tag in tags {
+            let Some(rule) = config.tags.get(tag) else {
+                continue;
+            };
+
+            let node = WorkspaceNode::Workspace(workspace.clone());
+            let dependencies = if rule.transitive {
+                package_graph.dependencies(&node)
+            } else {
+                package_graph
+                    .immediate_dependencies(&node)
+                    .unwrap_or_default()
+            };
+
+            for dependency in dependencies {
+                // The root node is an implicit dependency of every workspace
+                let WorkspaceNode::Workspace(dependency @ WorkspaceName::Other(_)) = dependency
+                else {
+                    continue;
+                };
+                let dependency_tags = workspace_tags.get(dependency).unwrap_or(&no_tags);
+
+                if let Some(dependency_tag) = dependency_tags
+                    .iter()
+                    .find(|dependency_tag| rule.deny.contains(dependency_tag))
+                {
+                    violations.push(Violation::Denied {
+                        workspace: workspace.clone(),
+                        tag: tag.clone(),
+                        dependency: dependency.clone(),
+                        dependency_tag: dependency_tag.clone(),
+                    });
+                } else if let Some(allow) = &rule.allow {
+                    if !dependency_tags
+                        .iter()
+                        .any(|dependency_tag| allow.contains(dependency_tag))
+                    {
+                        violations.push(Violation::NotAllowed {
+                            workspace: workspace.clone(),
+                            tag: tag.clone(),
+                            dependency: dependency.clone(),
+                            allowed: allow
+                                .iter()
+                                .map(|tag| format!("\"{tag}\""))
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    violations.sort();
+    violations
+}
+
+/// Like `check`, but returns an error if any violations were found
+pub fn enforce(
+    package_graph: &PackageGraph,
+    workspace_tags: &HashMap<WorkspaceName, Vec<String>>,
+    config: &BoundariesConfig,
+) -> Result<(), Error> {
+    let violations = check(package_graph, workspace_tags, config);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Violations(violations))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_repository::{package_json::PackageJson, package_manager::PackageManager};
+
+    use super::*;
+
+    // ui -> shared -> server
+    // app -> ui, server
+    fn package_graph() -> PackageGraph {
+        let root =
+            AbsoluteSystemPathBuf::new(if cfg!(windows) { r"C:\repo" } else { "/repo" }).unwrap();
+        let package_jsons = [
+            (
+                "app",
+                json!({ "name": "app", "dependencies": { "ui": "*", "server": "*" } }),
+            ),
+            (
+                "ui",
+                json!({ "name": "ui", "dependencies": { "shared": "*" } }),
+            ),
+            (
+                "shared",
+                json!({ "name": "shared", "dependencies": { "server": "*" } }),
+            ),
+            ("server", json!({ "name": "server" })),
+        ]
+        .into_iter()
+        .map(|(dir, package_json)| {
+            (
+                root.join_components(&[dir, "package.json"]),
+                PackageJson::from_value(package_json).unwrap(),
+            )
+        })
+        .collect();
+
+        PackageGraph::builder(
+            &root,
+            PackageJson::from_value(json!({ "name": "root" })).unwrap(),
+        )
+        .with_package_manger(Some(PackageManager::Npm))
+        .with_package_jsons(Some(package_jsons))
+        .build()
+        .unwrap()
+    }
+
+    fn workspace_tags() -> HashMap<WorkspaceName, Vec<String>> {
+        [
+            ("app", vec!["app"]),
+            ("ui", vec!["ui"]),
+            ("shared", vec!["shared"]),
+            ("server", vec!["server"]),
+        ]
+        .into_iter()
+        .map(|(name, tags)| {
+            (
+                WorkspaceName::from(name),
+                tags.into_iter().map(String::from).collect(),
+            )
+        })
+        .collect()
+    }
+
+    fn denied(workspace: &str, tag: &str, dependency: &str, dependency_tag: &str) -> Violation {
+        Violation::Denied {
+            workspace: workspace.into(),
+            tag: tag.to_string(),
+            dependency: dependency.into(),
+            dependency_tag: dependency_tag.to_string(),
+        }
+    }
+
+    fn not_allowed(workspace: &str, tag: &str, dependency: &str, allowed: &str) -> Violation {
+        Violation::NotAllowed {
+            workspace: workspace.into(),
+            tag: tag.to_string(),
+            dependency: dependency.into(),
+            allowed: allowed.to_string(),
+        }
+    }
+
+    #[test_case(json!({}), vec![] ; "no rules")]
+    #[test_case(
+        json!({ "tags": { "ui": { "deny": ["server"] } } }),
+        vec![] ;
+        "direct deny is satisfied"
+    )]
+    #[test_case(
+        json!({ "tags": { "ui": { "deny": ["server"], "transitive": true } } }),
+        vec![denied("ui", "ui", "server", "server")] ;
+        "transitive deny"
+    )]
+    #[test_case(
+        json!({ "tags": { "app": { "deny": ["server"] }, "shared": { "deny": ["server"] } } }),
+        vec![denied("app", "app", "server", "server"), denied("shared", "shared", "server", "server")] ;
+        "multiple denied dependents"
+    )]
+    #[test_case(
+        json!({ "tags": { "app": { "allow": ["ui"] } } }),
+        vec![not_allowed("app", "app", "server", "\"ui\"")] ;
+        "allow list"
+    )]
+    #[test_case(
+        json!({ "tags": { "app": { "allow": ["ui", "server"], "transitive": true } } }),
+        vec![not_allowed("app", "app", "shared", "\"ui\", \"server\"")] ;
+        "transitive allow list"
+    )]
+    fn test_check(config: serde_json::Value, expected: Vec<Violation>) {
+        let config: BoundariesConfig = serde_json::from_value(config).unwrap();
+        let violations = check(&package_graph(), &workspace_tags(), &config);
+        assert_eq!(violations, expected);
+    }
+
+    #[test]
+    fn test_untagged_dependency_violates_allow_list() {
+        let config: BoundariesConfig =
+            serde_json::from_value(json!({ "tags": { "ui": { "allow": ["shared"] } } })).unwrap();
+        let mut workspace_tags = workspace_tags();
+        workspace_tags.remove(&WorkspaceName::from("shared"));
+
+        let violations = check(&package_graph(), &workspace_tags, &config);
+        assert_eq!(
+            violations,
+            vec![not_allowed("ui", "ui", "shared", "\"shared\"")]
+        );
+        assert!(enforce(&package_graph(), &workspace_tags, &config).is_err());
+    }
+}
@@ -10,7 +10,10 @@ use turbopath::AbsoluteSystemPathBuf;
 use turborepo_ui::UI;
 
 use crate::{
-    commands::{bin, daemon, generate, info, link, login, logout, prune, unlink, CommandBase},
+    commands::{
+        bin, boundaries, daemon, generate, info, link, lockfile, login, logout, prune, query,
+        unlink, CommandBase,
+    },
     get_version,
     shim::{RepoMode, RepoState},
     tracing::TurboSubscriber,
@@ -192,6 +195,66 @@ pub enum DaemonCommand {
     /// Stops the turbo daemon if it is already running, and removes any stale
     /// daemon state
     Clean,
+    /// Prints the turbo daemon's log file for this repository
+    Logs {
+        /// Keep printing new log lines as they are written
+        #[clap(long, short)]
+        follow: bool,
+        /// Only print log lines at or above this level
+        #[clap(long, value_enum)]
+        level: Option<DaemonLogLevel>,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum DaemonLogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+#[derive(Subcommand, Clone, Debug, Serialize, PartialEq)]
+#[serde(tag = "command")]
+pub enum LockfileCommand {
+    /// Show how each workspace's external dependencies changed since a
+    /// previous commit
+    Diff {
+        /// The git ref to compare the lockfile against
+        #[clap(value_name = "REF")]
+        from: String,
+        /// Output the changes in JSON format
+        #[clap(long)]
+        json: bool,
+    },
+    /// List packages that are resolved to more than one version
+    Duplicates {
+        /// Output the duplicates in JSON format
+        #[clap(long)]
+        json: bool,
+    },
+    /// Convert the lockfile to the format of another package manager
+    Convert {
+        /// The package manager whose lockfile format to convert to
+        #[clap(long, value_enum)]
+        to: LockfileTarget,
+        /// Where to write the converted lockfile. This is required so the
+        /// lockfile in the repository root isn't overwritten by accident.
+        #[clap(long)]
+        out: String,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, ValueEnum)]
+pub enum LockfileTarget {
+    Npm,
+    Pnpm,
+    /// Yarn v1
+    Yarn,
+    /// Yarn v2 and later
+    Berry,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, ValueEnum)]
@@ -306,6 +369,9 @@ pub enum Command {
     // them as `{ "Bin": {} }` instead of as `"Bin"`.
     /// Get the path to the Turbo binary
     Bin {},
+    /// Check workspace dependencies against the boundaries defined in
+    /// turbo.json
+    Boundaries {},
     /// Generate the autocompletion script for the specified shell
     #[serde(skip)]
     Completion { shell: Shell },
@@ -358,9 +424,22 @@ pub enum Command {
     Login {
         #[clap(long = "sso-team")]
         sso_team: Option<String>,
+        /// Save the login under a named profile, which can be selected later
+        /// with TURBO_PROFILE
+        #[clap(long)]
+        profile: Option<String>,
+    },
+    /// Inspect the external dependencies resolved by your lockfile
+    Lockfile {
+        #[clap(subcommand)]
+        command: LockfileCommand,
     },
     /// Logout to your Vercel account
-    Logout {},
+    Logout {
+        /// Remove the login saved under a named profile
+        #[clap(long)]
+        profile: Option<String>,
+    },
     /// Prepare a subset of your monorepo.
     Prune {
         #[clap(hide = true, long)]
@@ -376,6 +455,19 @@ pub enum Command {
         docker: bool,
         #[clap(long = "out-dir", default_value_t = String::from("out"), value_parser)]
         output_dir: String,
+        /// Write the pruned output as a single reproducible `<out-dir>.tar.gz`
+        /// instead of a directory
+        #[clap(long)]
+        tarball: bool,
+        /// Additional files to copy from the repository root into the pruned
+        /// output, can be passed multiple times
+        #[clap(long, value_name = "GLOB")]
+        include: Vec<String>,
+    },
+    /// Query the package and task graphs of your monorepo
+    Query {
+        /// The query to run, or the path to a file containing it
+        query: String,
     },
 
     /// Run tasks across projects in your monorepo
@@ -548,6 +640,11 @@ pub struct RunArgs {
     /// which parts of your build were slow.
     #[clap(long)]
     pub profile: Option<String>,
+    /// Run tasks in a pseudo-terminal so that they keep colors and
+    /// interactive features. When there is a single persistent task, stdin
+    /// is forwarded to it. Only supported on unix systems.
+    #[clap(long)]
+    pub pty: bool,
     /// Ignore the local filesystem cache for all tasks. Only
     /// allow reading and caching artifacts using the remote cache.
     #[clap(long, env = "TURBO_REMOTE_ONLY", value_name = "BOOL", action = ArgAction::Set, default_value = "false", default_missing_value = "true", num_args = 0..=1)]
@@ -703,6 +800,12 @@ pub async fn run(
 
             Ok(Payload::Rust(Ok(0)))
         }
+        Command::Boundaries { .. } => {
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            let exit_code = boundaries::run(&base)?;
+
+            Ok(Payload::Rust(Ok(exit_code)))
+        }
         #[allow(unused_variables)]
         Command::Daemon { command, idle_time } => {
             let base = CommandBase::new(cli_args.clone(), repo_root, version, ui)?;
@@ -764,21 +867,32 @@ pub async fn run(
 
             Ok(Payload::Rust(Ok(0)))
         }
-        Command::Logout { .. } => {
-            let mut base = CommandBase::new(cli_args, repo_root, version, ui)?;
+        Command::Lockfile { command } => {
+            let command = command.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            lockfile::run(&base, &command)?;
+
+            Ok(Payload::Rust(Ok(0)))
+        }
+        Command::Logout { profile } => {
+            let profile = profile.clone();
+            let mut base =
+                CommandBase::new(cli_args, repo_root, version, ui)?.with_profile(profile);
             logout::logout(&mut base)?;
 
             Ok(Payload::Rust(Ok(0)))
         }
-        Command::Login { sso_team } => {
+        Command::Login { sso_team, profile } => {
             if cli_args.test_run {
                 println!("Login test run successful");
                 return Ok(Payload::Rust(Ok(0)));
             }
 
             let sso_team = sso_team.clone();
+            let profile = profile.clone();
 
-            let mut base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            let mut base =
+                CommandBase::new(cli_args, repo_root, version, ui)?.with_profile(profile);
 
             if let Some(sso_team) = sso_team {
                 login::sso_login(&mut base, &sso_team).await?;
@@ -834,6 +948,8 @@ pub async fn run(
             scope_arg,
             docker,
             output_dir,
+            tarball,
+            include,
         } => {
             let scope = scope_arg
                 .as_ref()
@@ -842,8 +958,17 @@ pub async fn run(
                 .unwrap_or_default();
             let docker = *docker;
             let output_dir = output_dir.clone();
+            let tarball = *tarball;
+            let include = include.clone();
             let base = CommandBase::new(cli_args, repo_root, version, ui)?;
-            prune::prune(&base, &scope, docker, &output_dir)?;
+            prune::prune(&base, &scope, docker, &output_dir, tarball, &include)?;
+            Ok(Payload::Rust(Ok(0)))
+        }
+        Command::Query { query } => {
+            let query = query.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            query::run(&base, &query)?;
+
             Ok(Payload::Rust(Ok(0)))
         }
         Command::Completion { shell } => {
@@ -913,7 +1038,8 @@ mod test {
     use anyhow::Result;
 
     use crate::cli::{
-        Args, Command, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode, RunArgs, Verbosity,
+        Args, Command, DaemonCommand, DaemonLogLevel, DryRunMode, EnvMode, LockfileCommand,
+        LockfileTarget, LogOrder, LogPrefix, OutputLogsMode, RunArgs, Verbosity,
     };
 
     #[test]
@@ -1476,6 +1602,18 @@ mod test {
             }
         );
 
+        assert_eq!(
+            Args::try_parse_from(["turbo", "run", "build", "--pty"]).unwrap(),
+            Args {
+                command: Some(Command::Run(Box::new(RunArgs {
+                    tasks: vec!["build".to_string()],
+                    pty: true,
+                    ..get_default_run_args()
+                }))),
+                ..Args::default()
+            }
+        );
+
         // remote-only flag tests
         assert_eq!(
             Args::try_parse_from(["turbo", "run", "build"]).unwrap(),
@@ -1602,12 +1740,120 @@ mod test {
         .test();
     }
 
+    #[test]
+    fn test_parse_daemon_logs() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "daemon", "logs"]).unwrap(),
+            Args {
+                command: Some(Command::Daemon {
+                    idle_time: "4h0m0s".to_string(),
+                    command: Some(DaemonCommand::Logs {
+                        follow: false,
+                        level: None,
+                    }),
+                }),
+                ..Args::default()
+            }
+        );
+        assert_eq!(
+            Args::try_parse_from(["turbo", "daemon", "logs", "-f", "--level", "warn"]).unwrap(),
+            Args {
+                command: Some(Command::Daemon {
+                    idle_time: "4h0m0s".to_string(),
+                    command: Some(DaemonCommand::Logs {
+                        follow: true,
+                        level: Some(DaemonLogLevel::Warn),
+                    }),
+                }),
+                ..Args::default()
+            }
+        );
+        assert!(Args::try_parse_from(["turbo", "daemon", "logs", "--level", "loud"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_boundaries() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "boundaries"]).unwrap(),
+            Args {
+                command: Some(Command::Boundaries {}),
+                ..Args::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "query", "{ packages { name } }"]).unwrap(),
+            Args {
+                command: Some(Command::Query {
+                    query: "{ packages { name } }".to_string(),
+                }),
+                ..Args::default()
+            }
+        );
+        assert!(Args::try_parse_from(["turbo", "query"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_lockfile() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "lockfile", "diff", "main"]).unwrap(),
+            Args {
+                command: Some(Command::Lockfile {
+                    command: LockfileCommand::Diff {
+                        from: "main".to_string(),
+                        json: false,
+                    },
+                }),
+                ..Args::default()
+            }
+        );
+        assert_eq!(
+            Args::try_parse_from(["turbo", "lockfile", "duplicates", "--json"]).unwrap(),
+            Args {
+                command: Some(Command::Lockfile {
+                    command: LockfileCommand::Duplicates { json: true },
+                }),
+                ..Args::default()
+            }
+        );
+        assert_eq!(
+            Args::try_parse_from([
+                "turbo",
+                "lockfile",
+                "convert",
+                "--to",
+                "pnpm",
+                "--out",
+                "pnpm-lock.yaml",
+            ])
+            .unwrap(),
+            Args {
+                command: Some(Command::Lockfile {
+                    command: LockfileCommand::Convert {
+                        to: LockfileTarget::Pnpm,
+                        out: "pnpm-lock.yaml".to_string(),
+                    },
+                }),
+                ..Args::default()
+            }
+        );
+        assert!(Args::try_parse_from(["turbo", "lockfile", "diff"]).is_err());
+        assert!(Args::try_parse_from(["turbo", "lockfile", "convert", "--to", "bun"]).is_err());
+        assert!(Args::try_parse_from(["turbo", "lockfile", "convert", "--to", "pnpm"]).is_err());
+    }
+
     #[test]
     fn test_parse_login() {
         assert_eq!(
             Args::try_parse_from(["turbo", "login"]).unwrap(),
             Args {
-                command: Some(Command::Login { sso_team: None }),
+                command: Some(Command::Login {
+                    sso_team: None,
+                    profile: None,
+                }),
                 ..Args::default()
             }
         );
@@ -1617,7 +1863,10 @@ mod test {
             command_args: vec![],
             global_args: vec![vec!["--cwd", "../examples/with-yarn"]],
             expected_output: Args {
-                command: Some(Command::Login { sso_team: None }),
+                command: Some(Command::Login {
+                    sso_team: None,
+                    profile: None,
+                }),
                 cwd: Some(Utf8PathBuf::from("../examples/with-yarn")),
                 ..Args::default()
             },
@@ -1631,6 +1880,22 @@ mod test {
             expected_output: Args {
                 command: Some(Command::Login {
                     sso_team: Some("my-team".to_string()),
+                    profile: None,
+                }),
+                cwd: Some(Utf8PathBuf::from("../examples/with-yarn")),
+                ..Args::default()
+            },
+        }
+        .test();
+
+        CommandTestCase {
+            command: "login",
+            command_args: vec![vec!["--profile", "work"]],
+            global_args: vec![vec!["--cwd", "../examples/with-yarn"]],
+            expected_output: Args {
+                command: Some(Command::Login {
+                    sso_team: None,
+                    profile: Some("work".to_string()),
                 }),
                 cwd: Some(Utf8PathBuf::from("../examples/with-yarn")),
                 ..Args::default()
@@ -1644,7 +1909,7 @@ mod test {
         assert_eq!(
             Args::try_parse_from(["turbo", "logout"]).unwrap(),
             Args {
-                command: Some(Command::Logout {}),
+                command: Some(Command::Logout { profile: None }),
                 ..Args::default()
             }
         );
@@ -1654,7 +1919,7 @@ mod test {
             command_args: vec![],
             global_args: vec![vec!["--cwd", "../examples/with-yarn"]],
             expected_output: Args {
-                command: Some(Command::Logout {}),
+                command: Some(Command::Logout { profile: None }),
                 cwd: Some(Utf8PathBuf::from("../examples/with-yarn")),
                 ..Args::default()
             },
@@ -1696,6 +1961,8 @@ mod test {
             scope_arg: Some(vec!["foo".into()]),
             docker: false,
             output_dir: "out".to_string(),
+            tarball: false,
+            include: Vec::new(),
         };
 
         assert_eq!(
@@ -1726,6 +1993,8 @@ mod test {
                     scope_arg: None,
                     docker: false,
                     output_dir: "out".to_string(),
+                    tarball: false,
+                    include: Vec::new(),
                 }),
                 ..Args::default()
             }
@@ -1739,6 +2008,8 @@ mod test {
                     scope_arg: Some(vec!["foo".to_string(), "bar".to_string()]),
                     docker: false,
                     output_dir: "out".to_string(),
+                    tarball: false,
+                    include: Vec::new(),
                 }),
                 ..Args::default()
             }
@@ -1752,6 +2023,8 @@ mod test {
                     scope_arg: Some(vec!["foo".into()]),
                     docker: true,
                     output_dir: "out".to_string(),
+                    tarball: false,
+                    include: Vec::new(),
                 }),
                 ..Args::default()
             }
@@ -1765,6 +2038,8 @@ mod test {
                     scope_arg: Some(vec!["foo".into()]),
                     docker: false,
                     output_dir: "dist".to_string(),
+                    tarball: false,
+                    include: Vec::new(),
                 }),
                 ..Args::default()
             }
@@ -1780,6 +2055,8 @@ mod test {
                     scope_arg: Some(vec!["foo".into()]),
                     docker: true,
                     output_dir: "dist".to_string(),
+                    tarball: false,
+                    include: Vec::new(),
                 }),
                 ..Args::default()
             },
@@ -1796,6 +2073,8 @@ mod test {
                     scope_arg: Some(vec!["foo".into()]),
                     docker: true,
                     output_dir: "dist".to_string(),
+                    tarball: false,
+                    include: Vec::new(),
                 }),
                 cwd: Some(Utf8PathBuf::from("../examples/with-yarn")),
                 ..Args::default()
@@ -1817,11 +2096,38 @@ mod test {
                     scope_arg: None,
                     docker: true,
                     output_dir: "dist".to_string(),
+                    tarball: false,
+                    include: Vec::new(),
                 }),
                 ..Args::default()
             },
         }
         .test();
+
+        assert_eq!(
+            Args::try_parse_from([
+                "turbo",
+                "prune",
+                "foo",
+                "--tarball",
+                "--include",
+                ".nvmrc",
+                "--include",
+                "config/*.json"
+            ])
+            .unwrap(),
+            Args {
+                command: Some(Command::Prune {
+                    scope: None,
+                    scope_arg: Some(vec!["foo".into()]),
+                    docker: false,
+                    output_dir: "out".to_string(),
+                    tarball: true,
+                    include: vec![".nvmrc".to_string(), "config/*.json".to_string()],
+                }),
+                ..Args::default()
+            }
+        );
     }
 
     #[test]
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use turborepo_repository::{package_json::PackageJson, package_manager::PackageManager};
+use turborepo_ui::{BOLD, GREY};
+
+use crate::{boundaries, commands::CommandBase, config::TurboJson, package_graph::PackageGraph};
+
+/// Checks the workspace dependency graph against the `boundaries` rules in
+/// the root turbo.json. Returns a non-zero exit code if any rule is violated.
+pub fn run(base: &CommandBase) -> Result<i32> {
+    let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
+
+    let package_manager =
+        PackageManager::get_package_manager(&base.repo_root, Some(&root_package_json))?;
+
+    let root_turbo_json = TurboJson::load(&base.repo_root, &root_package_json, false)?;
+
+    let package_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+        .with_package_manger(Some(package_manager))
+        .build()?;
+
+    let Some(config) = &root_turbo_json.boundaries else {
+        println!(
+            "{}",
+            base.ui
+                .apply(GREY.apply_to("No \"boundaries\" configured in turbo.json"))
+        );
+        return Ok(0);
+    };
+
+    let workspace_tags = TurboJson::load_workspace_tags(&base.repo_root, &package_graph)?;
+    let violations = boundaries::check(&package_graph, &workspace_tags, config);
+
+    if violations.is_empty() {
+        println!(
+            "{}",
+            base.ui.apply(BOLD.apply_to(format!(
+                "Checked {} workspaces, no boundary violations found",
+                // We subtract 1 for the root workspace
+                package_graph.len() - 1
+            )))
+        );
+        return Ok(0);
+    }
+
+    for violation in &violations {
+        eprintln!("  - {violation}");
+    }
+    eprintln!(
+        "\n{}",
+        base.ui
+            .apply(BOLD.apply_to(format!("{} boundary violation(s) found", violations.len())))
+    );
+
+    Ok(1)
+}
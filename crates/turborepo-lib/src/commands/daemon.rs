@@ -1,17 +1,26 @@
-use std::time::Duration;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    time::Duration,
+};
 
 use camino::Utf8PathBuf;
 use futures::FutureExt;
+use itertools::Itertools;
 use pidlock::PidlockError::AlreadyOwned;
 use time::{format_description, OffsetDateTime};
 use tokio::signal::ctrl_c;
-use tracing::{trace, warn};
-use turbopath::AbsoluteSystemPathBuf;
+use tracing::{trace, warn, Level};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 
 use super::CommandBase;
 use crate::{
-    cli::DaemonCommand,
-    daemon::{endpoint::SocketOpenError, CloseReason, DaemonConnector, DaemonError},
+    cli::{DaemonCommand, DaemonLogLevel},
+    daemon::{
+        endpoint::SocketOpenError,
+        logs::{self, LogLevelFilter},
+        CloseReason, DaemonConnector, DaemonError,
+    },
     tracing::TurboSubscriber,
 };
 
@@ -22,6 +31,7 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
         DaemonCommand::Restart | DaemonCommand::Stop => (false, true),
         DaemonCommand::Start => (true, true),
         DaemonCommand::Clean => (false, true),
+        DaemonCommand::Logs { .. } => (false, false),
     };
 
     let pid_file = base.daemon_file_root().join_component("turbod.pid");
@@ -58,6 +68,15 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
                 log_file: log_file.into(),
                 pid_file: client.pid_file().to_owned(),
                 sock_file: client.sock_file().to_owned(),
+                file_watching: status.file_watching,
+                watched_roots: status.watched_roots.into_iter().map(Into::into).collect(),
+                output_hashes: status.output_hashes,
+                output_globs: status.output_globs,
+                time_saved_entries: status.time_saved_entries,
+                events_total: status.events_total,
+                events_last_10s: status.events_last_10s,
+                events_last_60s: status.events_last_60s,
+                memory_bytes: status.memory_bytes,
             };
             if *json {
                 println!("{}", serde_json::to_string_pretty(&status)?);
@@ -69,6 +88,30 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
                 );
                 println!("Daemon pid file: {}", status.pid_file);
                 println!("Daemon socket file: {}", status.sock_file);
+                println!(
+                    "Daemon memory usage: {:.1} MiB",
+                    status.memory_bytes as f64 / (1024.0 * 1024.0)
+                );
+                if status.file_watching {
+                    println!(
+                        "Daemon watched roots: {}",
+                        status.watched_roots.iter().join(", ")
+                    );
+                } else {
+                    println!("Daemon filewatching: starting");
+                }
+                println!(
+                    "Daemon output globs: {} across {} task hashes",
+                    status.output_globs, status.output_hashes
+                );
+                println!("Daemon time saved entries: {}", status.time_saved_entries);
+                println!(
+                    "Daemon filesystem events: {} total, {:.1}/s over the last 10s, {:.1}/s over \
+                     the last minute",
+                    status.events_total,
+                    status.events_last_10s as f64 / 10.0,
+                    status.events_last_60s as f64 / 60.0
+                );
             }
         }
         DaemonCommand::Clean => {
@@ -118,6 +161,12 @@ pub async fn daemon_client(command: &DaemonCommand, base: &CommandBase) -> Resul
                 return Err(DaemonError::CleanFailed);
             }
         }
+        DaemonCommand::Logs { follow, level } => {
+            // Logs are located by repo hash, so this works whether or not the
+            // daemon is currently running.
+            let (_, log_file) = log_paths(base);
+            print_logs(&log_file, *follow, level.map(Level::from)).await?;
+        }
     };
 
     Ok(())
@@ -133,26 +182,117 @@ fn log_filename(base_filename: &str) -> Result<String, time::Error> {
     Ok(format!("{}.{}", base_filename, date))
 }
 
+/// Returns the daemon's log folder, and the base name of its log file for
+/// this repository. The daemon appends the date to the base name when
+/// rotating.
+fn log_paths(base: &CommandBase) -> (AbsoluteSystemPathBuf, AbsoluteSystemPathBuf) {
+    let directories = directories::ProjectDirs::from("com", "turborepo", "turborepo")
+        .expect("user has a home dir");
+
+    let folder = AbsoluteSystemPathBuf::new(directories.data_dir().to_str().expect("UTF-8 path"))
+        .expect("absolute");
+
+    let log_folder = folder.join_component("logs");
+    let log_file = log_folder.join_component(format!("{}-turbo.log", base.repo_hash()).as_str());
+
+    (log_folder, log_file)
+}
+
+/// How often the daemon removes old log files
+const LOG_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How often to check for new log lines when following
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Prints the most recent daemon log file, optionally following it as it
+/// grows and moving on to new files as they are rotated in.
+async fn print_logs(
+    log_file: &AbsoluteSystemPath,
+    follow: bool,
+    max_level: Option<Level>,
+) -> Result<(), DaemonError> {
+    let latest_log_file = || {
+        logs::latest_log_file(log_file).map_err(|e| DaemonError::LogFile(e, log_file.to_owned()))
+    };
+    let open = |path: &AbsoluteSystemPath| {
+        path.open()
+            .map(|file| LogReader::new(path.to_owned(), file, max_level))
+            .map_err(|e| DaemonError::LogFile(e, path.to_owned()))
+    };
+
+    let mut current = latest_log_file()?.map(|path| open(&path)).transpose()?;
+    if current.is_none() && !follow {
+        return Err(DaemonError::NoLogFile(log_file.to_owned()));
+    }
+
+    loop {
+        if let Some(reader) = &mut current {
+            reader.print_new_lines()?;
+        }
+        if !follow {
+            return Ok(());
+        }
+
+        tokio::time::sleep(FOLLOW_INTERVAL).await;
+        let latest = latest_log_file()?;
+        if latest.as_deref() != current.as_ref().map(|reader| &*reader.path) {
+            // The log was rotated, so finish off the old file before moving on
+            if let Some(reader) = &mut current {
+                reader.print_new_lines()?;
+                reader.flush_partial_line();
+            }
+            current = latest.map(|path| open(&path)).transpose()?;
+        }
+    }
+}
+
+/// Prints complete lines from a log file that is still being written to.
+struct LogReader {
+    path: AbsoluteSystemPathBuf,
+    reader: BufReader<File>,
+    filter: LogLevelFilter,
+    // holds a line that hasn't been completely written yet
+    partial_line: String,
+}
+
+impl LogReader {
+    fn new(path: AbsoluteSystemPathBuf, file: File, max_level: Option<Level>) -> Self {
+        Self {
+            path,
+            reader: BufReader::new(file),
+            filter: LogLevelFilter::new(max_level),
+            partial_line: String::new(),
+        }
+    }
+
+    fn print_new_lines(&mut self) -> Result<(), DaemonError> {
+        loop {
+            let read = self
+                .reader
+                .read_line(&mut self.partial_line)
+                .map_err(|e| DaemonError::LogFile(e, self.path.clone()))?;
+            if read == 0 || !self.partial_line.ends_with('\n') {
+                return Ok(());
+            }
+            self.flush_partial_line();
+        }
+    }
+
+    fn flush_partial_line(&mut self) {
+        if self.filter.should_show(&self.partial_line) {
+            print!("{}", self.partial_line);
+        }
+        self.partial_line.clear();
+    }
+}
+
 #[tracing::instrument(skip(base, logging), fields(repo_root = %base.repo_root))]
 pub async fn daemon_server(
     base: &CommandBase,
     idle_time: &String,
     logging: &TurboSubscriber,
 ) -> Result<(), DaemonError> {
-    let (log_folder, log_file) = {
-        let directories = directories::ProjectDirs::from("com", "turborepo", "turborepo")
-            .expect("user has a home dir");
-
-        let folder =
-            AbsoluteSystemPathBuf::new(directories.data_dir().to_str().expect("UTF-8 path"))
-                .expect("absolute");
-
-        let log_folder = folder.join_component("logs");
-        let log_file =
-            log_folder.join_component(format!("{}-turbo.log", base.repo_hash()).as_str());
-
-        (log_folder, log_file)
-    };
+    let (log_folder, log_file) = log_paths(base);
 
     tracing::trace!("logging to file: {:?}", log_file);
     if let Err(e) = logging.set_daemon_logger(tracing_appender::rolling::daily(
@@ -162,6 +302,18 @@ pub async fn daemon_server(
         // error here is not fatal, just log it
         tracing::error!("failed to set file logger: {}", e);
     }
+    // The daemon can outlive many rotations, so keep pruning old log files
+    // for as long as it runs.
+    let prune_log_file = log_file.clone();
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(LOG_PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = logs::prune_log_files(&prune_log_file, logs::MAX_LOG_FILES) {
+                warn!("failed to prune daemon log files: {}", e);
+            }
+        }
+    });
 
     let timeout = go_parse_duration::parse_duration(idle_time)
         .map_err(|_| DaemonError::InvalidTimeout(idle_time.to_owned()))
@@ -216,6 +368,18 @@ pub async fn daemon_server(
     Ok(())
 }
 
+impl From<DaemonLogLevel> for Level {
+    fn from(level: DaemonLogLevel) -> Self {
+        match level {
+            DaemonLogLevel::Error => Level::ERROR,
+            DaemonLogLevel::Warn => Level::WARN,
+            DaemonLogLevel::Info => Level::INFO,
+            DaemonLogLevel::Debug => Level::DEBUG,
+            DaemonLogLevel::Trace => Level::TRACE,
+        }
+    }
+}
+
 #[derive(serde::Serialize)]
 pub struct DaemonStatus {
     pub uptime_ms: u64,
@@ -224,4 +388,13 @@ pub struct DaemonStatus {
     pub log_file: Utf8PathBuf,
     pub pid_file: turbopath::AbsoluteSystemPathBuf,
     pub sock_file: turbopath::AbsoluteSystemPathBuf,
+    pub file_watching: bool,
+    pub watched_roots: Vec<Utf8PathBuf>,
+    pub output_hashes: u64,
+    pub output_globs: u64,
+    pub time_saved_entries: u64,
+    pub events_total: u64,
+    pub events_last_10s: u64,
+    pub events_last_60s: u64,
+    pub memory_bytes: u64,
 }
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use turbopath::AbsoluteSystemPath;
 use turborepo_repository::{package_json::PackageJson, package_manager::PackageManager};
 use turborepo_ui::GREY;
 
@@ -13,17 +14,55 @@ pub fn run(base: &mut CommandBase, workspace: Option<&str>) -> Result<()> {
     let package_manager =
         PackageManager::get_package_manager(&base.repo_root, Some(&root_package_json))?;
 
-    let package_graph = PackageGraph::builder(&base.repo_root, root_package_json)
-        .with_package_manger(Some(package_manager))
+    let package_graph = PackageGraph::builder(&base.repo_root, root_package_json.clone())
+        .with_package_manger(Some(package_manager.clone()))
         .build()?;
 
     if let Some(workspace) = workspace {
         print_workspace_details(&package_graph, workspace)
     } else {
+        print_package_manager_details(&base.repo_root, &package_manager, &root_package_json);
         print_repo_details(&package_graph)
     }
 }
 
+fn print_package_manager_details(
+    repo_root: &AbsoluteSystemPath,
+    package_manager: &PackageManager,
+    root_package_json: &PackageJson,
+) {
+    // Failing to find any of these details shouldn't prevent us from displaying the
+    // rest, we report what is missing instead
+    let installed_version = package_manager
+        .get_installed_version(repo_root)
+        .map_or_else(|e| format!("unknown ({e})"), |version| version.to_string());
+    let pinned_version = match package_manager.get_pinned_version(root_package_json) {
+        Ok(Some(version)) => version.to_string(),
+        Ok(None) => "not set".to_string(),
+        Err(e) => format!("invalid ({e})"),
+    };
+    let lockfile_version = match package_manager.read_lockfile(repo_root, root_package_json) {
+        Ok(lockfile) => lockfile
+            .lockfile_version()
+            .unwrap_or_else(|| "unversioned".to_string()),
+        Err(e) => format!("unknown ({e})"),
+    };
+
+    println!("Package manager: {}", package_manager);
+    println!("  installed version: {}", installed_version);
+    println!("  packageManager version: {}", pinned_version);
+    println!(
+        "  lockfile: {} {}",
+        package_manager.lockfile_name(),
+        GREY.apply_to(format!("(version {lockfile_version})"))
+    );
+    // Single package repositories don't have workspace globs
+    if let Ok(globs) = package_manager.get_workspace_globs(repo_root) {
+        println!("  workspace globs: {}", globs.raw_inclusions.join(", "));
+    }
+    println!();
+}
+
 fn print_repo_details(package_graph: &PackageGraph) -> Result<()> {
     // We subtract 1 for the root workspace
     println!("{} packages found in workspace\n", package_graph.len() - 1);
@@ -0,0 +1,160 @@
+use anyhow::{anyhow, Result};
+use tracing::warn;
+use turbopath::AbsoluteSystemPathBuf;
+use turborepo_lockfiles::{LockfileFormat, WorkspaceManifest};
+use turborepo_repository::{package_json::PackageJson, package_manager::PackageManager};
+use turborepo_scm::SCM;
+use turborepo_ui::{BOLD, GREY};
+
+use crate::{
+    cli::{LockfileCommand, LockfileTarget},
+    commands::CommandBase,
+    lockfile_audit,
+    package_graph::{PackageGraph, WorkspaceName},
+};
+
+pub fn run(base: &CommandBase, command: &LockfileCommand) -> Result<()> {
+    let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
+
+    let package_manager =
+        PackageManager::get_package_manager(&base.repo_root, Some(&root_package_json))?;
+
+    let package_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+        .with_package_manger(Some(package_manager))
+        .build()?;
+
+    match command {
+        LockfileCommand::Diff { from, json } => diff(base, &package_graph, from, *json),
+        LockfileCommand::Duplicates { json } => duplicates(base, &package_graph, *json),
+        LockfileCommand::Convert { to, out } => convert(base, &package_graph, *to, out),
+    }
+}
+
+fn diff(base: &CommandBase, package_graph: &PackageGraph, from: &str, json: bool) -> Result<()> {
+    let package_manager = package_graph.package_manager();
+    let lockfile_path = package_manager.lockfile_path(&base.repo_root);
+    let scm = SCM::new(&base.repo_root);
+    let previous_contents = scm.previous_content(from, &lockfile_path)?;
+    let previous =
+        package_manager.parse_lockfile(package_graph.root_package_json(), &previous_contents)?;
+
+    let changes = lockfile_audit::diff(package_graph, previous.as_ref())?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+        return Ok(());
+    }
+
+    if changes.is_empty() {
+        println!(
+            "{}",
+            base.ui
+                .apply(GREY.apply_to(format!("No changes to external dependencies since {from}")))
+        );
+        return Ok(());
+    }
+
+    for (workspace, changes) in &changes {
+        println!("{}", base.ui.apply(BOLD.apply_to(workspace)));
+        for change in changes {
+            println!("  {change}");
+        }
+    }
+
+    Ok(())
+}
+
+fn duplicates(base: &CommandBase, package_graph: &PackageGraph, json: bool) -> Result<()> {
+    let duplicates = lockfile_audit::duplicates(package_graph)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&duplicates)?);
+        return Ok(());
+    }
+
+    if duplicates.is_empty() {
+        println!(
+            "{}",
+            base.ui
+                .apply(GREY.apply_to("No packages are resolved to multiple versions"))
+        );
+        return Ok(());
+    }
+
+    for duplicate in &duplicates {
+        println!("{}", base.ui.apply(BOLD.apply_to(&duplicate.name)));
+        for (version, workspaces) in &duplicate.versions {
+            let workspaces = workspaces
+                .iter()
+                .map(|workspace| workspace.to_string())
+                .collect::<Vec<_>>();
+            println!("  {version}: {}", workspaces.join(", "));
+        }
+    }
+
+    Ok(())
+}
+
+fn convert(
+    base: &CommandBase,
+    package_graph: &PackageGraph,
+    target: LockfileTarget,
+    out: &str,
+) -> Result<()> {
+    let lockfile = package_graph
+        .lockfile()
+        .ok_or_else(|| anyhow!("no lockfile found to convert"))?;
+
+    let workspaces = package_graph
+        .workspaces()
+        .map(|(name, info)| {
+            let package_json = &info.package_json;
+            WorkspaceManifest {
+                path: info.package_path().to_unix().to_string(),
+                name: match name {
+                    WorkspaceName::Root => package_json
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| "root".to_string()),
+                    WorkspaceName::Other(name) => name.clone(),
+                },
+                version: package_json.version.clone(),
+                dependencies: package_json.dependencies.clone().unwrap_or_default(),
+                dev_dependencies: package_json.dev_dependencies.clone().unwrap_or_default(),
+                optional_dependencies: package_json
+                    .optional_dependencies
+                    .clone()
+                    .unwrap_or_default(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let format = match target {
+        LockfileTarget::Npm => LockfileFormat::Npm,
+        LockfileTarget::Pnpm => LockfileFormat::Pnpm,
+        LockfileTarget::Yarn => LockfileFormat::Yarn1,
+        LockfileTarget::Berry => LockfileFormat::Berry,
+    };
+    let conversion = turborepo_lockfiles::convert(lockfile, &workspaces, format)?;
+
+    let out_path = AbsoluteSystemPathBuf::from_unknown(&base.repo_root, out);
+    out_path.create_with_contents(&conversion.contents)?;
+
+    for unsupported in &conversion.unsupported {
+        warn!("{unsupported}");
+    }
+    println!(
+        "Wrote converted lockfile to {}",
+        base.ui.apply(BOLD.apply_to(&out_path))
+    );
+    if !conversion.unsupported.is_empty() {
+        println!(
+            "{}",
+            base.ui.apply(GREY.apply_to(
+                "Packages that couldn't be converted will be resolved on the next install"
+            ))
+        );
+    }
+
+    Ok(())
+}
@@ -26,7 +26,9 @@ pub async fn sso_login(base: &mut CommandBase, sso_team: &str) -> Result<()> {
         &login_url_config,
         sso_team,
     )
-    .await
+    .await?;
+
+    save_profile_remote(base)
 }
 
 pub async fn login(base: &mut CommandBase) -> Result<()> {
@@ -41,5 +43,18 @@ pub async fn login(base: &mut CommandBase) -> Result<()> {
         Ok(base.user_config_mut()?.set_token(Some(token.to_string()))?)
     };
 
-    auth_login(&api_client, &ui, &token_path, set_token, &login_url_config).await
+    auth_login(&api_client, &ui, &token_path, set_token, &login_url_config).await?;
+
+    save_profile_remote(base)
+}
+
+// When logging in to a named profile, the remote cache given on the command
+// line is remembered so later commands using the profile go to the same place.
+// This only happens once the login has succeeded.
+fn save_profile_remote(base: &mut CommandBase) -> Result<()> {
+    let args = base.args();
+    let (api, login, team) = (args.api.clone(), args.login.clone(), args.team.clone());
+    base.user_config_mut()?
+        .set_profile_remote(api, login, team)?;
+    Ok(())
 }
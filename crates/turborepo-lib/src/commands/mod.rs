@@ -2,6 +2,7 @@ use std::{borrow::Borrow, cell::OnceCell};
 
 use anyhow::Result;
 use sha2::{Digest, Sha256};
+use tracing::warn;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 use turborepo_api_client::APIClient;
 use turborepo_ui::UI;
@@ -15,13 +16,16 @@ use crate::{
 };
 
 pub(crate) mod bin;
+pub(crate) mod boundaries;
 pub(crate) mod daemon;
 pub(crate) mod generate;
 pub(crate) mod info;
 pub(crate) mod link;
+pub(crate) mod lockfile;
 pub(crate) mod login;
 pub(crate) mod logout;
 pub(crate) mod prune;
+pub(crate) mod query;
 pub(crate) mod run;
 pub(crate) mod unlink;
 
@@ -33,6 +37,9 @@ pub struct CommandBase {
     repo_config: OnceCell<RepoConfig>,
     client_config: OnceCell<ClientConfig>,
     args: Args,
+    // The login profile selected by a command, which takes precedence over
+    // TURBO_PROFILE
+    profile: Option<String>,
     version: &'static str,
 }
 
@@ -50,17 +57,37 @@ impl CommandBase {
             repo_config: OnceCell::new(),
             user_config: OnceCell::new(),
             client_config: OnceCell::new(),
+            profile: None,
             version,
         })
     }
 
+    /// Selects the login profile to use. This needs to happen before the
+    /// configs are loaded.
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
     fn repo_config_init(&self) -> Result<RepoConfig, ConfigError> {
         let repo_config_path = get_repo_config_path(self.repo_root.borrow());
 
+        // Only the profile's remote cache settings are needed here, so a user
+        // config that can't be loaded shouldn't stop commands that don't log
+        // in. Commands that do will report the error when they need a token.
+        let profile = match self.user_config() {
+            Ok(user_config) => user_config.profile().cloned(),
+            Err(e) => {
+                warn!("unable to load the login profile, using the repository config: {e}");
+                None
+            }
+        };
+
         RepoConfigLoader::new(repo_config_path)
             .with_api(self.args.api.clone())
             .with_login(self.args.login.clone())
             .with_team_slug(self.args.team.clone())
+            .with_profile(profile)
             .load()
     }
 
@@ -77,6 +104,7 @@ impl CommandBase {
     fn user_config_init(&self) -> Result<UserConfig, ConfigError> {
         UserConfigLoader::new(default_user_config_path()?)
             .with_token(self.args.token.clone())
+            .with_profile(self.profile.clone())
             .load()
     }
 
@@ -118,12 +146,10 @@ impl CommandBase {
 
         let api_url = repo_config.api_url();
         let timeout = client_config.remote_cache_timeout();
-        Ok(APIClient::new(
-            api_url,
-            timeout,
-            self.version,
-            args.preflight,
-        )?)
+        Ok(
+            APIClient::new(api_url, timeout, self.version, args.preflight)?
+                .with_headers(client_config.remote_cache_headers()?)?,
+        )
     }
 
     pub fn daemon_file_root(&self) -> AbsoluteSystemPathBuf {
@@ -1,13 +1,20 @@
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
-use std::sync::OnceLock;
+use std::{collections::HashSet, fs, sync::OnceLock};
 
+use flate2::{write::GzEncoder, Compression};
+use globwalk::WalkType;
 use lazy_static::lazy_static;
 use tracing::trace;
 use turbopath::{
-    AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf, RelativeUnixPath,
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+    RelativeUnixPath,
+};
+use turborepo_lockfiles::{berry_cache_prefixes, berry_package_name};
+use turborepo_repository::{
+    package_json::PackageJson,
+    package_manager::{PackageManager, YarnRc, YARNRC},
 };
-use turborepo_repository::package_json::PackageJson;
 use turborepo_ui::BOLD;
 
 use super::CommandBase;
@@ -26,12 +33,16 @@ pub enum Error {
     Json(#[from] serde_json::Error),
     #[error("path error while pruning: {0}")]
     Path(#[from] turbopath::PathError),
+    #[error("unable to find files to include: {0}")]
+    Walk(#[from] globwalk::WalkError),
     #[error(transparent)]
     PackageJson(#[from] turborepo_repository::package_json::Error),
     #[error(transparent)]
     PackageGraph(#[from] crate::package_graph::Error),
     #[error(transparent)]
     Lockfile(#[from] turborepo_lockfiles::Error),
+    #[error(transparent)]
+    PackageManager(#[from] turborepo_repository::package_manager::Error),
     #[error("turbo doesn't support workspaces at file system root")]
     WorkspaceAtFilesystemRoot,
     #[error("at least one target must be specified")]
@@ -40,10 +51,10 @@ pub enum Error {
     MissingWorkspace(WorkspaceName),
     #[error("Cannot prune without parsed lockfile")]
     MissingLockfile,
-    #[error("Prune is not supported for Bun")]
-    BunUnsupported,
 }
 
+const BUN_TEXT_LOCKFILE: &str = "yarn.lock";
+
 // Files that should be copied from root and if they're required for install
 lazy_static! {
     static ref ADDITIONAL_FILES: Vec<(&'static RelativeUnixPath, Option<CopyDestination>)> = vec![
@@ -53,7 +64,11 @@ lazy_static! {
             Some(CopyDestination::Docker)
         ),
         (
-            RelativeUnixPath::new(".yarnrc.yml").unwrap(),
+            RelativeUnixPath::new(YARNRC).unwrap(),
+            Some(CopyDestination::Docker)
+        ),
+        (
+            RelativeUnixPath::new("bunfig.toml").unwrap(),
             Some(CopyDestination::Docker)
         ),
     ];
@@ -84,20 +99,26 @@ pub fn prune(
     scope: &[String],
     docker: bool,
     output_dir: &str,
+    tarball: bool,
+    include: &[String],
 ) -> Result<(), Error> {
-    let prune = Prune::new(base, scope, docker, output_dir)?;
-
-    if matches!(
-        prune.package_graph.package_manager(),
-        turborepo_repository::package_manager::PackageManager::Bun
-    ) {
-        return Err(Error::BunUnsupported);
-    }
+    let out_directory = AbsoluteSystemPathBuf::from_unknown(&base.repo_root, output_dir);
+    let tarball_path = tarball.then(|| {
+        AbsoluteSystemPathBuf::from_unknown(&base.repo_root, format!("{output_dir}.tar.gz"))
+    });
+    // A tarball gets assembled in a scratch directory that is removed once archived
+    let staging_dir = tarball.then(tempfile::tempdir).transpose()?;
+    let prune_directory = match &staging_dir {
+        Some(staging_dir) => AbsoluteSystemPathBuf::try_from(staging_dir.path())?,
+        None => out_directory.clone(),
+    };
+    let prune = Prune::new(base, scope, docker, prune_directory)?;
 
     println!(
         "Generating pruned monorepo for {} in {}",
         base.ui.apply(BOLD.apply_to(scope.join(", "))),
-        base.ui.apply(BOLD.apply_to(&prune.out_directory)),
+        base.ui
+            .apply(BOLD.apply_to(tarball_path.as_ref().unwrap_or(&out_directory))),
     );
 
     if let Some(workspace_config_path) = prune
@@ -152,7 +173,18 @@ pub fn prune(
         .subgraph(&workspace_paths, &lockfile_keys)?;
 
     let lockfile_contents = lockfile.encode()?;
-    let lockfile_name = prune.package_graph.package_manager().lockfile_name();
+    let lockfile_name = match prune.package_graph.package_manager() {
+        // bun.lockb is a binary format so we write out its text form instead,
+        // `bun install` will create a new bun.lockb from it
+        PackageManager::Bun => {
+            println!(
+                " - Wrote the pruned lockfile as {BUN_TEXT_LOCKFILE}, run `bun install` in the \
+                 pruned output to create bun.lockb from it"
+            );
+            BUN_TEXT_LOCKFILE
+        }
+        package_manager => package_manager.lockfile_name(),
+    };
     let lockfile_path = prune.out_directory.join_component(lockfile_name);
     lockfile_path.create_with_contents(&lockfile_contents)?;
     if prune.docker {
@@ -172,6 +204,14 @@ pub fn prune(
         prune.copy_directory(&path, *required_for_install)?;
     }
 
+    if matches!(prune.package_graph.package_manager(), PackageManager::Berry) {
+        prune.copy_berry_files(&workspace_names, &lockfile_keys)?;
+    }
+
+    if !include.is_empty() {
+        prune.copy_included_files(include, &out_directory)?;
+    }
+
     prune.copy_turbo_json(&workspace_names)?;
 
     let original_patches = prune
@@ -221,6 +261,50 @@ pub fn prune(
         prune.copy_file(package_json(), Some(CopyDestination::Docker))?;
     }
 
+    if let Some(tarball_path) = tarball_path {
+        write_tarball(&prune.out_directory, &tarball_path)?;
+    }
+
+    Ok(())
+}
+
+// Entries are added in a stable order with normalized metadata so pruning the
+// same inputs always produces an identical archive, which keeps Docker layers
+// that `ADD` it cached.
+fn write_tarball(
+    directory: &AbsoluteSystemPath,
+    tarball_path: &AbsoluteSystemPath,
+) -> Result<(), Error> {
+    let mut entries = Vec::new();
+    collect_entries(directory, &mut entries)?;
+    entries.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+
+    tarball_path.ensure_dir()?;
+    let file = fs::File::create(tarball_path)?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    archive.mode(tar::HeaderMode::Deterministic);
+    archive.follow_symlinks(false);
+    for entry in entries {
+        let name = directory.anchor(&entry)?.to_unix();
+        archive.append_path_with_name(&entry, name.as_str())?;
+    }
+    archive.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn collect_entries(
+    directory: &AbsoluteSystemPath,
+    entries: &mut Vec<AbsoluteSystemPathBuf>,
+) -> Result<(), Error> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let path = AbsoluteSystemPathBuf::try_from(entry.path().as_path())?;
+        let is_dir = entry.file_type()?.is_dir();
+        entries.push(path.clone());
+        if is_dir {
+            collect_entries(&path, entries)?;
+        }
+    }
     Ok(())
 }
 
@@ -247,7 +331,7 @@ impl<'a> Prune<'a> {
         base: &CommandBase,
         scope: &'a [String],
         docker: bool,
-        output_dir: &str,
+        out_directory: AbsoluteSystemPathBuf,
     ) -> Result<Self, Error> {
         if scope.is_empty() {
             return Err(Error::NoWorkspaceSpecified);
@@ -258,8 +342,6 @@ impl<'a> Prune<'a> {
 
         let package_graph = PackageGraph::builder(&base.repo_root, root_package_json).build()?;
 
-        let out_directory = AbsoluteSystemPathBuf::from_unknown(&base.repo_root, output_dir);
-
         let full_directory = match docker {
             true => out_directory.join_component("full"),
             false => out_directory.clone(),
@@ -367,6 +449,94 @@ impl<'a> Prune<'a> {
         Ok(())
     }
 
+    // Writes out the parts of berry's install state that depend on which packages
+    // are in the pruned lockfile. `.pnp.cjs` isn't copied as it gets regenerated by
+    // `yarn install`.
+    fn copy_berry_files(
+        &self,
+        workspaces: &[String],
+        lockfile_keys: &[String],
+    ) -> Result<(), Error> {
+        let yarnrc = YarnRc::load(&self.root)?;
+
+        if !yarnrc.package_extensions.is_empty() {
+            let package_names = lockfile_keys
+                .iter()
+                .map(|key| berry_package_name(key))
+                .chain(workspaces.iter().cloned().map(Ok))
+                .collect::<Result<HashSet<_>, _>>()
+                .map_err(turborepo_lockfiles::Error::from)?;
+            let yarnrc_path = AnchoredSystemPath::new(YARNRC)?;
+            let contents = self.root.resolve(yarnrc_path).read()?;
+            if let Some(pruned) = YarnRc::prune_package_extensions(&contents, &package_names)? {
+                self.full_directory
+                    .resolve(yarnrc_path)
+                    .create_with_contents(&pruned)?;
+                if self.docker {
+                    self.docker_directory()
+                        .resolve(yarnrc_path)
+                        .create_with_contents(&pruned)?;
+                }
+            }
+        }
+
+        // Zero-install repositories check their cache in so PnP can load packages
+        // without an install step, we only carry over the archives that are needed.
+        if !yarnrc.is_pnp() || !yarnrc.uses_local_cache() {
+            return Ok(());
+        }
+        let Some(cache_folder) = yarnrc.cache_folder() else {
+            return Ok(());
+        };
+        let cache_path = self
+            .root
+            .resolve(&RelativeUnixPath::new(cache_folder)?.to_anchored_system_path_buf());
+        if !cache_path.try_exists()? {
+            trace!("{cache_path} doesn't exist, skipping copying yarn cache");
+            return Ok(());
+        }
+        let prefixes = berry_cache_prefixes(lockfile_keys.iter().map(|key| key.as_str()))
+            .map_err(turborepo_lockfiles::Error::from)?;
+        for entry in fs::read_dir(&cache_path)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            if !file_name.ends_with(".zip")
+                || !prefixes.iter().any(|prefix| file_name.starts_with(prefix))
+            {
+                continue;
+            }
+            let mut path = RelativeUnixPath::new(cache_folder)?.to_anchored_system_path_buf();
+            path.push(file_name);
+            self.copy_file(&path, Some(CopyDestination::Docker))?;
+        }
+        Ok(())
+    }
+
+    // Copies the files matching the user provided globs, these are treated as
+    // being required for install e.g. `.nvmrc` or config read by postinstall
+    // scripts
+    fn copy_included_files(
+        &self,
+        include: &[String],
+        out_directory: &AbsoluteSystemPath,
+    ) -> Result<(), Error> {
+        let mut exclusions = vec!["**/node_modules/**".to_string()];
+        // Avoid picking up the output of a previous prune
+        if let Ok(out_directory) = self.root.anchor(out_directory) {
+            exclusions.push(format!("{}/**", out_directory.to_unix()));
+        }
+        let files = globwalk::globwalk(&self.root, include, &exclusions, WalkType::Files)?;
+        for file in files {
+            let path = self.root.anchor(&file)?;
+            trace!("including {path}");
+            self.copy_file(&path, Some(CopyDestination::Docker))?;
+        }
+        Ok(())
+    }
+
     fn copy_workspace(&self, package_json_path: &AnchoredSystemPath) -> Result<(), Error> {
         let package_json_path = self.root.resolve(package_json_path);
         let original_dir = package_json_path
@@ -434,3 +604,63 @@ impl<'a> Prune<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn create_tree(root: &AbsoluteSystemPath, files: &[&str]) {
+        for file in files {
+            let path = root
+                .join_unix_path(RelativeUnixPath::new(*file).unwrap())
+                .unwrap();
+            path.ensure_dir().unwrap();
+            path.create_with_contents(file.as_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_tarball_is_reproducible() {
+        let tmp = tempdir().unwrap();
+        let tmp = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let first = tmp.join_component("first");
+        let second = tmp.join_component("second");
+        // Create the files in a different order to change directory iteration order
+        create_tree(
+            &first,
+            &["package.json", "packages/ui/package.json", "yarn.lock"],
+        );
+        create_tree(
+            &second,
+            &["yarn.lock", "packages/ui/package.json", "package.json"],
+        );
+
+        let first_tarball = tmp.join_component("first.tar.gz");
+        let second_tarball = tmp.join_component("second.tar.gz");
+        write_tarball(&first, &first_tarball).unwrap();
+        write_tarball(&second, &second_tarball).unwrap();
+
+        assert_eq!(
+            first_tarball.read().unwrap(),
+            second_tarball.read().unwrap()
+        );
+
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(
+            fs::File::open(&first_tarball).unwrap(),
+        ));
+        let files = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap())
+            .filter(|entry| entry.header().entry_type().is_file())
+            .map(|entry| entry.path().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            vec!["package.json", "packages/ui/package.json", "yarn.lock"]
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::Result;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+use turborepo_repository::{package_json::PackageJson, package_manager::PackageManager};
+use turborepo_scm::SCM;
+
+use crate::{
+    commands::CommandBase, config::TurboJson, package_graph::PackageGraph, query::QueryRunner,
+};
+
+/// Runs `query` against the package and task graphs and prints the result as
+/// JSON. `query` may also be a path to a file containing the query.
+pub fn run(base: &CommandBase, query: &str) -> Result<()> {
+    let query = read_query(&AbsoluteSystemPathBuf::cwd()?, query)?;
+
+    let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
+
+    let package_manager =
+        PackageManager::get_package_manager(&base.repo_root, Some(&root_package_json))?;
+
+    let root_turbo_json = TurboJson::load(&base.repo_root, &root_package_json, false)?;
+
+    let package_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+        .with_package_manger(Some(package_manager))
+        .build()?;
+
+    let scm = SCM::new(&base.repo_root);
+
+    let result = QueryRunner::new(&base.repo_root, &package_graph, &root_turbo_json, &scm)
+        .execute(&query)?;
+    println!("{}", serde_json::to_string_pretty(&result)?);
+
+    Ok(())
+}
+
+// Reads the query from the file `query` points to, if there is one, and
+// otherwise treats it as the query itself
+fn read_query(cwd: &AbsoluteSystemPath, query: &str) -> Result<String> {
+    let path = AbsoluteSystemPathBuf::from_unknown(cwd, query);
+    if path.as_std_path().is_file() {
+        Ok(path.read_to_string()?)
+    } else {
+        Ok(query.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+    use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+    use super::read_query;
+
+    #[test_case("query.graphql" ; "file starting with query")]
+    #[test_case("queries/packages.gql" ; "file in a directory")]
+    fn test_read_query_file(file: &str) {
+        let tmp = tempfile::tempdir().unwrap();
+        let cwd = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let path = AbsoluteSystemPathBuf::from_unknown(&cwd, file);
+        path.ensure_dir().unwrap();
+        path.create_with_contents("{ packages { name } }").unwrap();
+
+        assert_eq!(read_query(&cwd, file).unwrap(), "{ packages { name } }");
+    }
+
+    #[test_case("{ packages { name } }" ; "anonymous query")]
+    #[test_case("query { packages { name } }" ; "named operation")]
+    fn test_read_query_text(query: &str) {
+        let tmp = tempfile::tempdir().unwrap();
+        let cwd = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+
+        assert_eq!(read_query(&cwd, query).unwrap(), query);
+    }
+}
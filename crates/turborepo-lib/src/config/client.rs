@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use anyhow::Result;
 use config::{Config, Environment};
 use serde::{Deserialize, Serialize};
+use turborepo_api_client::OidcTokenSource;
 
 use crate::config::Error;
 
@@ -16,6 +17,9 @@ pub struct ClientConfig {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 struct ClientConfigValue {
     remote_cache_timeout: u64,
+    remote_cache_headers: Option<String>,
+    oidc_token_file: Option<String>,
+    oidc_audience: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +32,31 @@ impl ClientConfig {
     pub fn remote_cache_timeout(&self) -> u64 {
         self.config.remote_cache_timeout
     }
+
+    /// Headers to send with every request to the remote cache, configured as
+    /// one `name: value` pair per line
+    pub fn remote_cache_headers(&self) -> Result<Vec<(&str, &str)>, Error> {
+        self.config
+            .remote_cache_headers
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split_once(':')
+                    .map(|(name, value)| (name.trim(), value.trim()))
+                    .ok_or_else(|| Error::InvalidRemoteCacheHeader(line.to_string()))
+            })
+            .collect()
+    }
+
+    /// Where to get OIDC tokens for the remote cache from, if anywhere
+    pub fn oidc_token_source(&self) -> Option<OidcTokenSource> {
+        OidcTokenSource::new(
+            self.config.oidc_token_file.as_deref(),
+            self.config.oidc_audience.as_deref(),
+        )
+    }
 }
 
 impl ClientConfigLoader {
@@ -129,6 +158,52 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_remote_cache_headers() -> Result<()> {
+        let config = ClientConfigLoader::new()
+            .with_environment({
+                let mut env = HashMap::new();
+                env.insert(
+                    "TURBO_REMOTE_CACHE_HEADERS".into(),
+                    "x-cache-region: eu\n\ncf-access-token:  a:b ".into(),
+                );
+                Some(env)
+            })
+            .load()?;
+        assert_eq!(
+            config.remote_cache_headers()?,
+            vec![("x-cache-region", "eu"), ("cf-access-token", "a:b")]
+        );
+
+        let config = ClientConfigLoader::new()
+            .with_environment({
+                let mut env = HashMap::new();
+                env.insert("TURBO_REMOTE_CACHE_HEADERS".into(), "no-value".into());
+                Some(env)
+            })
+            .load()?;
+        assert!(config.remote_cache_headers().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_oidc_token_file() -> Result<()> {
+        let config = ClientConfigLoader::new()
+            .with_environment({
+                let mut env = HashMap::new();
+                env.insert("TURBO_OIDC_TOKEN_FILE".into(), "/var/run/token".into());
+                Some(env)
+            })
+            .load()?;
+        assert_eq!(
+            config.oidc_token_source(),
+            Some(OidcTokenSource::File("/var/run/token".into()))
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_client_arg_env_variable() -> Result<()> {
         #[derive(Debug)]
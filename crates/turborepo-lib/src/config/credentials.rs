@@ -0,0 +1,210 @@
+//! `credentials`
+//!
+//! Places to keep auth tokens other than the plaintext user config. Tokens
+//! are stored per profile, so a machine can be logged in to more than one
+//! remote cache at a time.
+
+use std::{collections::BTreeMap, fmt::Debug, fs::OpenOptions, io, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+use crate::config::Error;
+
+// The name the profile is stored under when none has been selected
+pub const DEFAULT_PROFILE: &str = "default";
+
+pub trait CredentialStore: Debug + Send + Sync {
+    /// Returns the token stored for `profile`, if there is one
+    fn get(&self, profile: &str) -> Result<Option<String>, Error>;
+
+    fn set(&self, profile: &str, token: &str) -> Result<(), Error>;
+
+    /// Removes the token for `profile`. Removing a token that isn't there
+    /// isn't an error.
+    fn delete(&self, profile: &str) -> Result<(), Error>;
+}
+
+/// Which credential store the user has asked for
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialStoreKind {
+    /// The OS keyring: the Keychain on macOS, the Credential Manager on
+    /// Windows and the Secret Service on Linux
+    Keyring,
+    /// A file only readable by the current user, next to the user config
+    File,
+}
+
+impl CredentialStoreKind {
+    /// Opens the store, keeping any file next to the user config at
+    /// `config_path`
+    pub fn open(self, config_path: &AbsoluteSystemPath) -> Arc<dyn CredentialStore> {
+        let file_store = || -> Arc<dyn CredentialStore> {
+            let path = match config_path.parent() {
+                Some(dir) => dir.join_component("credentials.json"),
+                None => config_path.to_owned(),
+            };
+            Arc::new(FileStore::new(path))
+        };
+        match self {
+            #[cfg(feature = "keyring")]
+            CredentialStoreKind::Keyring => Arc::new(KeyringStore),
+            #[cfg(not(feature = "keyring"))]
+            CredentialStoreKind::Keyring => {
+                tracing::warn!(
+                    "this build of turbo doesn't support the OS keyring, using a file instead"
+                );
+                file_store()
+            }
+            CredentialStoreKind::File => file_store(),
+        }
+    }
+}
+
+/// Keeps tokens in a JSON file that only the current user can read. This is
+/// still plaintext, but it isn't mixed in with settings that get copied
+/// around.
+#[derive(Debug)]
+pub struct FileStore {
+    path: AbsoluteSystemPathBuf,
+}
+
+impl FileStore {
+    pub fn new(path: AbsoluteSystemPathBuf) -> Self {
+        Self { path }
+    }
+
+    fn read(&self) -> Result<BTreeMap<String, String>, Error> {
+        match self.path.read() {
+            Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write(&self, tokens: &BTreeMap<String, String>) -> Result<(), Error> {
+        if let Some(parent_dir) = self.path.parent() {
+            parent_dir.create_dir_all()?;
+        }
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = self.path.open_with_options(options)?;
+        serde_json::to_writer_pretty(&file, tokens)?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+impl CredentialStore for FileStore {
+    fn get(&self, profile: &str) -> Result<Option<String>, Error> {
+        Ok(self.read()?.remove(profile))
+    }
+
+    fn set(&self, profile: &str, token: &str) -> Result<(), Error> {
+        let mut tokens = self.read()?;
+        tokens.insert(profile.to_string(), token.to_string());
+        self.write(&tokens)
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), Error> {
+        let mut tokens = self.read()?;
+        if tokens.remove(profile).is_some() {
+            self.write(&tokens)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "turborepo";
+
+/// Keeps tokens in the OS keyring, with an entry per profile
+#[cfg(feature = "keyring")]
+#[derive(Debug)]
+pub struct KeyringStore;
+
+#[cfg(feature = "keyring")]
+impl KeyringStore {
+    fn entry(profile: &str) -> Result<keyring::Entry, Error> {
+        keyring::Entry::new(KEYRING_SERVICE, profile).map_err(|e| Error::Keyring(e.to_string()))
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl CredentialStore for KeyringStore {
+    fn get(&self, profile: &str) -> Result<Option<String>, Error> {
+        match Self::entry(profile)?.get_password() {
+            Ok(token) => Ok(Some(token)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(Error::Keyring(e.to_string())),
+        }
+    }
+
+    fn set(&self, profile: &str, token: &str) -> Result<(), Error> {
+        Self::entry(profile)?
+            .set_password(token)
+            .map_err(|e| Error::Keyring(e.to_string()))
+    }
+
+    fn delete(&self, profile: &str) -> Result<(), Error> {
+        match Self::entry(profile)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(Error::Keyring(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{CredentialStore, CredentialStoreKind, FileStore};
+
+    #[test]
+    fn test_file_store() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = AbsoluteSystemPathBuf::try_from(dir.path().join("credentials.json"))?;
+        let store = FileStore::new(path.clone());
+
+        assert_eq!(store.get("default")?, None);
+        // deleting from a store that doesn't exist yet is fine
+        store.delete("default")?;
+
+        store.set("default", "personal-token")?;
+        store.set("work", "work-token")?;
+        assert_eq!(store.get("default")?.as_deref(), Some("personal-token"));
+        assert_eq!(store.get("work")?.as_deref(), Some("work-token"));
+
+        store.delete("work")?;
+        assert_eq!(store.get("work")?, None);
+        assert_eq!(store.get("default")?.as_deref(), Some("personal-token"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(path.as_std_path())?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_store_next_to_config() -> Result<()> {
+        let dir = TempDir::new()?;
+        let config_path = AbsoluteSystemPathBuf::try_from(dir.path().join("config.json"))?;
+        let store = CredentialStoreKind::File.open(&config_path);
+        store.set("default", "token")?;
+
+        assert!(dir.path().join("credentials.json").exists());
+        assert!(!config_path.exists());
+        Ok(())
+    }
+}
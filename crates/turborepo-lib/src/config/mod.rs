@@ -1,4 +1,5 @@
 mod client;
+mod credentials;
 mod env;
 mod repo;
 mod turbo;
@@ -21,10 +22,11 @@ pub use repo::{get_repo_config_path, RepoConfig, RepoConfigLoader};
 use serde::Serialize;
 use thiserror::Error;
 pub use turbo::{
-    validate_extends, validate_no_package_task_syntax, RawTurboJSON, SpacesJson, TurboJson,
+    validate_extends, validate_no_package_task_syntax, PackageManagerCheck, RawTurboJSON,
+    SpacesJson, TurboJson,
 };
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
-pub use user::{UserConfig, UserConfigLoader};
+pub use user::{ProfileConfig, UserConfig, UserConfigLoader};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -69,6 +71,19 @@ pub enum Error {
     ExtendFromNonRoot,
     #[error("No \"extends\" key found")]
     NoExtends,
+    #[error("invalid remote cache header \"{0}\", expected \"name: value\"")]
+    InvalidRemoteCacheHeader(String),
+    #[error(
+        "invalid profile name \"{0}\", profile names can only contain lowercase letters, numbers, \
+         \"-\" and \"_\""
+    )]
+    InvalidProfileName(String),
+    #[cfg(feature = "keyring")]
+    #[error(
+        "unable to access the OS keyring: {0}. Set \"credential_store\" to \"file\" to store \
+         credentials in a file instead"
+    )]
+    Keyring(String),
 }
 
 pub fn default_user_config_path() -> Result<AbsoluteSystemPathBuf, Error> {
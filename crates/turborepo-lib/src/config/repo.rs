@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 
 use super::{write_to_disk, MappedEnvironment};
-use crate::config::Error;
+use crate::config::{Error, ProfileConfig};
 
 const DEFAULT_API_URL: &str = "https://vercel.com/api";
 const DEFAULT_LOGIN_URL: &str = "https://vercel.com";
@@ -81,6 +81,7 @@ pub struct RepoConfigLoader {
     api: Option<String>,
     login: Option<String>,
     team_slug: Option<String>,
+    profile: Option<ProfileConfig>,
     environment: Option<HashMap<String, String>>,
 }
 
@@ -196,6 +197,7 @@ impl RepoConfigLoader {
             api: None,
             login: None,
             team_slug: None,
+            profile: None,
             environment: None,
         }
     }
@@ -218,6 +220,14 @@ impl RepoConfigLoader {
         self
     }
 
+    /// Use the remote cache of the selected login profile. Its settings take
+    /// precedence over the config file, but not over environment variables
+    /// or flags.
+    pub fn with_profile(mut self, profile: Option<ProfileConfig>) -> Self {
+        self.profile = profile;
+        self
+    }
+
     #[allow(dead_code)]
     pub fn with_environment(mut self, environment: Option<HashMap<String, String>>) -> Self {
         self.environment = environment;
@@ -231,6 +241,7 @@ impl RepoConfigLoader {
             api,
             login,
             team_slug,
+            profile,
             environment,
         } = self;
         let raw_disk_config = Config::builder()
@@ -241,10 +252,20 @@ impl RepoConfigLoader {
             )
             .build()?;
 
-        let has_team_slug_override = team_slug.is_some();
+        // The team id saved by `turbo link` is for the team in the config
+        // file, so it's ignored when a different team is selected.
+        let has_team_slug_override = team_slug.is_some()
+            || profile
+                .as_ref()
+                .map_or(false, |profile| profile.team_slug.is_some());
+        let profile_config = Config::try_from(&ProfileConfig {
+            token: None,
+            ..profile.unwrap_or_default()
+        })?;
 
         let mut config: RepoConfigValue = Config::builder()
             .add_source(raw_disk_config.clone())
+            .add_source(profile_config)
             .add_source(
                 MappedEnvironment::with_prefix("turbo")
                     .source(environment)
@@ -338,6 +359,38 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_repo_config_with_profile() -> Result<()> {
+        let mut config_file = NamedTempFile::new()?;
+        let config_path = AbsoluteSystemPathBuf::try_from(config_file.path())?;
+        writeln!(
+            &mut config_file,
+            "{{\"apiUrl\": \"http://personal-cache\", \"teamId\": \"123\"}}"
+        )?;
+        let profile = ProfileConfig {
+            api_url: Some("http://work-cache".into()),
+            team_slug: Some("work-team".into()),
+            ..Default::default()
+        };
+
+        let config = RepoConfigLoader::new(config_path.clone())
+            .with_profile(Some(profile.clone()))
+            .load()?;
+        assert_eq!(config.api_url(), "http://work-cache");
+        assert_eq!(config.team_slug(), Some("work-team"));
+        assert_eq!(config.team_id(), None);
+
+        // environment variables still take precedence
+        let env = HashMap::from([("TURBO_API".to_string(), "http://env-cache".to_string())]);
+        let config = RepoConfigLoader::new(config_path)
+            .with_profile(Some(profile))
+            .with_environment(Some(env))
+            .load()?;
+        assert_eq!(config.api_url(), "http://env-cache");
+
+        Ok(())
+    }
+
     #[test]
     fn test_repo_config_includes_defaults() {
         let path = if cfg!(windows) {
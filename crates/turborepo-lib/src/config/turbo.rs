@@ -10,8 +10,10 @@ use turborepo_cache::RemoteCacheOpts;
 use turborepo_repository::package_json::PackageJson;
 
 use crate::{
+    boundaries::BoundariesConfig,
     cli::OutputLogsMode,
     config::Error,
+    package_graph::{PackageGraph, WorkspaceName},
     run::task_id::{TaskId, TaskName, ROOT_PKG_NAME},
     task_graph::{BookkeepingTaskDefinition, Pipeline, TaskDefinitionStable, TaskOutputs},
 };
@@ -27,14 +29,17 @@ pub struct SpacesJson {
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 // The processed TurboJSON ready for use by Turborepo.
 pub struct TurboJson {
+    pub(crate) boundaries: Option<BoundariesConfig>,
     pub(crate) extends: Vec<String>,
     pub(crate) global_deps: Vec<String>,
     pub(crate) global_dot_env: Vec<RelativeUnixPathBuf>,
     pub(crate) global_env: Vec<String>,
     pub(crate) global_pass_through_env: Option<Vec<String>>,
+    pub(crate) package_manager_check: PackageManagerCheck,
     pub(crate) pipeline: Pipeline,
     pub(crate) remote_cache_options: Option<RemoteCacheOpts>,
     pub(crate) space_id: Option<String>,
+    pub(crate) tags: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, PartialEq, Clone)]
@@ -46,6 +51,9 @@ pub struct RawTurboJSON {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub experimental_spaces: Option<SpacesJson>,
+    // Rules for which tagged workspaces may depend on each other
+    #[serde(skip_serializing_if = "Option::is_none")]
+    boundaries: Option<BoundariesConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     extends: Option<Vec<String>>,
     // Global root filesystem dependencies
@@ -58,6 +66,10 @@ pub struct RawTurboJSON {
     // .env files to consider, in order.
     #[serde(skip_serializing_if = "Option::is_none")]
     global_dot_env: Option<Vec<String>>,
+    // What to do when the installed package manager doesn't match the
+    // version in the root package.json's `packageManager` field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package_manager_check: Option<PackageManagerCheck>,
     // Pipeline is a map of Turbo pipeline entries which define the task graph
     // and cache behavior on a per task or per package-task basis.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -65,6 +77,18 @@ pub struct RawTurboJSON {
     // Configuration options when interfacing with the remote cache
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) remote_cache_options: Option<RemoteCacheOpts>,
+    // Tags used to group workspaces, e.g. for filtering
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum PackageManagerCheck {
+    #[default]
+    Off,
+    Warn,
+    Error,
 }
 
 #[derive(Serialize, Deserialize, Default, Debug, PartialEq, Clone)]
@@ -84,6 +108,9 @@ struct RawTaskDefinition {
     env: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     inputs: Option<Vec<String>>,
+    // Load the variables defined in `dotEnv` files into the task's environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    load_dot_env: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pass_through_env: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -92,6 +119,9 @@ struct RawTaskDefinition {
     outputs: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     output_mode: Option<OutputLogsMode>,
+    // Variables whose values are redacted from task logs and run summaries
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret_env: Option<Vec<String>>,
 }
 
 const CONFIG_FILE: &str = "turbo.json";
@@ -253,6 +283,22 @@ impl TryFrom<RawTaskDefinition> for BookkeepingTaskDefinition {
             .transpose()?
             .unwrap_or_default();
 
+        let secret_env = raw_task
+            .secret_env
+            .map(|env| -> Result<Vec<String>, Error> {
+                defined_fields.insert("SecretEnv".to_string());
+                let mut secret_env = HashSet::new();
+                gather_env_vars(env, "secretEnv", &mut secret_env)?;
+                let mut secret_env: Vec<String> = secret_env.into_iter().collect();
+                secret_env.sort();
+                Ok(secret_env)
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        if raw_task.load_dot_env.is_some() {
+            defined_fields.insert("LoadDotEnv".to_string());
+        }
         if raw_task.output_mode.is_some() {
             defined_fields.insert("OutputMode".to_string());
         }
@@ -273,6 +319,8 @@ impl TryFrom<RawTaskDefinition> for BookkeepingTaskDefinition {
                 inputs,
                 pass_through_env,
                 dot_env,
+                load_dot_env: raw_task.load_dot_env.unwrap_or_default(),
+                secret_env,
                 output_mode: raw_task.output_mode.unwrap_or_default(),
                 persistent: raw_task.persistent.unwrap_or_default(),
             },
@@ -378,9 +426,17 @@ impl TryFrom<RawTurboJSON> for TurboJson {
                 .collect::<Result<HashMap<_, _>, Error>>()?,
             // copy these over, we don't need any changes here.
             remote_cache_options: raw_turbo.remote_cache_options,
+            boundaries: raw_turbo.boundaries,
+            package_manager_check: raw_turbo.package_manager_check.unwrap_or_default(),
             extends: raw_turbo.extends.unwrap_or_default(),
             // Directly to space_id, we don't need to keep the struct
             space_id: raw_turbo.experimental_spaces.and_then(|s| s.id),
+            tags: {
+                let mut tags = raw_turbo.tags.unwrap_or_default();
+                tags.sort();
+                tags.dedup();
+                tags
+            },
         })
     }
 }
@@ -498,6 +554,26 @@ impl TurboJson {
         }
     }
 
+    /// Loads the tags declared in the turbo.json of every workspace in the
+    /// package graph. Workspaces without a turbo.json have no tags.
+    pub fn load_workspace_tags(
+        repo_root: &AbsoluteSystemPath,
+        package_graph: &PackageGraph,
+    ) -> Result<HashMap<WorkspaceName, Vec<String>>, Error> {
+        package_graph
+            .workspaces()
+            .map(|(name, info)| {
+                let workspace_dir = repo_root.resolve(info.package_path());
+                let tags = match TurboJson::load(&workspace_dir, &PackageJson::default(), false) {
+                    Ok(turbo_json) => turbo_json.tags,
+                    Err(Error::NoTurboJSON) => Vec::new(),
+                    Err(e) => return Err(e),
+                };
+                Ok((name.clone(), tags))
+            })
+            .collect()
+    }
+
     pub fn validate(&self, validations: &[TurboJSONValidation]) -> Vec<Error> {
         validations
             .iter()
@@ -561,8 +637,12 @@ mod tests {
 
     use super::RawTurboJSON;
     use crate::{
+        boundaries::{BoundariesConfig, TagRule},
         cli::OutputLogsMode,
-        config::{turbo::RawTaskDefinition, TurboJson},
+        config::{
+            turbo::{PackageManagerCheck, RawTaskDefinition},
+            TurboJson,
+        },
         run::task_id::TaskName,
         task_graph::{
             BookkeepingTaskDefinition, TaskDefinitionExperiments, TaskDefinitionStable, TaskOutputs,
@@ -582,6 +662,33 @@ mod tests {
             ..TurboJson::default()
         }
     ; "global dot env (unsorted)")]
+    #[test_case(r#"{ "tags": ["ui", "internal", "ui"] }"#,
+        TurboJson {
+            tags: vec!["internal".to_string(), "ui".to_string()],
+            ..TurboJson::default()
+        }
+    ; "tags (sorted and deduplicated)")]
+    #[test_case(r#"{ "boundaries": { "enforceOnRun": true, "tags": { "ui": { "deny": ["server"] } } } }"#,
+        TurboJson {
+            boundaries: Some(BoundariesConfig {
+                enforce_on_run: true,
+                tags: [(
+                    "ui".to_string(),
+                    TagRule {
+                        deny: vec!["server".to_string()],
+                        ..TagRule::default()
+                    }
+                )].into_iter().collect(),
+            }),
+            ..TurboJson::default()
+        }
+    ; "boundaries")]
+    #[test_case(r#"{ "packageManagerCheck": "error" }"#,
+        TurboJson {
+            package_manager_check: PackageManagerCheck::Error,
+            ..TurboJson::default()
+        }
+    ; "package manager check")]
     #[test_case(r#"{ "globalPassThroughEnv": ["GITHUB_TOKEN", "AWS_SECRET_KEY"] }"#,
         TurboJson {
             global_pass_through_env: Some(vec!["AWS_SECRET_KEY".to_string(), "GITHUB_TOKEN".to_string()]),
@@ -715,8 +822,10 @@ mod tests {
           "outputs": ["package/a/dist"],
           "cache": false,
           "inputs": ["package/a/src/**"],
+          "loadDotEnv": true,
           "outputMode": "full",
-          "persistent": true
+          "persistent": true,
+          "secretEnv": ["DB_PASSWORD", "API_TOKEN"]
         }"#,
         RawTaskDefinition {
             depends_on: Some(vec!["cli#build".to_string()]),
@@ -726,8 +835,10 @@ mod tests {
             outputs: Some(vec!["package/a/dist".to_string()]),
             cache: Some(false),
             inputs: Some(vec!["package/a/src/**".to_string()]),
+            load_dot_env: Some(true),
             output_mode: Some(OutputLogsMode::Full),
             persistent: Some(true),
+            secret_env: Some(vec!["DB_PASSWORD".to_string(), "API_TOKEN".to_string()]),
         },
         BookkeepingTaskDefinition {
             defined_fields: [
@@ -739,12 +850,16 @@ mod tests {
                 "Cache".to_string(),
                 "Persistent".to_string(),
                 "Inputs".to_string(),
-                "DependsOn".to_string()
+                "DependsOn".to_string(),
+                "LoadDotEnv".to_string(),
+                "SecretEnv".to_string()
             ].into_iter().collect(),
             experimental_fields: HashSet::new(),
             experimental: TaskDefinitionExperiments {},
             task_definition: TaskDefinitionStable {
                 dot_env: vec![RelativeUnixPathBuf::new("package/a/.env").unwrap()],
+                load_dot_env: true,
+                secret_env: vec!["API_TOKEN".to_string(), "DB_PASSWORD".to_string()],
                 env: vec!["OS".to_string()],
                 outputs: TaskOutputs {
                     inclusions: vec!["package/a/dist".to_string()],
@@ -1,19 +1,51 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, OnceLock},
+};
 
 use config::{Config, Environment};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 
-use super::write_to_disk;
+use super::{
+    credentials::{CredentialStore, CredentialStoreKind, DEFAULT_PROFILE},
+    write_to_disk,
+};
 use crate::config::Error;
 
 // Inner struct that matches the config file schema
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
 struct UserConfigValue {
     token: Option<String>,
+    // The profile to use when one isn't selected with a flag or TURBO_PROFILE
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<String, ProfileConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    credential_store: Option<CredentialStoreKind>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// The remote cache a named profile logs in to. Anything that isn't set here
+/// comes from the repository config as usual.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
+pub struct ProfileConfig {
+    // Only used when there isn't a credential store configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) token: Option<String>,
+
+    #[serde(rename = "apiurl", skip_serializing_if = "Option::is_none")]
+    pub(crate) api_url: Option<String>,
+
+    #[serde(rename = "loginurl", skip_serializing_if = "Option::is_none")]
+    pub(crate) login_url: Option<String>,
+
+    #[serde(rename = "teamslug", skip_serializing_if = "Option::is_none")]
+    pub(crate) team_slug: Option<String>,
+}
+
+#[derive(Debug, Clone)]
 pub struct UserConfig {
     // The configuration that comes from the disk
     // We keep this as a separate value to avoid saving values that come from
@@ -21,6 +53,12 @@ pub struct UserConfig {
     disk_config: UserConfigValue,
     config: UserConfigValue,
     path: AbsoluteSystemPathBuf,
+    // Where tokens are kept instead of the config file, if the user has
+    // opted into one
+    credential_store: Option<Arc<dyn CredentialStore>>,
+    // The token to use, which is only looked up once a command asks for it
+    // so that commands that don't need one never touch the credential store
+    token: OnceLock<Option<String>>,
 }
 
 /// Configuration options for loading a UserConfig object
@@ -28,13 +66,14 @@ pub struct UserConfig {
 pub struct UserConfigLoader {
     path: AbsoluteSystemPathBuf,
     token: Option<String>,
+    profile: Option<String>,
     environment: Option<HashMap<String, String>>,
 }
 
 impl UserConfig {
     #[allow(dead_code)]
     pub fn token(&self) -> Option<&str> {
-        self.config.token.as_deref()
+        self.token.get_or_init(|| self.saved_token()).as_deref()
     }
 
     /// Returns where a token will be written to.
@@ -42,13 +81,108 @@ impl UserConfig {
         &self.path
     }
 
+    /// The name of the selected profile, or `None` when using the default
+    /// profile
+    pub fn profile_name(&self) -> Option<&str> {
+        self.config
+            .profile
+            .as_deref()
+            .filter(|profile| *profile != DEFAULT_PROFILE)
+    }
+
+    /// The settings for the selected profile, if a named profile is selected
+    /// and has been logged in to
+    pub fn profile(&self) -> Option<&ProfileConfig> {
+        self.config.profiles.get(self.profile_name()?)
+    }
+
     /// Set token and sync the changes to disk
     pub fn set_token(&mut self, token: Option<String>) -> Result<(), Error> {
-        self.disk_config.token = token.clone();
-        self.config.token = token;
+        let profile = self.profile_name().map(|profile| profile.to_string());
+        if let Some(store) = &self.credential_store {
+            let profile = profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+            match &token {
+                Some(token) => store.set(profile, token)?,
+                None => store.delete(profile)?,
+            }
+        }
+
+        // Once there's a credential store we stop keeping a plaintext copy,
+        // which also clears out any token saved before the store was set up.
+        let plaintext_token = if self.credential_store.is_some() {
+            None
+        } else {
+            token.clone()
+        };
+        match profile {
+            Some(profile) => {
+                self.disk_config
+                    .profiles
+                    .entry(profile.clone())
+                    .or_default()
+                    .token = plaintext_token;
+                self.config.profiles.entry(profile).or_default();
+            }
+            None => self.disk_config.token = plaintext_token,
+        }
+        self.token = OnceLock::from(token);
+        self.write_to_disk()
+    }
+
+    /// Records which remote cache the selected profile uses. Only the values
+    /// that are passed are changed, and nothing is recorded for the default
+    /// profile, which keeps using the repository config.
+    pub fn set_profile_remote(
+        &mut self,
+        api_url: Option<String>,
+        login_url: Option<String>,
+        team_slug: Option<String>,
+    ) -> Result<(), Error> {
+        let Some(profile) = self.profile_name().map(|profile| profile.to_string()) else {
+            return Ok(());
+        };
+        for config in [&mut self.disk_config, &mut self.config] {
+            let profile_config = config.profiles.entry(profile.clone()).or_default();
+            if let Some(api_url) = &api_url {
+                profile_config.api_url = Some(api_url.clone());
+            }
+            if let Some(login_url) = &login_url {
+                profile_config.login_url = Some(login_url.clone());
+            }
+            if let Some(team_slug) = &team_slug {
+                profile_config.team_slug = Some(team_slug.clone());
+            }
+        }
         self.write_to_disk()
     }
 
+    // Looks up the token saved for the selected profile. A credential store
+    // that can't be read is treated as not having a token, rather than
+    // failing commands that may not need one.
+    fn saved_token(&self) -> Option<String> {
+        let profile = self.profile_name();
+        let plaintext_token = match profile {
+            Some(profile) => self
+                .disk_config
+                .profiles
+                .get(profile)
+                .and_then(|profile| profile.token.clone()),
+            None => self.disk_config.token.clone(),
+        };
+        let stored_token = match &self.credential_store {
+            Some(store) => store
+                .get(profile.unwrap_or(DEFAULT_PROFILE))
+                .unwrap_or_else(|e| {
+                    warn!("unable to read token from the credential store: {e}");
+                    None
+                }),
+            None => None,
+        };
+        // Tokens saved before the store was set up keep working until the
+        // next login moves them over
+        stored_token.or(plaintext_token)
+    }
+
     fn write_to_disk(&self) -> Result<(), Error> {
         write_to_disk(&self.path, &self.disk_config)
     }
@@ -60,6 +194,7 @@ impl UserConfigLoader {
         Self {
             path: path.into(),
             token: None,
+            profile: None,
             environment: None,
         }
     }
@@ -71,6 +206,13 @@ impl UserConfigLoader {
         self
     }
 
+    /// Set an override for the profile that the user provided via the
+    /// command line
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
     /// Use the given environment map instead of querying the processes
     /// environment
     #[allow(dead_code)]
@@ -84,6 +226,7 @@ impl UserConfigLoader {
         let Self {
             path,
             token,
+            profile,
             environment,
         } = self;
         // We load just the disk config to make sure we don't write a config
@@ -96,20 +239,50 @@ impl UserConfigLoader {
             )
             .build()?;
 
-        let config = Config::builder()
-            .add_source(raw_disk_config.clone())
+        // We also load the overrides on their own, so that we know whether
+        // the token came from one of them or should be looked up for the
+        // selected profile.
+        let raw_overrides = Config::builder()
             .add_source(Environment::with_prefix("TURBO").source(environment.clone()))
             .add_source(Environment::with_prefix("VERCEL_ARTIFACTS").source(environment))
             .set_override_option("token", token)?
+            .set_override_option("profile", profile)?
+            .build()?;
+
+        let config: UserConfigValue = Config::builder()
+            .add_source(raw_disk_config.clone())
+            .add_source(raw_overrides.clone())
             .build()?
             .try_deserialize()?;
 
         let disk_config: UserConfigValue = raw_disk_config.try_deserialize()?;
+        let overrides: UserConfigValue = raw_overrides.try_deserialize()?;
+
+        // Profile names end up as config keys and keyring entries, so we keep
+        // them simple enough to be safe as either.
+        if let Some(profile) = &config.profile {
+            let is_valid = profile
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+            if profile.is_empty() || !is_valid {
+                return Err(Error::InvalidProfileName(profile.clone()));
+            }
+        }
+
+        let credential_store = config.credential_store.map(|kind| kind.open(&path));
+        // A token from a flag or environment variable is used as is, anything
+        // else is looked up for the selected profile when it's needed
+        let token = match overrides.token {
+            Some(token) => OnceLock::from(Some(token)),
+            None => OnceLock::new(),
+        };
 
         Ok(UserConfig {
             disk_config,
             config,
             path,
+            credential_store,
+            token,
         })
     }
 }
@@ -180,4 +353,112 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_named_profile() -> Result<()> {
+        let config_dir = TempDir::new()?;
+        let config_path = AbsoluteSystemPathBuf::try_from(config_dir.path().join("config.json"))?;
+        let loader = UserConfigLoader::new(config_path.clone());
+        loader.clone().load()?.set_token(Some("personal".into()))?;
+
+        let mut work_config = loader.clone().with_profile(Some("work".into())).load()?;
+        assert_eq!(work_config.token(), None);
+        assert_eq!(work_config.profile(), None);
+        work_config.set_token(Some("work-token".into()))?;
+        work_config.set_profile_remote(Some("https://cache.example.com".into()), None, None)?;
+
+        assert_eq!(loader.clone().load()?.token(), Some("personal"));
+
+        let env = HashMap::from([("TURBO_PROFILE".to_string(), "work".to_string())]);
+        let work_config = loader.clone().with_environment(Some(env.clone())).load()?;
+        assert_eq!(work_config.profile_name(), Some("work"));
+        assert_eq!(work_config.token(), Some("work-token"));
+        assert_eq!(
+            work_config.profile().and_then(|p| p.api_url.as_deref()),
+            Some("https://cache.example.com")
+        );
+
+        // an explicit token still wins over the profile's
+        let config = loader
+            .with_environment(Some(env))
+            .with_token(Some("flag-token".into()))
+            .load()?;
+        assert_eq!(config.token(), Some("flag-token"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_profile_is_top_level() -> Result<()> {
+        let mut config_file = NamedTempFile::new()?;
+        writeln!(
+            &mut config_file,
+            "{{\"token\": \"foo\", \"profile\": \"work\"}}"
+        )?;
+        let loader =
+            UserConfigLoader::new(AbsoluteSystemPathBuf::try_from(config_file.path()).unwrap());
+
+        // the profile saved in the config is used when none is selected
+        assert_eq!(loader.clone().load()?.token(), None);
+        let config = loader.with_profile(Some("default".into())).load()?;
+        assert_eq!(config.profile_name(), None);
+        assert_eq!(config.token(), Some("foo"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_credential_store() -> Result<()> {
+        let config_dir = TempDir::new()?;
+        let config_path = config_dir.path().join("config.json");
+        std::fs::write(
+            &config_path,
+            "{\"token\": \"plaintext\", \"credential_store\": \"file\"}",
+        )?;
+        let loader = UserConfigLoader::new(AbsoluteSystemPathBuf::try_from(config_path.clone())?);
+
+        // tokens from before the store was set up are still picked up
+        let mut config = loader.clone().load()?;
+        assert_eq!(config.token(), Some("plaintext"));
+
+        config.set_token(Some("stored".into()))?;
+        let on_disk: serde_json::Value = serde_json::from_slice(&std::fs::read(&config_path)?)?;
+        assert_eq!(on_disk["token"], serde_json::Value::Null);
+        assert_eq!(loader.clone().load()?.token(), Some("stored"));
+
+        let mut work_config = loader.clone().with_profile(Some("work".into())).load()?;
+        work_config.set_token(Some("work-token".into()))?;
+        assert_eq!(loader.clone().load()?.token(), Some("stored"));
+
+        work_config.set_token(None)?;
+        let work_config = loader.with_profile(Some("work".into())).load()?;
+        assert_eq!(work_config.token(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unreadable_credential_store() -> Result<()> {
+        let config_dir = TempDir::new()?;
+        let config_path = config_dir.path().join("config.json");
+        std::fs::write(
+            &config_path,
+            "{\"token\": \"plaintext\", \"credential_store\": \"file\"}",
+        )?;
+        std::fs::write(config_dir.path().join("credentials.json"), "not json")?;
+
+        // loading doesn't read the store, and a store that can't be read
+        // falls back to the plaintext token
+        let config = UserConfigLoader::new(AbsoluteSystemPathBuf::try_from(config_path)?).load()?;
+        assert_eq!(config.token(), Some("plaintext"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_profile_name() -> Result<()> {
+        let config_dir = TempDir::new()?;
+        let config_path = AbsoluteSystemPathBuf::try_from(config_dir.path().join("config.json"))?;
+        let result = UserConfigLoader::new(config_path)
+            .with_profile(Some("Work Profile".into()))
+            .load();
+        assert!(matches!(result, Err(Error::InvalidProfileName(_))));
+        Ok(())
+    }
 }
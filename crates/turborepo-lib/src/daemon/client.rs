@@ -1,9 +1,10 @@
-use std::io;
+use std::{collections::HashMap, io};
 
 use thiserror::Error;
 use tonic::{Code, Status};
 use tracing::info;
-use turbopath::AbsoluteSystemPathBuf;
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeUnixPathBuf};
+use turborepo_scm::GitHashes;
 
 use self::proto::turbod_client::TurbodClient;
 use super::{
@@ -106,6 +107,64 @@ impl DaemonClient<DaemonConnector> {
         Ok(())
     }
 
+    /// Get the raw contents of every workspace package.json, keyed by path
+    /// relative to the repository root.
+    pub async fn discover_packages(
+        &mut self,
+    ) -> Result<HashMap<AnchoredSystemPathBuf, String>, DaemonError> {
+        self.client
+            .discover_packages(proto::DiscoverPackagesRequest {})
+            .await?
+            .into_inner()
+            .package_files
+            .into_iter()
+            .map(|package_file| {
+                let path = AnchoredSystemPathBuf::from_raw(package_file.path)
+                    .map_err(|_| DaemonError::MalformedResponse)?;
+                Ok((path, package_file.contents))
+            })
+            .collect()
+    }
+
+    /// Get the file hashes for each package path and set of inputs, in
+    /// order.
+    pub async fn get_file_hashes(
+        &mut self,
+        packages: &[(AnchoredSystemPathBuf, Vec<String>)],
+    ) -> Result<Vec<GitHashes>, DaemonError> {
+        let hashes = self
+            .client
+            .get_file_hashes(proto::GetFileHashesRequest {
+                packages: packages
+                    .iter()
+                    .map(|(package_path, inputs)| proto::PackageInputs {
+                        package_path: package_path.to_string(),
+                        inputs: inputs.clone(),
+                    })
+                    .collect(),
+            })
+            .await?
+            .into_inner()
+            .hashes;
+        if hashes.len() != packages.len() {
+            return Err(DaemonError::MalformedResponse);
+        }
+        hashes
+            .into_iter()
+            .map(|hashes| {
+                hashes
+                    .file_hashes
+                    .into_iter()
+                    .map(|(path, hash)| {
+                        let path = RelativeUnixPathBuf::new(path)
+                            .map_err(|_| DaemonError::MalformedResponse)?;
+                        Ok((path, hash))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
     /// Get the status of the daemon.
     pub async fn status(&mut self) -> Result<proto::DaemonStatus, DaemonError> {
         self.client
@@ -166,6 +225,12 @@ pub enum DaemonError {
 
     #[error("failed to setup cookie dir {1}: {0}")]
     CookieDir(io::Error, AbsoluteSystemPathBuf),
+
+    #[error("failed to read daemon log file {1}: {0}")]
+    LogFile(io::Error, AbsoluteSystemPathBuf),
+
+    #[error("no daemon log files found for {0}")]
+    NoLogFile(AbsoluteSystemPathBuf),
 }
 
 impl From<Status> for DaemonError {
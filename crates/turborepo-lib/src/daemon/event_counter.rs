@@ -0,0 +1,99 @@
+//! Counts the filesystem events seen by the daemon, both in total and over
+//! recent windows, so that runaway filewatching can be diagnosed.
+
+use std::time::Instant;
+
+/// The longest window, in seconds, that recent counts can be reported for
+pub const MAX_WINDOW_SECS: u64 = 60;
+
+#[derive(Debug)]
+pub struct EventCounter {
+    start: Instant,
+    total: u64,
+    /// per-second counts, indexed by the second since `start` modulo the
+    /// number of buckets. Each bucket records which second it holds so that
+    /// stale buckets can be ignored without needing to clear them.
+    buckets: [(u64, u64); MAX_WINDOW_SECS as usize],
+}
+
+impl EventCounter {
+    pub fn new(start: Instant) -> Self {
+        Self {
+            start,
+            total: 0,
+            buckets: [(0, 0); MAX_WINDOW_SECS as usize],
+        }
+    }
+
+    pub fn record(&mut self, now: Instant, count: u64) {
+        let second = self.second(now);
+        let bucket = &mut self.buckets[(second % MAX_WINDOW_SECS) as usize];
+        if bucket.0 != second {
+            *bucket = (second, 0);
+        }
+        bucket.1 += count;
+        self.total += count;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the number of events seen in the last `window_secs` seconds,
+    /// including the current one.
+    pub fn recent(&self, now: Instant, window_secs: u64) -> u64 {
+        let second = self.second(now);
+        let window_secs = window_secs.min(MAX_WINDOW_SECS);
+        self.buckets
+            .iter()
+            .filter(|(bucket_second, _)| {
+                *bucket_second <= second && second - bucket_second < window_secs
+            })
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    fn second(&self, now: Instant) -> u64 {
+        now.saturating_duration_since(self.start).as_secs()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::EventCounter;
+
+    #[test]
+    fn test_recent_events() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut counter = EventCounter::new(start);
+
+        counter.record(at(0), 3);
+        counter.record(at(5), 2);
+        counter.record(at(5), 1);
+        counter.record(at(30), 4);
+
+        assert_eq!(counter.total(), 10);
+        assert_eq!(counter.recent(at(30), 10), 4);
+        assert_eq!(counter.recent(at(30), 60), 10);
+        assert_eq!(counter.recent(at(64), 60), 7);
+        assert_eq!(counter.recent(at(70), 60), 4);
+        assert_eq!(counter.recent(at(100), 60), 0);
+    }
+
+    #[test]
+    fn test_buckets_are_reused() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut counter = EventCounter::new(start);
+
+        counter.record(at(1), 5);
+        // lands in the same bucket as second 1
+        counter.record(at(61), 2);
+
+        assert_eq!(counter.total(), 7);
+        assert_eq!(counter.recent(at(61), 60), 2);
+    }
+}
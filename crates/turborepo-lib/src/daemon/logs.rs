@@ -0,0 +1,166 @@
+//! Daemon log files
+//!
+//! The daemon logs to a new file each day via `tracing_appender`'s daily
+//! rotation. Files are named after the configured log file with the date
+//! appended, for example `<repo hash>-turbo.log.2023-06-01`, so sorting them
+//! by name also sorts them by age. Only the most recent files are kept so
+//! that long-lived daemons don't grow their logs without limit.
+
+use std::{io, str::FromStr};
+
+use tracing::Level;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+/// The number of daily log files to keep for each repository
+pub const MAX_LOG_FILES: usize = 7;
+
+/// Returns the rotated files for `log_file`, oldest first.
+pub fn log_files(log_file: &AbsoluteSystemPath) -> io::Result<Vec<AbsoluteSystemPathBuf>> {
+    let (Some(folder), Some(file_name)) = (log_file.parent(), log_file.as_path().file_name())
+    else {
+        return Ok(Vec::new());
+    };
+    let prefix = format!("{file_name}.");
+    let entries = match std::fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(|name| name.to_owned()) else {
+            continue;
+        };
+        if name.starts_with(&prefix) && entry.file_type()?.is_file() {
+            files.push(folder.join_component(&name));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the file the daemon is currently logging to, if any.
+pub fn latest_log_file(log_file: &AbsoluteSystemPath) -> io::Result<Option<AbsoluteSystemPathBuf>> {
+    Ok(log_files(log_file)?.pop())
+}
+
+/// Removes all but the `keep` most recent files for `log_file`.
+pub fn prune_log_files(log_file: &AbsoluteSystemPath, keep: usize) -> io::Result<()> {
+    let files = log_files(log_file)?;
+    let stale = files.len().saturating_sub(keep);
+    for file in &files[..stale] {
+        file.remove_file()?;
+    }
+    Ok(())
+}
+
+/// Filters daemon log lines by level. Lines without a level, such as the
+/// continuation of a multi-line message, follow the line before them.
+#[derive(Debug)]
+pub struct LogLevelFilter {
+    max_level: Option<Level>,
+    showing: bool,
+}
+
+impl LogLevelFilter {
+    pub fn new(max_level: Option<Level>) -> Self {
+        Self {
+            max_level,
+            showing: true,
+        }
+    }
+
+    pub fn should_show(&mut self, line: &str) -> bool {
+        let Some(max_level) = self.max_level else {
+            return true;
+        };
+        if let Some(level) = line_level(line) {
+            // more verbose levels compare as greater
+            self.showing = level <= max_level;
+        }
+        self.showing
+    }
+}
+
+// Lines written by the default tracing formatter start with a timestamp
+// followed by the level
+fn line_level(line: &str) -> Option<Level> {
+    let level = line.split_whitespace().nth(1)?;
+    Level::from_str(level).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+    use tracing::Level;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{latest_log_file, log_files, prune_log_files, LogLevelFilter};
+
+    #[test]
+    fn test_prune_log_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let folder = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let log_file = folder.join_component("abc-turbo.log");
+        for name in [
+            "abc-turbo.log.2023-06-03",
+            "abc-turbo.log.2023-06-01",
+            "abc-turbo.log.2023-06-02",
+            "def-turbo.log.2023-06-01",
+        ] {
+            folder
+                .join_component(name)
+                .create_with_contents("")
+                .unwrap();
+        }
+
+        prune_log_files(&log_file, 2).unwrap();
+
+        assert_eq!(
+            log_files(&log_file).unwrap(),
+            vec![
+                folder.join_component("abc-turbo.log.2023-06-02"),
+                folder.join_component("abc-turbo.log.2023-06-03"),
+            ]
+        );
+        assert_eq!(
+            latest_log_file(&log_file).unwrap(),
+            Some(folder.join_component("abc-turbo.log.2023-06-03"))
+        );
+        // other repositories' logs are left alone
+        assert!(folder.join_component("def-turbo.log.2023-06-01").exists());
+    }
+
+    #[test]
+    fn test_missing_log_folder() {
+        let tmp = tempfile::tempdir().unwrap();
+        let log_file = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .join_components(&["logs", "abc-turbo.log"]);
+
+        assert_eq!(latest_log_file(&log_file).unwrap(), None);
+        prune_log_files(&log_file, 2).unwrap();
+    }
+
+    #[test_case(None, &[true, true, true, true] ; "no filter")]
+    #[test_case(Some(Level::WARN), &[false, true, true, false] ; "warn")]
+    #[test_case(Some(Level::TRACE), &[true, true, true, true] ; "trace")]
+    #[test_case(Some(Level::ERROR), &[false, false, false, false] ; "error")]
+    fn test_level_filter(max_level: Option<Level>, expected: &[bool]) {
+        let lines = [
+            "2023-06-01T00:00:00.000000Z  INFO turborepo_lib::daemon::server: filewatching started",
+            "2023-06-01T00:00:01.000000Z  WARN turborepo_lib::daemon::server: Root watcher \
+             triggering shutdown",
+            "  with a continuation line",
+            "2023-06-01T00:00:02.000000Z DEBUG turborepo_lib::daemon::server: exiting",
+        ];
+        let mut filter = LogLevelFilter::new(max_level);
+        let shown = lines
+            .iter()
+            .map(|line| filter.should_show(line))
+            .collect::<Vec<_>>();
+        assert_eq!(shown, expected);
+    }
+}
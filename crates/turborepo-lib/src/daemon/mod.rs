@@ -3,6 +3,9 @@ mod bump_timeout_layer;
 mod client;
 mod connector;
 pub(crate) mod endpoint;
+mod event_counter;
+pub(crate) mod logs;
+mod package_watcher;
 mod server;
 
 pub use client::{DaemonClient, DaemonError};
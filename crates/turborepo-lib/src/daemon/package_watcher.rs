@@ -0,0 +1,505 @@
+//! Package Watcher
+//!
+//! Keeps the results of package discovery and per-package file hashing in
+//! memory so that `turbo run` can skip both when nothing relevant has changed.
+//!
+//! ## Implementation Notes
+//!
+//! Results are computed lazily, the first time they are requested, and are
+//! invalidated by filesystem events:
+//!
+//! - package discovery is invalidated by changes to any `package.json` or
+//!   `pnpm-workspace.yaml`, or to a directory that is, or may now contain, a
+//!   package
+//! - file hashes for a package are invalidated by any change within that
+//!   package, and all file hashes are invalidated by changes to ignore files
+//!
+//! Invalidated results are recomputed in the background shortly after the
+//! filesystem settles, so that the next run finds them warm again. Every
+//! relevant event bumps a generation counter, and a computation that started
+//! before the latest event is returned to its caller but never cached.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use rayon::prelude::*;
+use thiserror::Error;
+use tokio::{
+    sync::{broadcast, oneshot},
+    task::JoinError,
+    time::Instant,
+};
+use tracing::{debug, warn};
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+};
+use turborepo_filewatch::{
+    cookie_jar::{CookieError, CookieJar},
+    Event, NotifyError,
+};
+use turborepo_repository::{
+    package_json::{self, PackageJson},
+    package_manager::{self, PackageManager},
+};
+use turborepo_scm::{GitHashes, SCM};
+
+/// How long the filesystem must be quiet before invalidated results are
+/// recomputed.
+const REFRESH_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Cookie(#[from] CookieError),
+    #[error("failed to read package.json: {0}")]
+    PackageJson(#[from] package_json::Error),
+    #[error("failed to discover packages: {0}")]
+    PackageManager(#[from] package_manager::Error),
+    #[error("failed to read {1}: {0}")]
+    Io(io::Error, AbsoluteSystemPathBuf),
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
+    #[error("failed to hash package files: {0}")]
+    Scm(#[from] turborepo_scm::Error),
+    #[error("package watching task failed: {0}")]
+    Join(#[from] JoinError),
+}
+
+/// A discovered `package.json` and its raw contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageFile {
+    pub path: AnchoredSystemPathBuf,
+    pub contents: String,
+}
+
+/// The inputs used to hash the files of a single package
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PackageInputs {
+    pub package_path: AnchoredSystemPathBuf,
+    pub inputs: Vec<String>,
+}
+
+impl PackageInputs {
+    // Inputs that reach outside of the package can't be invalidated by
+    // watching the package directory, so we never cache them.
+    fn is_cacheable(&self) -> bool {
+        !self.inputs.iter().any(|input| {
+            input
+                .trim_start_matches('!')
+                .split(['/', '\\'])
+                .any(|component| component == "..")
+        })
+    }
+}
+
+pub struct PackageWatcher {
+    cookie_jar: CookieJar,
+    state: Arc<PackageState>,
+    // _exit_ch exists to trigger a close on the receiver when an instance
+    // of this struct is dropped. The task that is receiving events will exit.
+    _exit_ch: oneshot::Sender<()>,
+}
+
+impl PackageWatcher {
+    /// Creates a new package watcher. The cookie jar must not share its
+    /// directory with any other cookie jar. Package discovery starts warming
+    /// immediately.
+    pub fn new(
+        repo_root: &AbsoluteSystemPath,
+        cookie_jar: CookieJar,
+        ignored: Vec<AnchoredSystemPathBuf>,
+        recv: broadcast::Receiver<Result<Event, NotifyError>>,
+    ) -> Self {
+        let (exit_ch, exit_signal) = oneshot::channel();
+        let state = Arc::new(PackageState {
+            repo_root: repo_root.to_owned(),
+            cache: Mutex::new(Cache {
+                rediscover: true,
+                ..Default::default()
+            }),
+        });
+        tokio::task::spawn(
+            PackageTracker {
+                state: state.clone(),
+                ignored,
+                exit_signal,
+                recv,
+            }
+            .watch(),
+        );
+        Self {
+            cookie_jar,
+            state,
+            _exit_ch: exit_ch,
+        }
+    }
+
+    /// Returns every workspace `package.json` in the repository, excluding
+    /// the root.
+    pub async fn discover_packages(&self) -> Result<Arc<Vec<PackageFile>>, Error> {
+        self.cookie_jar.wait_for_cookie().await?;
+        self.state.package_files().await
+    }
+
+    /// Returns the file hashes for each of the given packages, in order.
+    pub async fn get_file_hashes(
+        &self,
+        packages: Vec<PackageInputs>,
+    ) -> Result<Vec<Arc<GitHashes>>, Error> {
+        self.cookie_jar.wait_for_cookie().await?;
+        self.state.file_hashes(packages).await
+    }
+}
+
+struct PackageState {
+    repo_root: AbsoluteSystemPathBuf,
+    cache: Mutex<Cache>,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    /// bumped on every relevant filesystem event
+    generation: u64,
+    package_files: Option<Arc<Vec<PackageFile>>>,
+    file_hashes: HashMap<PackageInputs, Arc<GitHashes>>,
+    /// whether package discovery should be recomputed on the next refresh
+    rediscover: bool,
+    /// invalidated file hashes to recompute on the next refresh
+    stale: HashSet<PackageInputs>,
+}
+
+impl Cache {
+    /// Invalidates any results affected by a change to `path`. Returns
+    /// `true` if a refresh is needed.
+    fn handle_path_change(&mut self, path: &AnchoredSystemPath, is_dir: bool) -> bool {
+        self.generation += 1;
+
+        let file_name = path.as_path().file_name().and_then(|name| name.to_str());
+        let affects_discovery = matches!(file_name, Some("package.json" | "pnpm-workspace.yaml"))
+            || is_dir
+            || self.package_files.as_ref().map_or(false, |files| {
+                files.iter().any(|file| {
+                    file.path
+                        .as_path()
+                        .parent()
+                        .map_or(false, |package_dir| package_dir.starts_with(path.as_path()))
+                })
+            });
+        if affects_discovery && self.package_files.take().is_some() {
+            self.rediscover = true;
+        }
+
+        let is_ignore_file = file_name == Some(".gitignore") || is_git_exclude(path);
+        let stale = &mut self.stale;
+        self.file_hashes.retain(|package, _| {
+            let package_path = package.package_path.as_path();
+            // A change to a parent directory, such as a rename, affects the package too
+            let affected = is_ignore_file
+                || path.as_path().starts_with(package_path)
+                || package_path.starts_with(path.as_path());
+            if affected {
+                stale.insert(package.clone());
+            }
+            !affected
+        });
+
+        self.rediscover || !self.stale.is_empty()
+    }
+
+    /// Drops every result, scheduling all of them to be recomputed.
+    fn flush(&mut self) {
+        self.generation += 1;
+        if self.package_files.take().is_some() {
+            self.rediscover = true;
+        }
+        self.stale
+            .extend(self.file_hashes.drain().map(|(package, _)| package));
+    }
+}
+
+impl PackageState {
+    fn lock(&self) -> MutexGuard<Cache> {
+        self.cache.lock().expect("package cache lock poisoned")
+    }
+
+    async fn package_files(self: &Arc<Self>) -> Result<Arc<Vec<PackageFile>>, Error> {
+        let generation = {
+            let cache = self.lock();
+            if let Some(package_files) = &cache.package_files {
+                return Ok(package_files.clone());
+            }
+            cache.generation
+        };
+
+        // Discovery runs on its own task so that it still populates the cache if
+        // the request that triggered it times out.
+        let state = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let package_files = Arc::new(discover_packages(&state.repo_root)?);
+            let mut cache = state.lock();
+            if cache.generation == generation {
+                cache.package_files = Some(package_files.clone());
+            }
+            Ok(package_files)
+        })
+        .await?
+    }
+
+    async fn file_hashes(
+        self: &Arc<Self>,
+        packages: Vec<PackageInputs>,
+    ) -> Result<Vec<Arc<GitHashes>>, Error> {
+        let (generation, cached) = {
+            let cache = self.lock();
+            let cached = packages
+                .iter()
+                .map(|package| cache.file_hashes.get(package).cloned())
+                .collect::<Vec<_>>();
+            (cache.generation, cached)
+        };
+        if cached.iter().all(Option::is_some) {
+            return Ok(cached.into_iter().flatten().collect());
+        }
+
+        // As with discovery, hashing runs on its own task so that it still
+        // populates the cache if the request that triggered it times out.
+        let state = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let scm = SCM::new(&state.repo_root);
+            let hashes = packages
+                .par_iter()
+                .zip(cached.into_par_iter())
+                .map(|(package, cached)| match cached {
+                    Some(hashes) => Ok(hashes),
+                    None => Ok(Arc::new(scm.get_package_file_hashes(
+                        &state.repo_root,
+                        &package.package_path,
+                        &package.inputs,
+                    )?)),
+                })
+                .collect::<Vec<Result<_, Error>>>();
+
+            let mut cache = state.lock();
+            if cache.generation == generation {
+                for (package, hashes) in packages.into_iter().zip(&hashes) {
+                    let Ok(hashes) = hashes else {
+                        continue;
+                    };
+                    if package.is_cacheable() {
+                        cache.file_hashes.insert(package, hashes.clone());
+                    }
+                }
+            }
+            hashes.into_iter().collect()
+        })
+        .await?
+    }
+
+    /// Recomputes any invalidated results in the background.
+    fn refresh(self: &Arc<Self>) {
+        let (rediscover, stale) = {
+            let mut cache = self.lock();
+            (
+                std::mem::take(&mut cache.rediscover),
+                cache.stale.drain().collect::<Vec<_>>(),
+            )
+        };
+        let state = self.clone();
+        tokio::task::spawn(async move {
+            if rediscover {
+                if let Err(e) = state.package_files().await {
+                    debug!("failed to refresh package discovery: {}", e);
+                }
+            }
+            if !stale.is_empty() {
+                if let Err(e) = state.file_hashes(stale).await {
+                    debug!("failed to refresh package file hashes: {}", e);
+                }
+            }
+        });
+    }
+}
+
+fn is_git_exclude(path: &AnchoredSystemPath) -> bool {
+    path.as_path() == Path::new(".git").join("info").join("exclude")
+}
+
+fn discover_packages(repo_root: &AbsoluteSystemPath) -> Result<Vec<PackageFile>, Error> {
+    let root_package_json = PackageJson::load(&repo_root.join_component("package.json"))?;
+    let package_manager = PackageManager::get_package_manager(repo_root, Some(&root_package_json))?;
+    package_manager
+        .get_package_jsons(repo_root)?
+        .map(|path| {
+            let contents = path
+                .read_to_string()
+                .map_err(|e| Error::Io(e, path.clone()))?;
+            Ok(PackageFile {
+                path: repo_root.anchor(&path)?,
+                contents,
+            })
+        })
+        .collect()
+}
+
+struct PackageTracker {
+    state: Arc<PackageState>,
+    /// paths that never affect packages, such as our own cookie directory
+    ignored: Vec<AnchoredSystemPathBuf>,
+    exit_signal: oneshot::Receiver<()>,
+    recv: broadcast::Receiver<Result<Event, NotifyError>>,
+}
+
+impl PackageTracker {
+    async fn watch(mut self) {
+        // discovery starts out marked for a refresh, so warm it right away
+        let mut refresh_at = Some(Instant::now());
+        loop {
+            let refresh = tokio::time::sleep_until(refresh_at.unwrap_or_else(Instant::now));
+            tokio::select! {
+                _ = &mut self.exit_signal => return,
+                _ = refresh, if refresh_at.is_some() => {
+                    refresh_at = None;
+                    self.state.refresh();
+                }
+                file_event = self.recv.recv() => match file_event {
+                    Err(broadcast::error::RecvError::Closed) => return,
+                    Err(e @ broadcast::error::RecvError::Lagged(_)) => {
+                        warn!("package watching lagged, flushing all packages: {}", e);
+                        self.state.lock().flush();
+                        refresh_at = Some(Instant::now() + REFRESH_DELAY);
+                    }
+                    Ok(Err(e)) => {
+                        warn!("encountered filewatching error, flushing all packages: {}", e);
+                        self.state.lock().flush();
+                        refresh_at = Some(Instant::now() + REFRESH_DELAY);
+                    }
+                    Ok(Ok(event)) => {
+                        if self.handle_event(event) {
+                            refresh_at = Some(Instant::now() + REFRESH_DELAY);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_event(&self, event: Event) -> bool {
+        let mut needs_refresh = false;
+        for path in event.paths {
+            let path = AbsoluteSystemPathBuf::try_from(path)
+                .expect("filewatching should produce absolute paths");
+            let Ok(anchored) = self.state.repo_root.anchor(&path) else {
+                // irrelevant filesystem update
+                continue;
+            };
+            if anchored.as_path().starts_with(".git") && !is_git_exclude(&anchored)
+                || self
+                    .ignored
+                    .iter()
+                    .any(|ignored| anchored.as_path().starts_with(ignored.as_path()))
+            {
+                continue;
+            }
+            let is_dir = path.as_std_path().is_dir();
+            needs_refresh |= self.state.lock().handle_path_change(&anchored, is_dir);
+        }
+        needs_refresh
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use test_case::test_case;
+    use turbopath::{AnchoredSystemPath, AnchoredSystemPathBuf, RelativeUnixPathBuf};
+    use turborepo_scm::GitHashes;
+
+    use super::{Cache, PackageFile, PackageInputs};
+
+    fn package(path: &str, inputs: &[&str]) -> PackageInputs {
+        PackageInputs {
+            package_path: AnchoredSystemPathBuf::from_raw(path).unwrap(),
+            inputs: inputs.iter().map(|input| input.to_string()).collect(),
+        }
+    }
+
+    fn cache() -> Cache {
+        let package_files = ["apps/web", "packages/ui"]
+            .iter()
+            .map(|dir| PackageFile {
+                path: AnchoredSystemPathBuf::from_raw(format!("{dir}/package.json")).unwrap(),
+                contents: "{}".to_string(),
+            })
+            .collect();
+        let hashes = Arc::new(GitHashes::from([(
+            RelativeUnixPathBuf::new("package.json").unwrap(),
+            "abc".to_string(),
+        )]));
+        Cache {
+            package_files: Some(Arc::new(package_files)),
+            file_hashes: [package("apps/web", &[]), package("packages/ui", &[])]
+                .into_iter()
+                .map(|package| (package, hashes.clone()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test_case(&[], true ; "no inputs")]
+    #[test_case(&["src/**"], true ; "package inputs")]
+    #[test_case(&["../shared/**"], false ; "parent inputs")]
+    #[test_case(&["!../shared/**"], false ; "negated parent inputs")]
+    #[test_case(&["src/../../shared"], false ; "nested parent inputs")]
+    fn test_is_cacheable(inputs: &[&str], expected: bool) {
+        assert_eq!(package("apps/web", inputs).is_cacheable(), expected);
+    }
+
+    #[test_case("apps/web/src/index.ts", false, true, &["packages/ui"] ; "source file")]
+    #[test_case("apps/web/package.json", false, false, &["packages/ui"] ; "package json")]
+    #[test_case("pnpm-workspace.yaml", false, false, &["apps/web", "packages/ui"] ; "workspace config")]
+    #[test_case("packages/ui/.gitignore", false, true, &[] ; "ignore file")]
+    #[test_case("packages", true, false, &["apps/web"] ; "package parent directory")]
+    #[test_case("packages/new", true, false, &["apps/web", "packages/ui"] ; "new directory")]
+    #[test_case("README.md", false, true, &["apps/web", "packages/ui"] ; "unrelated file")]
+    fn test_handle_path_change(
+        path: &str,
+        is_dir: bool,
+        keeps_discovery: bool,
+        expected_hashes: &[&str],
+    ) {
+        let mut cache = cache();
+        let needs_refresh =
+            cache.handle_path_change(AnchoredSystemPath::new(path).unwrap(), is_dir);
+
+        assert_eq!(cache.package_files.is_some(), keeps_discovery);
+        assert_eq!(cache.rediscover, !keeps_discovery);
+        let mut remaining = cache
+            .file_hashes
+            .keys()
+            .map(|package| package.package_path.as_str())
+            .collect::<Vec<_>>();
+        remaining.sort();
+        assert_eq!(remaining, expected_hashes);
+        assert_eq!(cache.stale.len(), 2 - expected_hashes.len());
+        assert_eq!(needs_refresh, !keeps_discovery || expected_hashes.len() < 2);
+    }
+
+    #[test]
+    fn test_flush() {
+        let mut cache = cache();
+        let generation = cache.generation;
+        cache.flush();
+
+        assert!(cache.package_files.is_none());
+        assert!(cache.file_hashes.is_empty());
+        assert!(cache.rediscover);
+        assert_eq!(cache.stale.len(), 2);
+        assert!(cache.generation > generation);
+    }
+}
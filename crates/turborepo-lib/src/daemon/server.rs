@@ -22,24 +22,27 @@ use std::{
 };
 
 use futures::Future;
+use sysinfo::{ProcessExt, ProcessRefreshKind, SystemExt};
 use thiserror::Error;
 use tokio::{
     select,
-    sync::{mpsc, oneshot, watch},
+    sync::{broadcast, mpsc, oneshot, watch},
 };
 use tonic::transport::{NamedService, Server};
 use tower::ServiceBuilder;
 use tracing::{error, info, trace, warn};
-use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
 use turborepo_filewatch::{
     cookie_jar::CookieJar,
-    globwatcher::{Error as GlobWatcherError, GlobError, GlobSet, GlobWatcher},
-    FileSystemWatcher, WatchError,
+    globwatcher::{Error as GlobWatcherError, GlobError, GlobSet, GlobWatcher, GlobWatcherStats},
+    Event, FileSystemWatcher, NotifyError, WatchError,
 };
 
 use super::{
     bump_timeout::BumpTimeout,
     endpoint::SocketOpenError,
+    event_counter::EventCounter,
+    package_watcher::{Error as PackageWatcherError, PackageInputs, PackageWatcher},
     proto::{self},
 };
 use crate::{
@@ -61,6 +64,7 @@ pub enum CloseReason {
 struct FileWatching {
     _watcher: FileSystemWatcher,
     glob_watcher: GlobWatcher,
+    package_watcher: PackageWatcher,
 }
 
 #[derive(Debug, Error)]
@@ -73,6 +77,10 @@ enum RpcError {
     GlobWatching(#[from] GlobWatcherError),
     #[error("filewatching unavailable")]
     NoFileWatching,
+    #[error("invalid path: {0}")]
+    InvalidPath(#[from] turbopath::PathError),
+    #[error("package watching failed: {0}")]
+    PackageWatching(#[from] PackageWatcherError),
 }
 
 impl From<RpcError> for tonic::Status {
@@ -84,6 +92,8 @@ impl From<RpcError> for tonic::Status {
             RpcError::InvalidGlob(e) => tonic::Status::invalid_argument(e.to_string()),
             RpcError::GlobWatching(e) => tonic::Status::unavailable(e.to_string()),
             RpcError::NoFileWatching => tonic::Status::unavailable("filewatching unavailable"),
+            RpcError::InvalidPath(e) => tonic::Status::invalid_argument(e.to_string()),
+            RpcError::PackageWatching(e) => tonic::Status::unavailable(e.to_string()),
         }
     }
 }
@@ -92,19 +102,57 @@ async fn start_filewatching(
     repo_root: AbsoluteSystemPathBuf,
     cookie_dir: AbsoluteSystemPathBuf,
     watcher_tx: watch::Sender<Option<Arc<FileWatching>>>,
+    event_counter: Arc<Mutex<EventCounter>>,
 ) -> Result<(), WatchError> {
     let watcher = FileSystemWatcher::new(&repo_root).await?;
+    tokio::task::spawn(count_events(watcher.subscribe(), event_counter));
     let cookie_jar = CookieJar::new(&cookie_dir, Duration::from_millis(100), watcher.subscribe());
     let glob_watcher = GlobWatcher::new(&repo_root, cookie_jar, watcher.subscribe());
+    // Cookie jars number their cookies independently, so each one needs its
+    // own directory.
+    let package_cookie_dir = cookie_dir.join_component("packages");
+    package_cookie_dir
+        .create_dir_all()
+        .map_err(|e| WatchError::Setup(format!("failed to create {package_cookie_dir}: {e}")))?;
+    let package_cookie_jar = CookieJar::new(
+        &package_cookie_dir,
+        Duration::from_millis(100),
+        watcher.subscribe(),
+    );
+    let package_watcher = PackageWatcher::new(
+        &repo_root,
+        package_cookie_jar,
+        repo_root.anchor(&cookie_dir).into_iter().collect(),
+        watcher.subscribe(),
+    );
     // We can ignore failures here, it means the server is shutting down and
     // receivers have gone out of scope.
     let _ = watcher_tx.send(Some(Arc::new(FileWatching {
         _watcher: watcher,
         glob_watcher,
+        package_watcher,
     })));
     Ok(())
 }
 
+async fn count_events(
+    mut recv: broadcast::Receiver<Result<Event, NotifyError>>,
+    event_counter: Arc<Mutex<EventCounter>>,
+) {
+    loop {
+        let count = match recv.recv().await {
+            Ok(_) => 1,
+            // We still want to count the events we missed
+            Err(broadcast::error::RecvError::Lagged(count)) => count,
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        event_counter
+            .lock()
+            .expect("event counter lock poisoned")
+            .record(Instant::now(), count);
+    }
+}
+
 /// Timeout for every RPC the server handles
 const REQUEST_TIMEOUT: Duration = Duration::from_millis(100);
 
@@ -142,8 +190,13 @@ where
     // watch receivers as a group own the filewatcher, which will exit when
     // all references are dropped.
     let fw_shutdown = trigger_shutdown.clone();
+    let start_time = Instant::now();
+    let event_counter = Arc::new(Mutex::new(EventCounter::new(start_time)));
+    let fw_event_counter = event_counter.clone();
     let fw_handle = tokio::task::spawn(async move {
-        if let Err(e) = start_filewatching(watcher_repo_root, cookie_dir, watcher_tx).await {
+        if let Err(e) =
+            start_filewatching(watcher_repo_root, cookie_dir, watcher_tx, fw_event_counter).await
+        {
             error!("filewatching failed to start: {}", e);
             let _ = fw_shutdown.send(()).await;
         }
@@ -180,8 +233,10 @@ where
         shutdown: trigger_shutdown,
         watcher_rx,
         times_saved: Arc::new(Mutex::new(HashMap::new())),
-        start_time: Instant::now(),
+        start_time,
         log_file,
+        repo_root: repo_root.to_owned(),
+        event_counter,
     };
     let server_fut = {
         let service = ServiceBuilder::new()
@@ -225,6 +280,8 @@ struct TurboGrpcService {
     times_saved: Arc<Mutex<HashMap<String, u64>>>,
     start_time: Instant,
     log_file: AbsoluteSystemPathBuf,
+    repo_root: AbsoluteSystemPathBuf,
+    event_counter: Arc<Mutex<EventCounter>>,
 }
 
 impl TurboGrpcService {
@@ -268,6 +325,100 @@ impl TurboGrpcService {
         let changed_globs = fw.glob_watcher.get_changed_globs(hash, candidates).await?;
         Ok((changed_globs, time_saved))
     }
+
+    async fn status(&self) -> proto::DaemonStatus {
+        // Status should be available while filewatching is still starting, so
+        // we don't wait for it here.
+        let fw = self.watcher_rx.borrow().as_ref().cloned();
+        let glob_stats = match &fw {
+            Some(fw) => fw.glob_watcher.stats().await.unwrap_or_else(|e| {
+                warn!("failed to get globwatching stats: {}", e);
+                GlobWatcherStats::default()
+            }),
+            None => GlobWatcherStats::default(),
+        };
+        let time_saved_entries = self
+            .times_saved
+            .lock()
+            .expect("times saved lock poisoned")
+            .len();
+        let (events_total, events_last_10s, events_last_60s) = {
+            let event_counter = self
+                .event_counter
+                .lock()
+                .expect("event counter lock poisoned");
+            let now = Instant::now();
+            (
+                event_counter.total(),
+                event_counter.recent(now, 10),
+                event_counter.recent(now, 60),
+            )
+        };
+
+        proto::DaemonStatus {
+            uptime_msec: self.start_time.elapsed().as_millis() as u64,
+            log_file: self.log_file.to_string(),
+            file_watching: fw.is_some(),
+            watched_roots: fw
+                .is_some()
+                .then(|| self.repo_root.to_string())
+                .into_iter()
+                .collect(),
+            output_hashes: glob_stats.hashes as u64,
+            output_globs: glob_stats.globs as u64,
+            time_saved_entries: time_saved_entries as u64,
+            events_total,
+            events_last_10s,
+            events_last_60s,
+            memory_bytes: memory_usage().unwrap_or_default(),
+        }
+    }
+
+    async fn discover_packages(&self) -> Result<Vec<proto::PackageFile>, RpcError> {
+        let fw = self.wait_for_filewatching().await?;
+        let package_files = fw.package_watcher.discover_packages().await?;
+        Ok(package_files
+            .iter()
+            .map(|package_file| proto::PackageFile {
+                path: package_file.path.to_string(),
+                contents: package_file.contents.clone(),
+            })
+            .collect())
+    }
+
+    async fn get_file_hashes(
+        &self,
+        packages: Vec<proto::PackageInputs>,
+    ) -> Result<Vec<proto::FileHashes>, RpcError> {
+        let packages = packages
+            .into_iter()
+            .map(|package| {
+                Ok(PackageInputs {
+                    package_path: AnchoredSystemPathBuf::from_raw(package.package_path)?,
+                    inputs: package.inputs,
+                })
+            })
+            .collect::<Result<Vec<_>, RpcError>>()?;
+        let fw = self.wait_for_filewatching().await?;
+        let hashes = fw.package_watcher.get_file_hashes(packages).await?;
+        Ok(hashes
+            .iter()
+            .map(|hashes| proto::FileHashes {
+                file_hashes: hashes
+                    .iter()
+                    .map(|(path, hash)| (path.to_string(), hash.clone()))
+                    .collect(),
+            })
+            .collect())
+    }
+}
+
+// Resident memory of the daemon process, in bytes
+fn memory_usage() -> Option<u64> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut system = sysinfo::System::new();
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+    system.process(pid).map(|process| process.memory())
 }
 
 async fn wait_for_filewatching(
@@ -369,10 +520,7 @@ impl proto::turbod_server::Turbod for TurboGrpcService {
         _request: tonic::Request<proto::StatusRequest>,
     ) -> Result<tonic::Response<proto::StatusResponse>, tonic::Status> {
         Ok(tonic::Response::new(proto::StatusResponse {
-            daemon_status: Some(proto::DaemonStatus {
-                uptime_msec: self.start_time.elapsed().as_millis() as u64,
-                log_file: self.log_file.to_string(),
-            }),
+            daemon_status: Some(self.status().await),
         }))
     }
 
@@ -405,6 +553,26 @@ impl proto::turbod_server::Turbod for TurboGrpcService {
             time_saved,
         }))
     }
+
+    async fn discover_packages(
+        &self,
+        _request: tonic::Request<proto::DiscoverPackagesRequest>,
+    ) -> Result<tonic::Response<proto::DiscoverPackagesResponse>, tonic::Status> {
+        let package_files = self.discover_packages().await?;
+        Ok(tonic::Response::new(proto::DiscoverPackagesResponse {
+            package_files,
+        }))
+    }
+
+    async fn get_file_hashes(
+        &self,
+        request: tonic::Request<proto::GetFileHashesRequest>,
+    ) -> Result<tonic::Response<proto::GetFileHashesResponse>, tonic::Status> {
+        let hashes = self.get_file_hashes(request.into_inner().packages).await?;
+        Ok(tonic::Response::new(proto::GetFileHashesResponse {
+            hashes,
+        }))
+    }
 }
 
 impl NamedService for TurboGrpcService {
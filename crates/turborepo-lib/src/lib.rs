@@ -9,6 +9,7 @@
 #![allow(clippy::needless_pass_by_ref_mut)]
 #![allow(dead_code)]
 
+mod boundaries;
 mod child;
 mod cli;
 mod commands;
@@ -20,9 +21,11 @@ mod framework;
 pub(crate) mod globwatcher;
 pub mod graph;
 mod hash;
+mod lockfile_audit;
 mod opts;
 mod package_graph;
 mod process;
+mod query;
 mod rewrite_json;
 mod run;
 mod shim;
@@ -0,0 +1,332 @@
+//! Reports on the external packages each workspace resolves to via the
+//! lockfile: how they changed between two revisions of the lockfile and which
+//! packages are resolved to more than one version.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+};
+
+use serde::Serialize;
+use thiserror::Error;
+use turborepo_lockfiles::Lockfile;
+
+use crate::package_graph::{PackageGraph, WorkspaceName};
+
+/// The versions of each external package a workspace depends on, keyed by
+/// package name
+pub type ResolvedPackages = BTreeMap<String, BTreeSet<String>>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("No lockfile found")]
+    NoLockfile,
+    #[error(transparent)]
+    Lockfile(#[from] turborepo_lockfiles::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum PackageChange {
+    Added {
+        name: String,
+        versions: BTreeSet<String>,
+    },
+    Removed {
+        name: String,
+        versions: BTreeSet<String>,
+    },
+    Changed {
+        name: String,
+        previous: BTreeSet<String>,
+        current: BTreeSet<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Duplicate {
+    pub name: String,
+    // The workspaces that depend on each version of the package
+    pub versions: BTreeMap<String, BTreeSet<WorkspaceName>>,
+}
+
+/// Resolves the transitive external dependencies of every workspace in
+/// `package_graph` using `lockfile`
+pub fn resolved_packages(
+    package_graph: &PackageGraph,
+    lockfile: &dyn Lockfile,
+) -> Result<BTreeMap<WorkspaceName, ResolvedPackages>, Error> {
+    package_graph
+        .workspaces()
+        .map(|(name, info)| {
+            let unresolved_deps = info
+                .unresolved_external_dependencies
+                .iter()
+                .flatten()
+                .map(|(name, version)| (name.to_owned(), version.to_owned()))
+                .collect();
+            let closure = turborepo_lockfiles::named_transitive_closure(
+                lockfile,
+                info.package_path().to_unix().as_str(),
+                unresolved_deps,
+            )?;
+
+            let mut packages = ResolvedPackages::new();
+            for (package, package_name) in closure {
+                packages
+                    .entry(package_name)
+                    .or_default()
+                    .insert(package.version);
+            }
+            Ok((name.clone(), packages))
+        })
+        .collect()
+}
+
+/// Compares the packages each workspace resolves to with `previous` against
+/// the packages it resolves to with the current lockfile. Workspaces without
+/// any changes are omitted.
+pub fn diff(
+    package_graph: &PackageGraph,
+    previous: &dyn Lockfile,
+) -> Result<BTreeMap<WorkspaceName, Vec<PackageChange>>, Error> {
+    let current = package_graph.lockfile().ok_or(Error::NoLockfile)?;
+    let previous = resolved_packages(package_graph, previous)?;
+    let current = resolved_packages(package_graph, current)?;
+
+    Ok(current
+        .into_iter()
+        .filter_map(|(workspace, current)| {
+            let changes = diff_packages(previous.get(&workspace), &current);
+            (!changes.is_empty()).then_some((workspace, changes))
+        })
+        .collect())
+}
+
+fn diff_packages(
+    previous: Option<&ResolvedPackages>,
+    current: &ResolvedPackages,
+) -> Vec<PackageChange> {
+    let empty = ResolvedPackages::new();
+    let previous = previous.unwrap_or(&empty);
+
+    let names = previous
+        .keys()
+        .chain(current.keys())
+        .collect::<BTreeSet<_>>();
+    names
+        .into_iter()
+        .filter_map(|name| match (previous.get(name), current.get(name)) {
+            (None, Some(versions)) => Some(PackageChange::Added {
+                name: name.clone(),
+                versions: versions.clone(),
+            }),
+            (Some(versions), None) => Some(PackageChange::Removed {
+                name: name.clone(),
+                versions: versions.clone(),
+            }),
+            (Some(previous), Some(current)) if previous != current => {
+                Some(PackageChange::Changed {
+                    name: name.clone(),
+                    previous: previous.clone(),
+                    current: current.clone(),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds the packages that the current lockfile resolves to more than one
+/// version, either within a single workspace or across workspaces
+pub fn duplicates(package_graph: &PackageGraph) -> Result<Vec<Duplicate>, Error> {
+    let lockfile = package_graph.lockfile().ok_or(Error::NoLockfile)?;
+
+    let mut packages: HashMap<String, BTreeMap<String, BTreeSet<WorkspaceName>>> = HashMap::new();
+    for (workspace, resolved) in resolved_packages(package_graph, lockfile)? {
+        for (name, versions) in resolved {
+            let entry = packages.entry(name).or_default();
+            for version in versions {
+                entry.entry(version).or_default().insert(workspace.clone());
+            }
+        }
+    }
+
+    let mut duplicates = packages
+        .into_iter()
+        .filter(|(_, versions)| versions.len() > 1)
+        .map(|(name, versions)| Duplicate { name, versions })
+        .collect::<Vec<_>>();
+    duplicates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(duplicates)
+}
+
+impl fmt::Display for PackageChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |versions: &BTreeSet<String>| {
+            versions
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            PackageChange::Added { name, versions } => write!(f, "+ {name} {}", join(versions)),
+            PackageChange::Removed { name, versions } => write!(f, "- {name} {}", join(versions)),
+            PackageChange::Changed {
+                name,
+                previous,
+                current,
+            } => write!(f, "~ {name} {} -> {}", join(previous), join(current)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_repository::{package_json::PackageJson, package_manager::PackageManager};
+
+    use super::*;
+
+    // Resolves `name@range` to the version listed for it, packages have no
+    // dependencies of their own
+    struct MockLockfile {
+        versions: HashMap<(&'static str, &'static str), &'static str>,
+    }
+
+    impl MockLockfile {
+        fn new(versions: &[(&'static str, &'static str, &'static str)]) -> Self {
+            Self {
+                versions: versions
+                    .iter()
+                    .map(|(name, range, version)| ((*name, *range), *version))
+                    .collect(),
+            }
+        }
+    }
+
+    impl Lockfile for MockLockfile {
+        fn resolve_package(
+            &self,
+            _workspace_path: &str,
+            name: &str,
+            version: &str,
+        ) -> Result<Option<turborepo_lockfiles::Package>, turborepo_lockfiles::Error> {
+            Ok(self
+                .versions
+                .iter()
+                .find(|((n, range), _)| *n == name && *range == version)
+                .map(|(_, resolved)| {
+                    turborepo_lockfiles::Package::new(format!("{name}@{resolved}"), *resolved)
+                }))
+        }
+
+        fn all_dependencies(
+            &self,
+            _key: &str,
+        ) -> Result<Option<HashMap<String, String>>, turborepo_lockfiles::Error> {
+            Ok(None)
+        }
+
+        fn subgraph(
+            &self,
+            _workspace_packages: &[String],
+            _packages: &[String],
+        ) -> Result<Box<dyn Lockfile>, turborepo_lockfiles::Error> {
+            unreachable!("lockfile pruning not necessary for audits")
+        }
+
+        fn encode(&self) -> Result<Vec<u8>, turborepo_lockfiles::Error> {
+            unreachable!("lockfile encoding not necessary for audits")
+        }
+
+        fn global_change_key(&self) -> Vec<u8> {
+            Vec::new()
+        }
+    }
+
+    fn package_graph(lockfile: MockLockfile) -> PackageGraph {
+        let root =
+            AbsoluteSystemPathBuf::new(if cfg!(windows) { r"C:\repo" } else { "/repo" }).unwrap();
+        PackageGraph::builder(
+            &root,
+            PackageJson::from_value(json!({ "name": "root" })).unwrap(),
+        )
+        .with_package_manger(Some(PackageManager::Npm))
+        .with_package_jsons(Some(
+            [
+                (
+                    "web",
+                    json!({ "name": "web", "dependencies": { "react": "^18", "lodash": "^4" } }),
+                ),
+                (
+                    "docs",
+                    json!({ "name": "docs", "dependencies": { "react": "^17" } }),
+                ),
+            ]
+            .into_iter()
+            .map(|(dir, package_json)| {
+                (
+                    root.join_components(&[dir, "package.json"]),
+                    PackageJson::from_value(package_json).unwrap(),
+                )
+            })
+            .collect(),
+        ))
+        .with_lockfile(Some(Box::new(lockfile)))
+        .build()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff() {
+        let package_graph = package_graph(MockLockfile::new(&[
+            ("react", "^18", "18.2.0"),
+            ("react", "^17", "17.0.2"),
+            ("lodash", "^4", "4.17.21"),
+        ]));
+        let previous = MockLockfile::new(&[("react", "^18", "18.1.0"), ("react", "^17", "17.0.2")]);
+
+        let changes = diff(&package_graph, &previous).unwrap();
+        assert_eq!(
+            changes
+                .iter()
+                .map(|(workspace, changes)| (
+                    workspace.to_string(),
+                    changes.iter().map(|c| c.to_string()).collect::<Vec<_>>()
+                ))
+                .collect::<Vec<_>>(),
+            vec![(
+                "web".to_string(),
+                vec![
+                    "+ lodash 4.17.21".to_string(),
+                    "~ react 18.1.0 -> 18.2.0".to_string()
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let package_graph = package_graph(MockLockfile::new(&[
+            ("react", "^18", "18.2.0"),
+            ("react", "^17", "17.0.2"),
+            ("lodash", "^4", "4.17.21"),
+        ]));
+
+        assert_eq!(
+            duplicates(&package_graph).unwrap(),
+            vec![Duplicate {
+                name: "react".into(),
+                versions: [
+                    ("17.0.2".to_string(), ["docs".into()].into_iter().collect()),
+                    ("18.2.0".to_string(), ["web".into()].into_iter().collect()),
+                ]
+                .into_iter()
+                .collect(),
+            }]
+        );
+    }
+}
@@ -69,6 +69,7 @@ pub struct RunOpts<'a> {
     pub(crate) dry_run_json: bool,
     pub graph: Option<GraphOpts<'a>>,
     pub(crate) no_daemon: bool,
+    pub(crate) pty: bool,
     pub(crate) single_package: bool,
     pub log_prefix: ResolvedLogPrefix,
     pub log_order: ResolvedLogOrder,
@@ -145,6 +146,7 @@ impl<'a> TryFrom<&'a RunArgs> for RunOpts<'a> {
             pass_through_args: args.pass_through_args.as_ref(),
             only: args.only,
             no_daemon: args.no_daemon,
+            pty: args.pty,
             single_package: args.single_package,
             graph,
             dry_run_json: matches!(args.dry_run, Some(DryRunMode::Json)),
@@ -194,7 +194,6 @@ impl PackageGraph {
     /// a -> b -> c
     ///
     /// immediate_ancestors(c) -> {b}
-    #[allow(dead_code)]
     pub fn immediate_ancestors(
         &self,
         workspace: &WorkspaceNode,
@@ -220,7 +219,6 @@ impl PackageGraph {
     /// a -> b -> c (external)
     ///
     /// dependencies(a) = {b, c}
-    #[allow(dead_code)]
     pub fn dependencies<'a>(&'a self, node: &WorkspaceNode) -> HashSet<&'a WorkspaceNode> {
         let mut dependencies =
             self.transitive_closure_inner(Some(node), petgraph::Direction::Outgoing);
@@ -302,52 +300,55 @@ impl PackageGraph {
     }
 
     /// Returns a list of changed packages based on the contents of a previous
-    /// `Lockfile`. This assumes that none of the package.json in the workspace
-    /// change, it is the responsibility of the caller to verify this.
+    /// `Lockfile`. A workspace is only considered changed if the transitive
+    /// closure of its external dependencies differs between the two lockfiles.
+    /// This assumes that none of the package.json in the workspace change, it
+    /// is the responsibility of the caller to verify this.
     pub fn changed_packages(
         &self,
         previous: &dyn Lockfile,
     ) -> Result<Vec<WorkspaceName>, ChangedPackagesError> {
         let current = self.lockfile().ok_or(ChangedPackagesError::NoLockfile)?;
 
+        // Changes to lockfile-wide settings such as overrides or patches can affect
+        // any package, so we can't be any more precise than "everything changed"
+        if current.global_change_key() != previous.global_change_key() {
+            return Ok(self.workspaces.keys().cloned().collect());
+        }
+
         let external_deps = self
             .workspaces()
-            .filter_map(|(_name, info)| {
-                info.unresolved_external_dependencies.as_ref().map(|dep| {
-                    (
-                        info.package_path().to_unix().to_string(),
-                        dep.iter()
-                            .map(|(name, version)| (name.to_owned(), version.to_owned()))
-                            .collect(),
-                    )
-                })
+            .map(|(_name, info)| {
+                (
+                    info.package_path().to_unix().to_string(),
+                    info.unresolved_external_dependencies
+                        .iter()
+                        .flatten()
+                        .map(|(name, version)| (name.to_owned(), version.to_owned()))
+                        .collect(),
+                )
             })
             .collect::<HashMap<_, HashMap<_, _>>>();
 
-        let closures = turborepo_lockfiles::all_transitive_closures(previous, external_deps)?;
-
-        let current_key = current.global_change_key();
-        let previous_key = previous.global_change_key();
-
-        let global_change = current_key != previous_key;
+        let previous_closures =
+            turborepo_lockfiles::all_transitive_closures(previous, external_deps.clone())?;
+        let current_closures =
+            turborepo_lockfiles::all_transitive_closures(current, external_deps)?;
 
-        let changed = if global_change {
-            None
-        } else {
-            self.workspaces
-                .iter()
-                .filter(|(_name, info)| {
-                    closures.get(info.package_path().as_str())
-                        != info.transitive_dependencies.as_ref()
-                })
-                .map(|(name, _info)| match name {
-                    WorkspaceName::Other(n) => Some(WorkspaceName::Other(n.to_owned())),
-                    // if the root package has changed, then we should report `None`
-                    // since all packages need to be revalidated
-                    WorkspaceName::Root => None,
-                })
-                .collect::<Option<Vec<WorkspaceName>>>()
-        };
+        let changed = self
+            .workspaces
+            .iter()
+            .filter(|(_name, info)| {
+                let path = info.package_path().to_unix();
+                previous_closures.get(path.as_str()) != current_closures.get(path.as_str())
+            })
+            .map(|(name, _info)| match name {
+                WorkspaceName::Other(n) => Some(WorkspaceName::Other(n.to_owned())),
+                // if the root package has changed, then we should report `None`
+                // since all packages need to be revalidated
+                WorkspaceName::Root => None,
+            })
+            .collect::<Option<Vec<WorkspaceName>>>();
 
         Ok(changed.unwrap_or_else(|| self.workspaces.keys().cloned().collect()))
     }
@@ -416,6 +417,7 @@ mod test {
     use std::assert_matches::assert_matches;
 
     use serde_json::json;
+    use test_case::test_case;
     use turbopath::AbsoluteSystemPathBuf;
 
     use super::*;
@@ -496,7 +498,12 @@ mod test {
         assert_eq!(pkg_version, "1.2.3");
     }
 
-    struct MockLockfile {}
+    #[derive(Default)]
+    struct MockLockfile {
+        // Packages resolved to a version other than "1"
+        versions: HashMap<&'static str, &'static str>,
+        global_change_key: Vec<u8>,
+    }
     impl turborepo_lockfiles::Lockfile for MockLockfile {
         fn resolve_package(
             &self,
@@ -505,10 +512,11 @@ mod test {
             _version: &str,
         ) -> std::result::Result<Option<turborepo_lockfiles::Package>, turborepo_lockfiles::Error>
         {
+            let version = self.versions.get(name).copied().unwrap_or("1");
             Ok(match name {
-                "a" => Some(turborepo_lockfiles::Package::new("key:a", "1")),
-                "b" => Some(turborepo_lockfiles::Package::new("key:b", "1")),
-                "c" => Some(turborepo_lockfiles::Package::new("key:c", "1")),
+                "a" => Some(turborepo_lockfiles::Package::new("key:a", version)),
+                "b" => Some(turborepo_lockfiles::Package::new("key:b", version)),
+                "c" => Some(turborepo_lockfiles::Package::new("key:c", version)),
                 _ => None,
             })
         }
@@ -549,7 +557,7 @@ mod test {
         }
 
         fn global_change_key(&self) -> Vec<u8> {
-            unreachable!()
+            self.global_change_key.clone()
         }
     }
 
@@ -586,7 +594,7 @@ mod test {
             );
             map
         }))
-        .with_lockfile(Some(Box::new(MockLockfile {})))
+        .with_lockfile(Some(Box::new(MockLockfile::default())))
         .build()
         .unwrap();
 
@@ -619,6 +627,99 @@ mod test {
         );
     }
 
+    // foo -> a -> c, bar -> b -> c, baz has no external dependencies
+    #[test_case(None, &[], &[] ; "no changes")]
+    #[test_case(None, &[("a", "2")], &["foo"] ; "direct dependency")]
+    #[test_case(None, &[("c", "2")], &["bar", "foo"] ; "shared transitive dependency")]
+    #[test_case(Some("b"), &[("b", "2")], &["//", "bar", "baz", "foo"] ; "root dependency")]
+    fn test_changed_packages(
+        root_dependency: Option<&str>,
+        changes: &[(&'static str, &'static str)],
+        expected: &[&str],
+    ) {
+        let root =
+            AbsoluteSystemPathBuf::new(if cfg!(windows) { r"C:\repo" } else { "/repo" }).unwrap();
+        let root_package_json = match root_dependency {
+            Some(dependency) => json!({ "name": "root", "devDependencies": { dependency: "1" } }),
+            None => json!({ "name": "root" }),
+        };
+        let pkg_graph =
+            PackageGraph::builder(&root, PackageJson::from_value(root_package_json).unwrap())
+                .with_package_manger(Some(PackageManager::Npm))
+                .with_package_jsons(Some(
+                    [
+                        (
+                            "package_a",
+                            json!({ "name": "foo", "dependencies": { "a": "1" } }),
+                        ),
+                        (
+                            "package_b",
+                            json!({ "name": "bar", "dependencies": { "b": "1" } }),
+                        ),
+                        (
+                            "package_c",
+                            json!({ "name": "baz", "dependencies": { "foo": "*" } }),
+                        ),
+                    ]
+                    .into_iter()
+                    .map(|(dir, package_json)| {
+                        (
+                            root.join_components(&[dir, "package.json"]),
+                            PackageJson::from_value(package_json).unwrap(),
+                        )
+                    })
+                    .collect(),
+                ))
+                .with_lockfile(Some(Box::new(MockLockfile {
+                    versions: changes.iter().copied().collect(),
+                    ..Default::default()
+                })))
+                .build()
+                .unwrap();
+
+        let mut changed = pkg_graph
+            .changed_packages(&MockLockfile::default())
+            .unwrap()
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        changed.sort();
+        assert_eq!(changed, expected);
+    }
+
+    #[test]
+    fn test_changed_packages_global_change() {
+        let root =
+            AbsoluteSystemPathBuf::new(if cfg!(windows) { r"C:\repo" } else { "/repo" }).unwrap();
+        let pkg_graph = PackageGraph::builder(
+            &root,
+            PackageJson::from_value(json!({ "name": "root" })).unwrap(),
+        )
+        .with_package_manger(Some(PackageManager::Npm))
+        .with_package_jsons(Some(
+            [(
+                root.join_components(&["package_a", "package.json"]),
+                PackageJson::from_value(json!({ "name": "foo" })).unwrap(),
+            )]
+            .into_iter()
+            .collect(),
+        ))
+        .with_lockfile(Some(Box::new(MockLockfile::default())))
+        .build()
+        .unwrap();
+
+        let previous = MockLockfile {
+            global_change_key: b"overrides".to_vec(),
+            ..Default::default()
+        };
+        let mut changed = pkg_graph.changed_packages(&previous).unwrap();
+        changed.sort();
+        assert_eq!(
+            changed,
+            vec![WorkspaceName::Root, WorkspaceName::from("foo")]
+        );
+    }
+
     #[test]
     fn test_circular_dependency() {
         let root =
@@ -662,7 +763,7 @@ mod test {
             );
             map
         }))
-        .with_lockfile(Some(Box::new(MockLockfile {})))
+        .with_lockfile(Some(Box::new(MockLockfile::default())))
         .build()
         .unwrap();
 
@@ -695,7 +796,7 @@ mod test {
             );
             map
         }))
-        .with_lockfile(Some(Box::new(MockLockfile {})))
+        .with_lockfile(Some(Box::new(MockLockfile::default())))
         .build()
         .unwrap();
 
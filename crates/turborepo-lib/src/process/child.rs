@@ -32,6 +32,8 @@ use tokio::{
 };
 use tracing::{debug, info};
 
+use super::resource_usage::{self, ResourceUsage};
+
 #[derive(Debug)]
 pub enum ChildState {
     Running(ChildCommandChannel),
@@ -61,10 +63,12 @@ pub enum ChildExit {
 #[derive(Clone)]
 pub enum ShutdownStyle {
     /// On windows this will immediately kill, and on posix systems it
-    /// will send a SIGINT. If `Duration` elapses, we then follow up with a
-    /// `Kill`.
+    /// will send a SIGINT to the child's process group. If `Duration`
+    /// elapses, we then follow up with a `Kill`.
     Graceful(Duration),
 
+    /// Kill the child, and on posix systems everything else in its process
+    /// group.
     Kill,
 }
 
@@ -83,7 +87,11 @@ impl ShutdownStyle {
     ///
     /// If an exit channel is provided, the exit code will be sent to the
     /// channel when the child process exits.
-    async fn process(&self, child: &mut tokio::process::Child) -> ChildState {
+    ///
+    /// Signals go to the child's whole process group when it has one, so that
+    /// processes started by wrappers like `npm run` or a shell are stopped
+    /// along with it.
+    async fn process(&self, child: &mut tokio::process::Child, gid: Option<u32>) -> ChildState {
         match self {
             ShutdownStyle::Graceful(timeout) => {
                 // try ro run the command for the given timeout
@@ -93,7 +101,10 @@ impl ShutdownStyle {
                         if let Some(pid) = child.id() {
                             debug!("sending SIGINT to child {}", pid);
                             unsafe {
-                                libc::kill(pid as i32, libc::SIGINT);
+                                match gid {
+                                    Some(gid) => libc::killpg(gid as i32, libc::SIGINT),
+                                    None => libc::kill(pid as i32, libc::SIGINT),
+                                };
                             }
                             debug!("waiting for child {}", pid);
                             child.wait().await.map(|es| es.code())
@@ -111,10 +122,7 @@ impl ShutdownStyle {
                         Ok(Err(_)) => ChildState::Exited(ChildExit::Failed),
                         Err(_) => {
                             info!("graceful shutdown timed out, killing child");
-                            match child.kill().await {
-                                Ok(_) => ChildState::Exited(ChildExit::Killed),
-                                Err(_) => ChildState::Exited(ChildExit::Failed),
-                            }
+                            kill(child, gid).await
                         }
                     }
                 }
@@ -128,14 +136,28 @@ impl ShutdownStyle {
                     }
                 }
             }
-            ShutdownStyle::Kill => match child.kill().await {
-                Ok(_) => ChildState::Exited(ChildExit::Killed),
-                Err(_) => ChildState::Exited(ChildExit::Failed),
-            },
+            ShutdownStyle::Kill => kill(child, gid).await,
         }
     }
 }
 
+async fn kill(child: &mut tokio::process::Child, gid: Option<u32>) -> ChildState {
+    #[cfg(unix)]
+    if let Some(gid) = gid {
+        debug!("killing process group {}", gid);
+        unsafe {
+            libc::killpg(gid as i32, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = gid;
+
+    match child.kill().await {
+        Ok(_) => ChildState::Exited(ChildExit::Killed),
+        Err(_) => ChildState::Exited(ChildExit::Failed),
+    }
+}
+
 /// A child process that can be interacted with asynchronously.
 ///
 /// This is a wrapper around the `tokio::process::Child` struct, which provides
@@ -149,6 +171,11 @@ pub struct Child {
     stdin: Arc<Mutex<Option<tokio::process::ChildStdin>>>,
     stdout: Arc<Mutex<Option<tokio::process::ChildStdout>>>,
     stderr: Arc<Mutex<Option<tokio::process::ChildStderr>>>,
+    // the controlling side of the child's pty, if it was spawned in one
+    pty_output: Arc<Mutex<Option<tokio::fs::File>>>,
+    pty_input: Arc<Mutex<Option<std::fs::File>>>,
+    // set once the child exits, if it could be measured
+    resource_usage: Arc<Mutex<Option<ResourceUsage>>>,
     label: String,
 }
 
@@ -179,22 +206,75 @@ impl Child {
     /// Start a child process, returning a handle that can be used to interact
     /// with it. The command will be started immediately.
     pub fn spawn(mut command: Command, shutdown_style: ShutdownStyle) -> io::Result<Self> {
-        let label = {
-            let cmd = command.as_std();
-            format!(
-                "({}) {} {}",
-                cmd.get_current_dir()
-                    .map(|dir| dir.to_string_lossy())
-                    .unwrap_or_default(),
-                cmd.get_program().to_string_lossy(),
-                cmd.get_args().map(|s| s.to_string_lossy()).join(" ")
-            )
-        };
+        let label = Self::command_label(&command);
 
         let group = command.group().spawn()?;
 
         let gid = group.id();
-        let mut child = group.into_inner();
+        let child = group.into_inner();
+
+        Ok(Self::manage(child, gid, shutdown_style, label, None))
+    }
+
+    /// Start a child process in a pseudo-terminal, so that it sees a terminal
+    /// on its stdin, stdout and stderr. Its combined output is read with
+    /// `pty_output`, and input can be written with `pty_input`.
+    ///
+    /// On platforms without pty support this is the same as `spawn`.
+    pub fn spawn_pty(command: Command, shutdown_style: ShutdownStyle) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            let mut command = command;
+            let label = Self::command_label(&command);
+
+            let pty = super::pty::Pty::open()?;
+            pty.attach(&mut command)?;
+            let child = command.spawn()?;
+            // The command holds on to copies of the terminal side, which all
+            // need to be closed for us to notice the child closing it.
+            drop(command);
+            let controller = pty.into_controller();
+
+            // the pty gives the child its own session, which is also its
+            // process group
+            let gid = child.id();
+            let output = tokio::fs::File::from_std(controller.try_clone()?);
+
+            Ok(Self::manage(
+                child,
+                gid,
+                shutdown_style,
+                label,
+                Some((output, controller)),
+            ))
+        }
+
+        #[cfg(not(unix))]
+        {
+            debug!("pseudo-terminals are not supported on this platform, using pipes");
+            Self::spawn(command, shutdown_style)
+        }
+    }
+
+    fn command_label(command: &Command) -> String {
+        let cmd = command.as_std();
+        format!(
+            "({}) {} {}",
+            cmd.get_current_dir()
+                .map(|dir| dir.to_string_lossy())
+                .unwrap_or_default(),
+            cmd.get_program().to_string_lossy(),
+            cmd.get_args().map(|s| s.to_string_lossy()).join(" ")
+        )
+    }
+
+    fn manage(
+        mut child: tokio::process::Child,
+        gid: Option<u32>,
+        shutdown_style: ShutdownStyle,
+        label: String,
+        pty: Option<(tokio::fs::File, std::fs::File)>,
+    ) -> Self {
         let pid = child.id();
 
         let stdin = child.stdin.take();
@@ -212,6 +292,8 @@ impl Child {
 
         let state = Arc::new(RwLock::new(ChildState::Running(command_tx)));
         let task_state = state.clone();
+        let resource_usage = Arc::new(Mutex::new(None));
+        let task_resource_usage = resource_usage.clone();
 
         let _task = tokio::spawn(async move {
             info!("waiting for task");
@@ -224,12 +306,12 @@ impl Child {
                         // dropped, and the cnannel is not closed while there are still permits
                         Some(ChildCommand::Stop) | None => {
                             debug!("stopping child process");
-                            shutdown_style.process(&mut child).await
+                            shutdown_style.process(&mut child, gid).await
                         }
                         // we received a command to kill the child process
                         Some(ChildCommand::Kill) => {
                             debug!("killing child process");
-                            ShutdownStyle::Kill.process(&mut child).await
+                            ShutdownStyle::Kill.process(&mut child, gid).await
                         }
                     };
 
@@ -248,16 +330,19 @@ impl Child {
                         *task_state = state;
                     }
                 }
-                status = child.wait() => {
+                status = resource_usage::wait(&mut child) => {
                     debug!("child process exited normally");
                     // the child process exited
-                    let child_exit = match status.map(|s| s.code()) {
-                        Ok(Some(c)) => ChildExit::Finished(Some(c)),
+                    let child_exit = match &status {
+                        Ok((Some(c), _)) => ChildExit::Finished(Some(*c)),
                         // if we hit this case, it means that the child process was killed
                         // by someone else, and we should report that it was killed
-                        Ok(None) => ChildExit::KilledExternal,
+                        Ok((None, _)) => ChildExit::KilledExternal,
                         Err(_e) => ChildExit::Failed,
                     };
+                    if let Ok((_, usage)) = status {
+                        *task_resource_usage.lock().unwrap() = usage;
+                    }
                     {
                         let mut task_state = task_state.write().await;
                         *task_state = ChildState::Exited(child_exit);
@@ -272,7 +357,9 @@ impl Child {
             debug!("child process stopped");
         });
 
-        Ok(Self {
+        let (pty_output, pty_input) = pty.unzip();
+
+        Self {
             pid,
             gid,
             state,
@@ -280,8 +367,11 @@ impl Child {
             stdin: Arc::new(Mutex::new(stdin)),
             stdout: Arc::new(Mutex::new(stdout)),
             stderr: Arc::new(Mutex::new(stderr)),
+            pty_output: Arc::new(Mutex::new(pty_output)),
+            pty_input: Arc::new(Mutex::new(pty_input)),
+            resource_usage,
             label,
-        })
+        }
     }
 
     /// Wait for the `Child` to exit, returning the exit code.
@@ -345,10 +435,15 @@ impl Child {
         code
     }
 
-    fn pid(&self) -> Option<u32> {
+    pub fn pid(&self) -> Option<u32> {
         self.pid
     }
 
+    /// The id of the process group the child was started in
+    pub fn gid(&self) -> Option<u32> {
+        self.gid
+    }
+
     pub fn stdin(&mut self) -> Option<tokio::process::ChildStdin> {
         self.stdin.lock().unwrap().take()
     }
@@ -361,8 +456,17 @@ impl Child {
         self.stderr.lock().unwrap().take()
     }
 
+    pub fn pty_output(&mut self) -> Option<tokio::fs::File> {
+        self.pty_output.lock().unwrap().take()
+    }
+
+    pub fn pty_input(&mut self) -> Option<std::fs::File> {
+        self.pty_input.lock().unwrap().take()
+    }
+
     /// Wait for the `Child` to exit and pipe any stdout and stderr to the
-    /// provided writers.
+    /// provided writers. If the child is in a pty, all of its output goes to
+    /// `stdout_pipe`.
     pub async fn wait_with_piped_outputs<W: Write>(
         &mut self,
         stdout_pipe: W,
@@ -378,8 +482,16 @@ impl Child {
             let mut stream = BufReader::new(stream);
             let mut buffer = String::new();
             loop {
+                let read = match stream.read_line(&mut buffer).await {
+                    Ok(read) => read,
+                    #[cfg(unix)]
+                    Err(e) if super::pty::is_closed(&e) => 0,
+                    Err(e) => return Err(e),
+                };
                 // If 0 bytes are read that indicates we've hit EOF
-                if stream.read_line(&mut buffer).await? == 0 {
+                if read == 0 {
+                    // a pty can be closed partway through a line
+                    sink.write_all(buffer.as_bytes())?;
                     break;
                 }
                 sink.write_all(buffer.as_bytes())?;
@@ -388,7 +500,14 @@ impl Child {
             Ok(())
         }
 
-        let stdout_fut = pipe_lines(self.stdout(), stdout_pipe);
+        let pty_output = self.pty_output();
+        let stdout = self.stdout();
+        let stdout_fut = async move {
+            match pty_output {
+                Some(output) => pipe_lines(Some(output), stdout_pipe).await,
+                None => pipe_lines(stdout, stdout_pipe).await,
+            }
+        };
         let stderr_fut = pipe_lines(self.stderr(), stderr_pipe);
 
         let (exit, _stdout, _stderr) =
@@ -397,6 +516,12 @@ impl Child {
         Ok(exit)
     }
 
+    /// The resources used by the child and its descendants, available once
+    /// it has exited by itself.
+    pub fn resource_usage(&self) -> Option<ResourceUsage> {
+        *self.resource_usage.lock().unwrap()
+    }
+
     pub fn label(&self) -> &str {
         &self.label
     }
@@ -656,5 +781,33 @@ mod test {
         assert_eq!(out, b"hello world\n");
         assert!(err.is_empty());
         assert_matches!(exit, Some(ChildExit::Finished(Some(0))));
+
+        #[cfg(unix)]
+        {
+            let usage = child.resource_usage().expect("usage is measured on unix");
+            assert!(usage.max_rss_bytes > 0);
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_wait_with_pty_output() {
+        let script = find_script_dir().join_component("is_tty.js");
+        let mut cmd = Command::new("node");
+        cmd.args([script.as_std_path()]);
+        let mut child = Child::spawn_pty(cmd, ShutdownStyle::Kill).unwrap();
+
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+
+        let exit = child
+            .wait_with_piped_outputs(&mut out, &mut err)
+            .await
+            .unwrap();
+
+        // the terminal translates newlines
+        assert_eq!(out, b"stdout: tty, stderr: tty\r\n");
+        assert!(err.is_empty());
+        assert_matches!(exit, Some(ChildExit::Finished(Some(0))));
     }
 }
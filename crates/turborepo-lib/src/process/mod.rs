@@ -10,6 +10,10 @@
 //! must be either `wait`ed on or `stop`ped to drive state.
 
 mod child;
+mod orphans;
+#[cfg(unix)]
+mod pty;
+mod resource_usage;
 
 use std::{
     io,
@@ -21,8 +25,14 @@ pub use child::Command;
 use futures::Future;
 use tokio::task::JoinSet;
 use tracing::{debug, trace};
+use turbopath::AbsoluteSystemPath;
 
-pub use self::child::{Child, ChildExit};
+use self::orphans::ProcessRecord;
+pub use self::{
+    child::{Child, ChildExit},
+    orphans::reap_orphans,
+    resource_usage::ResourceUsage,
+};
 
 /// A process manager that is responsible for spawning and managing child
 /// processes. When the manager is Open, new child processes can be spawned
@@ -35,6 +45,11 @@ pub struct ProcessManager(Arc<Mutex<ProcessManagerInner>>);
 struct ProcessManagerInner {
     is_closing: bool,
     children: Vec<child::Child>,
+    // Kept behind its own lock so the file isn't written while holding the
+    // manager's. It's always locked before the manager's is released, so
+    // records are updated in the same order children are spawned and
+    // cleared.
+    record: Option<Arc<Mutex<ProcessRecord>>>,
 }
 
 impl ProcessManager {
@@ -42,8 +57,18 @@ impl ProcessManager {
         Self(Arc::new(Mutex::new(ProcessManagerInner {
             is_closing: false,
             children: Vec::new(),
+            record: None,
         })))
     }
+
+    /// Record the process groups of spawned children in `dir`, so that they
+    /// can be cleaned up with `reap_orphans` if we exit without stopping
+    /// them.
+    pub fn with_process_record(self, dir: &AbsoluteSystemPath) -> Self {
+        self.0.lock().expect("not poisoned").record =
+            Some(Arc::new(Mutex::new(ProcessRecord::new(dir))));
+        self
+    }
 }
 
 impl ProcessManager {
@@ -59,15 +84,47 @@ impl ProcessManager {
         &self,
         command: child::Command,
         stop_timeout: Duration,
+    ) -> Option<io::Result<child::Child>> {
+        self.spawn_with(|| {
+            child::Child::spawn(command, child::ShutdownStyle::Graceful(stop_timeout))
+        })
+    }
+
+    /// Spawn a new child process in a pseudo-terminal. See `spawn` for how
+    /// the result should be handled.
+    pub fn spawn_pty(
+        &self,
+        command: child::Command,
+        stop_timeout: Duration,
+    ) -> Option<io::Result<child::Child>> {
+        self.spawn_with(|| {
+            child::Child::spawn_pty(command, child::ShutdownStyle::Graceful(stop_timeout))
+        })
+    }
+
+    fn spawn_with(
+        &self,
+        spawn: impl FnOnce() -> io::Result<child::Child>,
     ) -> Option<io::Result<child::Child>> {
         let mut lock = self.0.lock().unwrap();
         if lock.is_closing {
             return None;
         }
-        let child = child::Child::spawn(command, child::ShutdownStyle::Graceful(stop_timeout));
+        let child = spawn();
         if let Ok(child) = &child {
             lock.children.push(child.clone());
         }
+        let record = lock.record.clone();
+        let mut record = record.as_ref().map(|r| r.lock().expect("not poisoned"));
+        drop(lock);
+
+        if let (Ok(child), Some(record)) = (&child, &mut record) {
+            if let Some(gid) = child.gid() {
+                if let Err(e) = record.add(gid) {
+                    debug!("failed to record spawned process group {}: {}", gid, e);
+                }
+            }
+        }
         Some(child)
     }
 
@@ -114,12 +171,19 @@ impl ProcessManager {
             trace!("process exited: {:?}", out);
         }
 
-        {
-            let mut lock = self.0.lock().expect("not poisoned");
+        let mut lock = self.0.lock().expect("not poisoned");
 
-            // just allocate a new vec rather than clearing the old one
-            lock.children = vec![];
-            lock.is_closing = false;
+        // just allocate a new vec rather than clearing the old one
+        lock.children = vec![];
+        lock.is_closing = false;
+        let record = lock.record.clone();
+        let record = record.as_ref().map(|r| r.lock().expect("not poisoned"));
+        drop(lock);
+
+        if let Some(mut record) = record {
+            if let Err(e) = record.clear() {
+                debug!("failed to clear process record: {}", e);
+            }
         }
     }
 }
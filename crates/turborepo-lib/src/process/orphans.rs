@@ -0,0 +1,310 @@
+//! `orphans`
+//!
+//! Tasks are started in their own process groups, so if turbo exits without
+//! stopping them, say because it crashed or was killed, nothing else will.
+//! To be able to clean up after that, each turbo process records the process
+//! groups of the tasks it spawns in a file named after its own pid. Any
+//! record left behind by a turbo process that is no longer running points at
+//! processes that have been orphaned, which the next invocation of turbo can
+//! then kill.
+
+use std::io;
+
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, PidExt, ProcessExt, ProcessRefreshKind, System, SystemExt};
+use tracing::debug;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    /// When the turbo process that wrote this record started
+    start_time: u64,
+    groups: Vec<SpawnedGroup>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpawnedGroup {
+    gid: u32,
+    /// When the group's leader, whose pid is the group id, started
+    leader_start_time: u64,
+}
+
+/// The record of process groups spawned by this turbo process
+#[derive(Debug)]
+pub struct ProcessRecord {
+    path: AbsoluteSystemPathBuf,
+    record: Record,
+}
+
+impl ProcessRecord {
+    pub fn new(dir: &AbsoluteSystemPath) -> Self {
+        let pid = std::process::id();
+        Self {
+            path: dir.join_component(&format!("{pid}.json")),
+            record: Record {
+                start_time: process_start_time(Pid::from_u32(pid)).unwrap_or_default(),
+                groups: Vec::new(),
+            },
+        }
+    }
+
+    pub fn add(&mut self, gid: u32) -> io::Result<()> {
+        // Without the leader's start time we couldn't tell the group apart
+        // from a later one reusing its id, so there's no point recording it
+        let Some(leader_start_time) = process_start_time(Pid::from_u32(gid)) else {
+            debug!("unable to find the start time of process group {}", gid);
+            return Ok(());
+        };
+        self.record.groups.push(SpawnedGroup {
+            gid,
+            leader_start_time,
+        });
+        let contents = serde_json::to_string(&self.record)?;
+        self.path.ensure_dir()?;
+        self.path.create_with_contents(contents)
+    }
+
+    /// Clears the record once every process we spawned has been stopped.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.record.groups.clear();
+        match self.path.remove_file() {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Kills any process groups left running by turbo processes that have since
+/// exited, returning how many were killed. Records for turbo processes that
+/// are still running are left alone.
+pub fn reap_orphans(dir: &AbsoluteSystemPath) -> io::Result<usize> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut system = System::new();
+
+    let mut killed = 0;
+    for entry in entries {
+        let entry = entry?;
+        let Some(owner) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_suffix(".json"))
+            .and_then(|pid| pid.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let path = dir.join_component(&entry.file_name().to_string_lossy());
+        let record = match path
+            .read_to_string()
+            .map(|contents| serde_json::from_str::<Record>(&contents))
+        {
+            Ok(Ok(record)) => record,
+            Ok(Err(e)) => {
+                debug!("removing unreadable process record {}: {}", path, e);
+                path.remove_file()?;
+                continue;
+            }
+            // the owner may have cleared it in the meantime
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+
+        // A process with the owner's pid that started at the same time is
+        // the owner, anything else means the pid has been reused. If the
+        // owner couldn't find its start time we have to assume it's running.
+        let owner = Pid::from_u32(owner);
+        system.refresh_process_specifics(owner, ProcessRefreshKind::new());
+        let owner_is_running = system.process(owner).map_or(false, |process| {
+            record.start_time == 0 || process.start_time() == record.start_time
+        });
+        if owner_is_running {
+            continue;
+        }
+
+        for group in &record.groups {
+            killed += kill_group(&mut system, group);
+        }
+        path.remove_file()?;
+    }
+    Ok(killed)
+}
+
+// Kills the group if its leader is still running. Once the leader has exited
+// the group id can be reused by unrelated processes, so without the leader we
+// can't know whether the group is still ours and leave it alone.
+#[cfg(unix)]
+fn kill_group(system: &mut System, group: &SpawnedGroup) -> usize {
+    let leader = Pid::from_u32(group.gid);
+    system.refresh_process_specifics(leader, ProcessRefreshKind::new());
+    let Some(process) = system.process(leader) else {
+        return 0;
+    };
+    let gid = group.gid as libc::pid_t;
+    // SAFETY: getpgid has no memory safety requirements
+    let leads_group = unsafe { libc::getpgid(gid) } == gid;
+    if !leads_group || process.start_time() != group.leader_start_time {
+        return 0;
+    }
+    debug!(
+        "killing orphaned process group {} ({})",
+        gid,
+        process.name()
+    );
+    // SAFETY: killpg has no memory safety requirements
+    usize::from(unsafe { libc::killpg(gid, libc::SIGKILL) } == 0)
+}
+
+#[cfg(not(unix))]
+fn kill_group(_system: &mut System, group: &SpawnedGroup) -> usize {
+    debug!(
+        "unable to clean up process group {} on this platform",
+        group.gid
+    );
+    0
+}
+
+fn process_start_time(pid: Pid) -> Option<u64> {
+    let mut system = System::new();
+    system.refresh_process_specifics(pid, ProcessRefreshKind::new());
+    system.process(pid).map(|process| process.start_time())
+}
+
+#[cfg(test)]
+mod test {
+    use std::process::{Child, Command};
+
+    use sysinfo::{Pid, PidExt};
+    use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+    use super::{process_start_time, reap_orphans, ProcessRecord, Record, SpawnedGroup};
+
+    #[cfg(unix)]
+    fn spawn_group() -> Child {
+        use std::os::unix::process::CommandExt;
+        Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap()
+    }
+
+    // Writes a record owned by our pid, but with a start time that can't be
+    // ours, so the owner looks like it has exited
+    fn write_orphaned_record(dir: &AbsoluteSystemPath, groups: Vec<SpawnedGroup>) {
+        let path = dir.join_component(&format!("{}.json", std::process::id()));
+        let record = Record {
+            start_time: 1,
+            groups,
+        };
+        path.create_with_contents(serde_json::to_string(&record).unwrap())
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_record_lifecycle() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .join_component("processes");
+        let mut record = ProcessRecord::new(&dir);
+        let mut child = spawn_group();
+
+        record.add(child.id()).unwrap();
+        let contents = record.path.read_to_string().unwrap();
+        let written: Record = serde_json::from_str(&contents).unwrap();
+        assert_eq!(written.groups.len(), 1);
+        assert_eq!(written.groups[0].gid, child.id());
+        assert_ne!(written.groups[0].leader_start_time, 0);
+
+        // we're still running, so our record is left alone
+        assert_eq!(reap_orphans(&dir).unwrap(), 0);
+        assert!(record.path.exists());
+        assert!(child.try_wait().unwrap().is_none());
+
+        record.clear().unwrap();
+        assert!(!record.path.exists());
+        // clearing is idempotent
+        record.clear().unwrap();
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reap_exited_owner() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let mut child = spawn_group();
+        let leader_start_time = process_start_time(Pid::from_u32(child.id())).unwrap();
+        write_orphaned_record(
+            &dir,
+            vec![SpawnedGroup {
+                gid: child.id(),
+                leader_start_time,
+            }],
+        );
+
+        assert_eq!(reap_orphans(&dir).unwrap(), 1);
+        assert!(std::fs::read_dir(&dir).unwrap().next().is_none());
+        // killed rather than exiting on its own
+        assert!(!child.wait().unwrap().success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reap_reused_group() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let mut child = spawn_group();
+        // the group id is in use, but by a process that isn't the one we
+        // recorded
+        write_orphaned_record(
+            &dir,
+            vec![SpawnedGroup {
+                gid: child.id(),
+                leader_start_time: 1,
+            }],
+        );
+
+        assert_eq!(reap_orphans(&dir).unwrap(), 0);
+        assert!(std::fs::read_dir(&dir).unwrap().next().is_none());
+        assert!(child.try_wait().unwrap().is_none());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_reap_exited_leader() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        write_orphaned_record(
+            &dir,
+            vec![SpawnedGroup {
+                // a group id that won't be in use
+                gid: u32::MAX / 2,
+                leader_start_time: 1,
+            }],
+        );
+
+        assert_eq!(reap_orphans(&dir).unwrap(), 0);
+        assert!(std::fs::read_dir(&dir).unwrap().next().is_none());
+    }
+
+    #[test]
+    fn test_reap_missing_dir() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .join_component("processes");
+        assert_eq!(reap_orphans(&dir).unwrap(), 0);
+    }
+}
@@ -0,0 +1,139 @@
+//! `pty`
+//!
+//! Support for running a child process in a pseudo-terminal. Many tools only
+//! enable colors and interactive features when they detect a terminal, which
+//! they won't find when their output is piped. Giving them the terminal side
+//! of a pty lets them behave as if they were run directly, while turbo reads
+//! their output from, and writes their input to, the controlling side.
+
+use std::{
+    fs::File,
+    io,
+    os::fd::{AsRawFd, FromRawFd},
+    process::Stdio,
+};
+
+use tokio::process::Command;
+
+/// The window size used when turbo isn't attached to a terminal itself
+const DEFAULT_SIZE: (u16, u16) = (24, 80);
+
+#[derive(Debug)]
+pub struct Pty {
+    /// The side turbo reads output from and writes input to
+    pub controller: File,
+    /// The side given to the child as its stdin, stdout and stderr
+    terminal: File,
+}
+
+impl Pty {
+    /// Opens a new pty, sized to match the terminal turbo is running in.
+    pub fn open() -> io::Result<Self> {
+        let (rows, cols) = console::Term::stdout()
+            .size_checked()
+            .unwrap_or(DEFAULT_SIZE);
+        let mut size = libc::winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let mut controller = 0;
+        let mut terminal = 0;
+        // SAFETY: openpty only writes to the pointers we give it, and on
+        // success hands us ownership of the two descriptors it opened.
+        unsafe {
+            if libc::openpty(
+                &mut controller,
+                &mut terminal,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut size,
+            ) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        // SAFETY: openpty succeeded, so we own both descriptors
+        let pty = unsafe {
+            Self {
+                controller: File::from_raw_fd(controller),
+                terminal: File::from_raw_fd(terminal),
+            }
+        };
+        // openpty doesn't set close-on-exec, so without this every other
+        // task spawned meanwhile would inherit both sides, and our controller
+        // wouldn't see the terminal close until all of them had exited. The
+        // child gets the terminal side through its standard streams, which
+        // aren't affected.
+        set_cloexec(&pty.controller)?;
+        set_cloexec(&pty.terminal)?;
+        Ok(pty)
+    }
+
+    /// Closes our copy of the terminal side once the child has been spawned,
+    /// leaving the controller.
+    pub fn into_controller(self) -> File {
+        self.controller
+    }
+
+    /// Connects the standard streams of `command` to the terminal side and
+    /// makes it the command's controlling terminal.
+    ///
+    /// Note that this starts the command in a new session, which also puts
+    /// it in its own process group.
+    pub fn attach(&self, command: &mut Command) -> io::Result<()> {
+        command.stdin(Stdio::from(self.terminal.try_clone()?));
+        command.stdout(Stdio::from(self.terminal.try_clone()?));
+        command.stderr(Stdio::from(self.terminal.try_clone()?));
+        // SAFETY: only async-signal-safe functions are called between fork
+        // and exec
+        unsafe {
+            command.pre_exec(|| {
+                // only a session leader can take a controlling terminal
+                if libc::setsid() == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+}
+
+fn set_cloexec(file: &File) -> io::Result<()> {
+    let fd = file.as_raw_fd();
+    // SAFETY: fd is a valid descriptor owned by `file`
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFD);
+        if flags == -1 || libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Once every process has closed the terminal side, reading from the
+/// controller fails with `EIO` rather than reporting EOF.
+pub fn is_closed(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(libc::EIO)
+}
+
+#[cfg(test)]
+mod test {
+    use std::os::fd::AsRawFd;
+
+    use super::Pty;
+
+    #[test]
+    fn test_fds_not_inherited() {
+        let pty = Pty::open().unwrap();
+        for file in [&pty.controller, &pty.terminal] {
+            let flags = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETFD) };
+            assert_ne!(flags & libc::FD_CLOEXEC, 0);
+        }
+    }
+}
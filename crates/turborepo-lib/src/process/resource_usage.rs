@@ -0,0 +1,103 @@
+//! `resource_usage`
+//!
+//! Measures the resources used by a child process along with everything it
+//! ran. On unix we reap the child ourselves with `wait4`, which reports the
+//! usage of the child and of all the descendants that were waited for, so
+//! the processes started by `npm run` or a shell are included.
+
+use std::io;
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    /// The peak resident set size of the largest process, in bytes
+    pub max_rss_bytes: u64,
+    /// Time spent executing in user mode, in milliseconds
+    pub user_time_ms: u64,
+    /// Time spent executing in the kernel, in milliseconds
+    pub system_time_ms: u64,
+    /// Bytes read from storage. Reads served from the page cache aren't
+    /// counted.
+    pub read_bytes: u64,
+    /// Bytes written to storage
+    pub written_bytes: u64,
+}
+
+#[cfg(unix)]
+impl ResourceUsage {
+    fn from_rusage(usage: &libc::rusage) -> Self {
+        // Linux reports the peak rss in kilobytes, macOS in bytes
+        const RSS_UNIT: u64 = if cfg!(target_os = "macos") { 1 } else { 1024 };
+        // block operations are counted in units of 512 bytes
+        const BLOCK_SIZE: u64 = 512;
+        let millis = |time: libc::timeval| time.tv_sec as u64 * 1000 + time.tv_usec as u64 / 1000;
+        Self {
+            max_rss_bytes: usage.ru_maxrss as u64 * RSS_UNIT,
+            user_time_ms: millis(usage.ru_utime),
+            system_time_ms: millis(usage.ru_stime),
+            read_bytes: usage.ru_inblock as u64 * BLOCK_SIZE,
+            written_bytes: usage.ru_oublock as u64 * BLOCK_SIZE,
+        }
+    }
+}
+
+/// Waits for `child` to exit, returning its exit code along with its resource
+/// usage where that can be measured.
+pub async fn wait(
+    child: &mut tokio::process::Child,
+) -> io::Result<(Option<i32>, Option<ResourceUsage>)> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        if let Some((code, usage)) = wait4(pid).await {
+            return Ok((code, Some(usage)));
+        }
+    }
+    child.wait().await.map(|status| (status.code(), None))
+}
+
+/// Reaps `pid` with `wait4` once it exits. Returns `None` if the child was
+/// reaped elsewhere, in which case tokio has its exit status.
+#[cfg(unix)]
+async fn wait4(pid: u32) -> Option<(Option<i32>, ResourceUsage)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    // Blocking until the child exits without reaping it means that if this
+    // future is dropped, the child is still there for tokio to reap.
+    let exited = tokio::task::spawn_blocking(move || loop {
+        // SAFETY: siginfo_t is plain data that waitid fills in
+        let mut info = unsafe { std::mem::zeroed::<libc::siginfo_t>() };
+        let result = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        if result == 0 {
+            return true;
+        }
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            return false;
+        }
+    })
+    .await
+    .unwrap_or(false);
+    if !exited {
+        return None;
+    }
+
+    let mut status = 0;
+    // SAFETY: rusage is plain data that wait4 fills in
+    let mut usage = unsafe { std::mem::zeroed::<libc::rusage>() };
+    let reaped = unsafe { libc::wait4(pid as libc::pid_t, &mut status, libc::WNOHANG, &mut usage) };
+    if reaped != pid as libc::pid_t {
+        return None;
+    }
+    Some((
+        std::process::ExitStatus::from_raw(status).code(),
+        ResourceUsage::from_rusage(&usage),
+    ))
+}
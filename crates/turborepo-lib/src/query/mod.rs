@@ -0,0 +1,599 @@
+//! `turbo query` exposes the package and task graphs through a small,
+//! GraphQL-like query language so that tooling doesn't need to re-implement
+//! workspace discovery.
+//!
+//! ```graphql
+//! query {
+//!   packages(filter: "...[main]") {
+//!     name
+//!     path
+//!     dependencies { name }
+//!   }
+//!   tasks(names: ["build"]) {
+//!     id
+//!     dependencies { id }
+//!   }
+//! }
+//! ```
+
+mod parser;
+
+use std::{
+    cell::OnceCell,
+    collections::{BTreeSet, HashMap},
+};
+
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+use turbopath::AbsoluteSystemPath;
+use turborepo_scm::SCM;
+
+use self::parser::Field;
+use crate::{
+    config::TurboJson,
+    engine::{Engine, EngineBuilder, TaskNode},
+    opts::ScopeOpts,
+    package_graph::{PackageGraph, WorkspaceName, WorkspaceNode},
+    run::{scope, task_id::TaskName},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("syntax error at position {position}: {message}")]
+    Syntax { position: usize, message: String },
+    #[error("unknown field \"{field}\" on type \"{type_name}\"")]
+    UnknownField {
+        type_name: &'static str,
+        field: String,
+    },
+    #[error("field \"{field}\" of type \"{type_name}\" must have a selection of subfields")]
+    MissingSelection {
+        type_name: &'static str,
+        field: String,
+    },
+    #[error("missing required argument \"{argument}\" on field \"{field}\"")]
+    MissingArgument {
+        field: String,
+        argument: &'static str,
+    },
+    #[error("argument \"{argument}\" on field \"{field}\" must be {expected}")]
+    InvalidArgument {
+        field: String,
+        argument: String,
+        expected: &'static str,
+    },
+    #[error("package \"{0}\" not found")]
+    PackageNotFound(String),
+    #[error("unable to resolve packages: {0}")]
+    Filter(#[source] anyhow::Error),
+    #[error("unable to build task graph: {0}")]
+    Engine(#[source] anyhow::Error),
+    #[error("unable to serialize {0}: {1}")]
+    Serialize(&'static str, #[source] serde_json::Error),
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+}
+
+/// Resolves queries against a single snapshot of the repository
+pub struct QueryRunner<'a> {
+    repo_root: &'a AbsoluteSystemPath,
+    package_graph: &'a PackageGraph,
+    root_turbo_json: &'a TurboJson,
+    scm: &'a SCM,
+    workspace_tags: OnceCell<HashMap<WorkspaceName, Vec<String>>>,
+}
+
+impl<'a> QueryRunner<'a> {
+    pub fn new(
+        repo_root: &'a AbsoluteSystemPath,
+        package_graph: &'a PackageGraph,
+        root_turbo_json: &'a TurboJson,
+        scm: &'a SCM,
+    ) -> Self {
+        Self {
+            repo_root,
+            package_graph,
+            root_turbo_json,
+            scm,
+            workspace_tags: OnceCell::new(),
+        }
+    }
+
+    /// Parses and executes `query`, returning the response as JSON in the
+    /// shape of a GraphQL response, i.e. `{ "data": { ... } }`
+    pub fn execute(&self, query: &str) -> Result<Value, Error> {
+        let selections = parser::parse(query)?;
+
+        let mut data = Map::new();
+        for field in &selections {
+            data.insert(field.response_key().to_string(), self.query_field(field)?);
+        }
+
+        Ok(json!({ "data": data }))
+    }
+
+    fn query_field(&self, field: &Field) -> Result<Value, Error> {
+        match field.name.as_str() {
+            "packages" => {
+                let filter = string_list_argument(field, "filter")?;
+                let packages = self.filter_packages(filter.unwrap_or_default())?;
+                self.packages(field, "Package", packages.iter())
+            }
+            "package" => {
+                let name = string_argument(field, "name")?.ok_or(Error::MissingArgument {
+                    field: field.name.clone(),
+                    argument: "name",
+                })?;
+                let name = WorkspaceName::from(name);
+                if self.package_graph.workspace_info(&name).is_none() {
+                    return Err(Error::PackageNotFound(name.to_string()));
+                }
+                self.package(field, &name)
+            }
+            "changedPackages" => {
+                let since = string_argument(field, "since")?.ok_or(Error::MissingArgument {
+                    field: field.name.clone(),
+                    argument: "since",
+                })?;
+                let filter = match string_argument(field, "to")? {
+                    Some(to) => format!("[{since}...{to}]"),
+                    None => format!("[{since}]"),
+                };
+                let packages = self.filter_packages(vec![filter])?;
+                self.packages(field, "Package", packages.iter())
+            }
+            "tasks" => {
+                let names =
+                    string_list_argument(field, "names")?.ok_or(Error::MissingArgument {
+                        field: field.name.clone(),
+                        argument: "names",
+                    })?;
+                let filter = string_list_argument(field, "filter")?.unwrap_or_default();
+                let engine = self.engine(names, filter)?;
+                let tasks = engine
+                    .tasks()
+                    .filter_map(|task| match task {
+                        TaskNode::Task(task_id) => Some(task_id),
+                        TaskNode::Root => None,
+                    })
+                    .collect::<BTreeSet<_>>();
+
+                tasks
+                    .into_iter()
+                    .map(|task_id| self.task(field, &engine, task_id))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Value::Array)
+            }
+            _ => Err(Error::UnknownField {
+                type_name: "Query",
+                field: field.name.clone(),
+            }),
+        }
+    }
+
+    fn filter_packages(&self, filter: Vec<String>) -> Result<BTreeSet<WorkspaceName>, Error> {
+        let opts = ScopeOpts {
+            pkg_inference_root: None,
+            legacy_filter: Default::default(),
+            global_deps: Vec::new(),
+            filter_patterns: filter,
+            ignore_patterns: Vec::new(),
+        };
+
+        Ok(
+            scope::resolve_packages(&opts, self.repo_root, self.package_graph, self.scm)
+                .map_err(Error::Filter)?
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    fn engine(&self, names: Vec<String>, filter: Vec<String>) -> Result<Engine, Error> {
+        let workspaces = self.filter_packages(filter)?;
+
+        EngineBuilder::new(self.repo_root, self.package_graph, false)
+            .with_root_tasks(self.root_turbo_json.pipeline.keys().cloned())
+            .with_turbo_jsons(Some(
+                Some((WorkspaceName::Root, self.root_turbo_json.clone()))
+                    .into_iter()
+                    .collect(),
+            ))
+            .with_workspaces(workspaces.into_iter().collect())
+            .with_tasks(names.into_iter().map(TaskName::from))
+            .build()
+            .map_err(|e| Error::Engine(e.into()))
+    }
+
+    fn packages<'b>(
+        &self,
+        field: &Field,
+        type_name: &'static str,
+        packages: impl Iterator<Item = &'b WorkspaceName>,
+    ) -> Result<Value, Error> {
+        if field.selections.is_empty() {
+            return Err(Error::MissingSelection {
+                type_name,
+                field: field.name.clone(),
+            });
+        }
+
+        let mut packages = packages.collect::<Vec<_>>();
+        packages.sort();
+        packages
+            .into_iter()
+            .map(|package| self.package(field, package))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array)
+    }
+
+    fn package(&self, field: &Field, name: &WorkspaceName) -> Result<Value, Error> {
+        if field.selections.is_empty() {
+            return Err(Error::MissingSelection {
+                type_name: "Package",
+                field: field.name.clone(),
+            });
+        }
+
+        let info = self
+            .package_graph
+            .workspace_info(name)
+            .ok_or_else(|| Error::PackageNotFound(name.to_string()))?;
+        let node = WorkspaceNode::Workspace(name.clone());
+
+        let mut object = Map::new();
+        for selection in &field.selections {
+            let value = match selection.name.as_str() {
+                "name" => Value::from(name.to_string()),
+                "path" => Value::from(info.package_path().to_unix().to_string()),
+                "version" => info
+                    .package_json
+                    .version
+                    .clone()
+                    .map_or(Value::Null, Value::from),
+                "scripts" => info.package_json.scripts.keys().cloned().collect(),
+                "tags" => self
+                    .workspace_tags()?
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_default()
+                    .into(),
+                "directDependencies" => {
+                    let dependencies = self
+                        .package_graph
+                        .immediate_dependencies(&node)
+                        .unwrap_or_default();
+                    self.packages(selection, "Package", workspaces(dependencies))?
+                }
+                "dependencies" => {
+                    let dependencies = self.package_graph.dependencies(&node);
+                    self.packages(selection, "Package", workspaces(dependencies))?
+                }
+                "directDependents" => {
+                    let dependents = self
+                        .package_graph
+                        .immediate_ancestors(&node)
+                        .unwrap_or_default();
+                    self.packages(selection, "Package", workspaces(dependents))?
+                }
+                "dependents" => {
+                    let dependents = self.package_graph.ancestors(&node);
+                    self.packages(selection, "Package", workspaces(dependents))?
+                }
+                "externalDependencies" => {
+                    let mut dependencies = info
+                        .transitive_dependencies
+                        .iter()
+                        .flatten()
+                        .collect::<Vec<_>>();
+                    dependencies.sort_by(|a, b| (&a.key, &a.version).cmp(&(&b.key, &b.version)));
+                    dependencies
+                        .into_iter()
+                        .map(|dependency| {
+                            object_of(selection, "ExternalPackage", |field| match field {
+                                "key" => Some(Value::from(dependency.key.clone())),
+                                "version" => Some(Value::from(dependency.version.clone())),
+                                _ => None,
+                            })
+                        })
+                        .collect::<Result<Vec<_>, _>>()?
+                        .into()
+                }
+                _ => {
+                    return Err(Error::UnknownField {
+                        type_name: "Package",
+                        field: selection.name.clone(),
+                    })
+                }
+            };
+            object.insert(selection.response_key().to_string(), value);
+        }
+
+        Ok(Value::Object(object))
+    }
+
+    fn task(
+        &self,
+        field: &Field,
+        engine: &Engine,
+        task_id: &crate::run::task_id::TaskId<'static>,
+    ) -> Result<Value, Error> {
+        if field.selections.is_empty() {
+            return Err(Error::MissingSelection {
+                type_name: "Task",
+                field: field.name.clone(),
+            });
+        }
+
+        let mut object = Map::new();
+        for selection in &field.selections {
+            let value = match selection.name.as_str() {
+                "id" => Value::from(task_id.to_string()),
+                "name" => Value::from(task_id.task()),
+                "package" => self.package(selection, &task_id.to_workspace_name())?,
+                "definition" => engine
+                    .task_definition(task_id)
+                    .map(serde_json::to_value)
+                    .transpose()
+                    .map_err(|e| Error::Serialize("task definition", e))?
+                    .unwrap_or(Value::Null),
+                "dependencies" | "dependents" => {
+                    let tasks = if selection.name == "dependencies" {
+                        engine.dependencies(task_id)
+                    } else {
+                        engine.dependents(task_id)
+                    };
+                    let tasks = tasks
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|task| match task {
+                            TaskNode::Task(task_id) => Some(task_id),
+                            TaskNode::Root => None,
+                        })
+                        .collect::<BTreeSet<_>>();
+                    if selection.selections.is_empty() {
+                        return Err(Error::MissingSelection {
+                            type_name: "Task",
+                            field: selection.name.clone(),
+                        });
+                    }
+                    tasks
+                        .into_iter()
+                        .map(|task_id| self.task(selection, engine, task_id))
+                        .collect::<Result<Vec<_>, _>>()?
+                        .into()
+                }
+                _ => {
+                    return Err(Error::UnknownField {
+                        type_name: "Task",
+                        field: selection.name.clone(),
+                    })
+                }
+            };
+            object.insert(selection.response_key().to_string(), value);
+        }
+
+        Ok(Value::Object(object))
+    }
+
+    fn workspace_tags(&self) -> Result<&HashMap<WorkspaceName, Vec<String>>, Error> {
+        Ok(self.workspace_tags.get_or_try_init(|| {
+            TurboJson::load_workspace_tags(self.repo_root, self.package_graph)
+        })?)
+    }
+}
+
+/// Keeps only the workspaces from a set of package graph nodes, dropping the
+/// synthetic root node and the root workspace
+fn workspaces<'b>(
+    nodes: impl IntoIterator<Item = &'b WorkspaceNode>,
+) -> impl Iterator<Item = &'b WorkspaceName> {
+    nodes.into_iter().filter_map(|node| match node {
+        WorkspaceNode::Workspace(name @ WorkspaceName::Other(_)) => Some(name),
+        _ => None,
+    })
+}
+
+fn object_of(
+    field: &Field,
+    type_name: &'static str,
+    resolve: impl Fn(&str) -> Option<Value>,
+) -> Result<Value, Error> {
+    if field.selections.is_empty() {
+        return Err(Error::MissingSelection {
+            type_name,
+            field: field.name.clone(),
+        });
+    }
+
+    let mut object = Map::new();
+    for selection in &field.selections {
+        let value = resolve(&selection.name).ok_or_else(|| Error::UnknownField {
+            type_name,
+            field: selection.name.clone(),
+        })?;
+        object.insert(selection.response_key().to_string(), value);
+    }
+
+    Ok(Value::Object(object))
+}
+
+fn string_argument<'b>(field: &'b Field, argument: &str) -> Result<Option<&'b str>, Error> {
+    match field.arguments.get(argument) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(Error::InvalidArgument {
+            field: field.name.clone(),
+            argument: argument.to_string(),
+            expected: "a string",
+        }),
+    }
+}
+
+/// Like `string_argument`, but a single string is accepted in place of a list
+fn string_list_argument(field: &Field, argument: &str) -> Result<Option<Vec<String>>, Error> {
+    let invalid = || Error::InvalidArgument {
+        field: field.name.clone(),
+        argument: argument.to_string(),
+        expected: "a string or a list of strings",
+    };
+
+    match field.arguments.get(argument) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(vec![s.clone()])),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| value.as_str().map(String::from).ok_or_else(invalid))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+        Some(_) => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_repository::{package_json::PackageJson, package_manager::PackageManager};
+
+    use super::*;
+
+    // app -> ui -> utils
+    // docs -> ui
+    fn package_graph(root: &AbsoluteSystemPath) -> PackageGraph {
+        let package_jsons = [
+            (
+                "apps/app",
+                json!({ "name": "app", "version": "1.0.0", "dependencies": { "ui": "*" } }),
+            ),
+            ("apps/docs", json!({ "name": "docs", "dependencies": { "ui": "*" } })),
+            (
+                "packages/ui",
+                json!({ "name": "ui", "scripts": { "build": "tsc" }, "dependencies": { "utils": "*" } }),
+            ),
+            ("packages/utils", json!({ "name": "utils" })),
+        ]
+        .into_iter()
+        .map(|(dir, package_json)| {
+            let mut components = dir.split('/').collect::<Vec<_>>();
+            components.push("package.json");
+            (
+                root.join_components(&components),
+                PackageJson::from_value(package_json).unwrap(),
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+        PackageGraph::builder(
+            root,
+            PackageJson::from_value(json!({ "name": "root" })).unwrap(),
+        )
+        .with_package_manger(Some(PackageManager::Npm))
+        .with_package_jsons(Some(package_jsons))
+        .build()
+        .unwrap()
+    }
+
+    fn execute(query: &str) -> Result<Value, Error> {
+        let root =
+            AbsoluteSystemPathBuf::new(if cfg!(windows) { r"C:\repo" } else { "/repo" }).unwrap();
+        let package_graph = package_graph(&root);
+        let turbo_json: TurboJson = serde_json::from_value::<crate::config::RawTurboJSON>(
+            json!({ "pipeline": { "build": { "dependsOn": ["^build"] } } }),
+        )
+        .unwrap()
+        .try_into()
+        .unwrap();
+        let scm = SCM::new(&root);
+        let runner = QueryRunner::new(&root, &package_graph, &turbo_json, &scm);
+        runner
+            .workspace_tags
+            .set([(WorkspaceName::from("ui"), vec!["shared".to_string()])].into())
+            .unwrap();
+
+        runner.execute(query)
+    }
+
+    #[test_case(
+        "{ packages { name } }",
+        json!({ "packages": [{ "name": "app" }, { "name": "docs" }, { "name": "ui" }, { "name": "utils" }] }) ;
+        "all packages"
+    )]
+    #[test_case(
+        r#"{ packages(filter: "./apps/*") { name path } }"#,
+        json!({ "packages": [{ "name": "app", "path": "apps/app" }, { "name": "docs", "path": "apps/docs" }] }) ;
+        "filtered packages"
+    )]
+    #[test_case(
+        r#"{ app: package(name: "app") { version deps: dependencies { name } directDependencies { name } } }"#,
+        json!({ "app": {
+            "version": "1.0.0",
+            "deps": [{ "name": "ui" }, { "name": "utils" }],
+            "directDependencies": [{ "name": "ui" }],
+        } }) ;
+        "package dependencies"
+    )]
+    #[test_case(
+        r#"{ package(name: "ui") { tags scripts dependents { name } directDependents { name } } }"#,
+        json!({ "package": {
+            "tags": ["shared"],
+            "scripts": ["build"],
+            "dependents": [{ "name": "app" }, { "name": "docs" }],
+            "directDependents": [{ "name": "app" }, { "name": "docs" }],
+        } }) ;
+        "package dependents"
+    )]
+    #[test_case(
+        r#"{ tasks(names: "build", filter: "app") { id dependencies { id } } }"#,
+        json!({ "tasks": [
+            { "id": "app#build", "dependencies": [{ "id": "ui#build" }] },
+            { "id": "ui#build", "dependencies": [{ "id": "utils#build" }] },
+            { "id": "utils#build", "dependencies": [] },
+        ] }) ;
+        "task dependencies"
+    )]
+    #[test_case(
+        r#"{ tasks(names: ["build"], filter: "app") { name package { name } dependents { id } } }"#,
+        json!({ "tasks": [
+            { "name": "build", "package": { "name": "app" }, "dependents": [] },
+            { "name": "build", "package": { "name": "ui" }, "dependents": [{ "id": "app#build" }] },
+            { "name": "build", "package": { "name": "utils" }, "dependents": [{ "id": "ui#build" }] },
+        ] }) ;
+        "task dependents"
+    )]
+    fn test_query(query: &str, expected: Value) {
+        assert_eq!(execute(query).unwrap(), json!({ "data": expected }));
+    }
+
+    #[test]
+    fn test_query_errors() {
+        assert!(matches!(
+            execute("{ packages }"),
+            Err(Error::MissingSelection { .. })
+        ));
+        assert!(matches!(
+            execute("{ packages { nope } }"),
+            Err(Error::UnknownField {
+                type_name: "Package",
+                ..
+            })
+        ));
+        assert!(matches!(
+            execute("{ package { name } }"),
+            Err(Error::MissingArgument {
+                argument: "name",
+                ..
+            })
+        ));
+        assert!(matches!(
+            execute(r#"{ package(name: "nope") { name } }"#),
+            Err(Error::PackageNotFound(_))
+        ));
+        assert!(matches!(
+            execute("{ packages(filter: 1) { name } }"),
+            Err(Error::InvalidArgument { .. })
+        ));
+    }
+}
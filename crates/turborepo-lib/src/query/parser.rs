@@ -0,0 +1,387 @@
+//! Parser for the subset of GraphQL accepted by `turbo query`.
+//!
+//! Only anonymous or named `query` operations made of fields, aliases and
+//! literal arguments are supported. Variables, fragments and directives are
+//! not.
+
+use std::{collections::BTreeMap, iter::Peekable, str::CharIndices};
+
+use serde_json::Value;
+
+use super::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub alias: Option<String>,
+    pub name: String,
+    pub arguments: BTreeMap<String, Value>,
+    pub selections: Vec<Field>,
+}
+
+impl Field {
+    /// The key this field is written under in the response
+    pub fn response_key(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    String(String),
+    Int(i64),
+    Punctuator(char),
+}
+
+struct Lexer<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    source: &'a str,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            chars: source.char_indices().peekable(),
+            source,
+        }
+    }
+
+    fn skip_ignored(&mut self) {
+        while let Some(&(_, c)) = self.chars.peek() {
+            match c {
+                // Commas are insignificant in GraphQL
+                c if c.is_whitespace() || c == ',' => {
+                    self.chars.next();
+                }
+                '#' => {
+                    for (_, c) in self.chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<(usize, Token)>, Error> {
+        self.skip_ignored();
+        let Some((start, c)) = self.chars.next() else {
+            return Ok(None);
+        };
+
+        let token = match c {
+            '{' | '}' | '(' | ')' | '[' | ']' | ':' => Token::Punctuator(c),
+            '"' => Token::String(self.string(start)?),
+            c if c == '-' || c.is_ascii_digit() => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = self.chars.peek() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    self.chars.next();
+                }
+                let literal = &self.source[start..end];
+                Token::Int(literal.parse().map_err(|_| Error::Syntax {
+                    position: start,
+                    message: format!("invalid integer \"{literal}\""),
+                })?)
+            }
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = self.chars.peek() {
+                    if c != '_' && !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    self.chars.next();
+                }
+                Token::Name(self.source[start..end].to_string())
+            }
+            c => {
+                return Err(Error::Syntax {
+                    position: start,
+                    message: format!("unexpected character '{c}'"),
+                })
+            }
+        };
+
+        Ok(Some((start, token)))
+    }
+
+    fn string(&mut self, start: usize) -> Result<String, Error> {
+        let mut value = String::new();
+        while let Some((i, c)) = self.chars.next() {
+            match c {
+                '"' => return Ok(value),
+                '\\' => match self.chars.next() {
+                    Some((_, '"')) => value.push('"'),
+                    Some((_, '\\')) => value.push('\\'),
+                    Some((_, '/')) => value.push('/'),
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, c)) => {
+                        return Err(Error::Syntax {
+                            position: i,
+                            message: format!("unsupported escape sequence '\\{c}'"),
+                        })
+                    }
+                    None => break,
+                },
+                '\n' => break,
+                c => value.push(c),
+            }
+        }
+
+        Err(Error::Syntax {
+            position: start,
+            message: "unterminated string".to_string(),
+        })
+    }
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<Option<(usize, Token)>>,
+    len: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Result<Option<&(usize, Token)>, Error> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lexer.next_token()?);
+        }
+        Ok(self.peeked.as_ref().and_then(|token| token.as_ref()))
+    }
+
+    fn next(&mut self) -> Result<Option<(usize, Token)>, Error> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self.lexer.next_token(),
+        }
+    }
+
+    fn peek_is(&mut self, punctuator: char) -> Result<bool, Error> {
+        Ok(matches!(self.peek()?, Some((_, Token::Punctuator(c))) if *c == punctuator))
+    }
+
+    fn expect(&mut self, punctuator: char) -> Result<(), Error> {
+        match self.next()? {
+            Some((_, Token::Punctuator(c))) if c == punctuator => Ok(()),
+            Some((position, token)) => Err(Error::Syntax {
+                position,
+                message: format!("expected '{punctuator}', found {}", describe(&token)),
+            }),
+            None => Err(self.unexpected_end(&format!("'{punctuator}'"))),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, Error> {
+        match self.next()? {
+            Some((_, Token::Name(name))) => Ok(name),
+            Some((position, token)) => Err(Error::Syntax {
+                position,
+                message: format!("expected a name, found {}", describe(&token)),
+            }),
+            None => Err(self.unexpected_end("a name")),
+        }
+    }
+
+    fn unexpected_end(&self, expected: &str) -> Error {
+        Error::Syntax {
+            position: self.len,
+            message: format!("expected {expected}, found end of query"),
+        }
+    }
+
+    fn document(&mut self) -> Result<Vec<Field>, Error> {
+        match self.peek()? {
+            Some((_, Token::Name(keyword))) if keyword == "query" => {
+                self.next()?;
+                if let Some((_, Token::Name(_))) = self.peek()? {
+                    // The operation name isn't used for anything
+                    self.next()?;
+                }
+            }
+            Some((position, Token::Name(keyword))) => {
+                return Err(Error::Syntax {
+                    position: *position,
+                    message: format!(
+                        "unsupported operation \"{keyword}\", only \"query\" is supported"
+                    ),
+                })
+            }
+            _ => (),
+        }
+
+        let selections = self.selection_set()?;
+        if let Some((position, token)) = self.next()? {
+            return Err(Error::Syntax {
+                position,
+                message: format!("unexpected {} after query", describe(&token)),
+            });
+        }
+
+        Ok(selections)
+    }
+
+    fn selection_set(&mut self) -> Result<Vec<Field>, Error> {
+        self.expect('{')?;
+        let mut selections = Vec::new();
+        while !self.peek_is('}')? {
+            if self.peek()?.is_none() {
+                return Err(self.unexpected_end("'}'"));
+            }
+            selections.push(self.field()?);
+        }
+        self.expect('}')?;
+
+        Ok(selections)
+    }
+
+    fn field(&mut self) -> Result<Field, Error> {
+        let mut name = self.name()?;
+        let mut alias = None;
+        if self.peek_is(':')? {
+            self.next()?;
+            alias = Some(name);
+            name = self.name()?;
+        }
+
+        let mut arguments = BTreeMap::new();
+        if self.peek_is('(')? {
+            self.next()?;
+            while !self.peek_is(')')? {
+                let argument = self.name()?;
+                self.expect(':')?;
+                let value = self.value()?;
+                arguments.insert(argument, value);
+            }
+            self.expect(')')?;
+        }
+
+        let selections = if self.peek_is('{')? {
+            self.selection_set()?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Field {
+            alias,
+            name,
+            arguments,
+            selections,
+        })
+    }
+
+    fn value(&mut self) -> Result<Value, Error> {
+        match self.next()? {
+            Some((_, Token::String(s))) => Ok(Value::String(s)),
+            Some((_, Token::Int(i))) => Ok(Value::from(i)),
+            Some((_, Token::Name(name))) if name == "true" => Ok(Value::Bool(true)),
+            Some((_, Token::Name(name))) if name == "false" => Ok(Value::Bool(false)),
+            Some((_, Token::Name(name))) if name == "null" => Ok(Value::Null),
+            Some((_, Token::Punctuator('['))) => {
+                let mut values = Vec::new();
+                while !self.peek_is(']')? {
+                    values.push(self.value()?);
+                }
+                self.expect(']')?;
+                Ok(Value::Array(values))
+            }
+            Some((position, token)) => Err(Error::Syntax {
+                position,
+                message: format!("expected a value, found {}", describe(&token)),
+            }),
+            None => Err(self.unexpected_end("a value")),
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Name(name) => format!("\"{name}\""),
+        Token::String(s) => format!("string \"{s}\""),
+        Token::Int(i) => format!("integer {i}"),
+        Token::Punctuator(c) => format!("'{c}'"),
+    }
+}
+
+/// Parses a query document into its top-level selections
+pub fn parse(source: &str) -> Result<Vec<Field>, Error> {
+    Parser {
+        lexer: Lexer::new(source),
+        peeked: None,
+        len: source.len(),
+    }
+    .document()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn field(name: &str, selections: Vec<Field>) -> Field {
+        Field {
+            alias: None,
+            name: name.to_string(),
+            arguments: BTreeMap::new(),
+            selections,
+        }
+    }
+
+    #[test]
+    fn test_parse_nested_selections() {
+        let query = r#"
+            query Packages {
+                # every package
+                all: packages(filter: ["...foo", "bar"], limit: 2) {
+                    name,
+                    dependencies { name }
+                }
+            }
+        "#;
+
+        let selections = parse(query).unwrap();
+        assert_eq!(
+            selections,
+            vec![Field {
+                alias: Some("all".to_string()),
+                name: "packages".to_string(),
+                arguments: [
+                    ("filter".to_string(), json!(["...foo", "bar"])),
+                    ("limit".to_string(), json!(2)),
+                ]
+                .into_iter()
+                .collect(),
+                selections: vec![
+                    field("name", vec![]),
+                    field("dependencies", vec![field("name", vec![])]),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_anonymous_query() {
+        let selections = parse(r#"{ package(name: "a\"b") { name } }"#).unwrap();
+        assert_eq!(selections[0].arguments["name"], json!("a\"b"));
+        assert_eq!(selections[0].response_key(), "package");
+    }
+
+    #[test_case("" ; "empty")]
+    #[test_case("{ packages { name }" ; "unclosed selection set")]
+    #[test_case("{ packages(filter: ) { name } }" ; "missing value")]
+    #[test_case(r#"{ package(name: "foo) { name } }"# ; "unterminated string")]
+    #[test_case("mutation { packages { name } }" ; "mutation")]
+    #[test_case("{ packages { name } } }" ; "trailing tokens")]
+    #[test_case("{ packages { name% } }" ; "invalid character")]
+    fn test_parse_errors(query: &str) {
+        assert!(matches!(parse(query), Err(Error::Syntax { .. })));
+    }
+}
@@ -12,7 +12,7 @@ use crate::{
     cli::OutputLogsMode,
     daemon::{DaemonClient, DaemonConnector},
     opts::RunCacheOpts,
-    run::task_id::TaskId,
+    run::{output_record::OutputRecord, task_id::TaskId},
     task_graph::{TaskDefinition, TaskOutputs},
 };
 
@@ -28,6 +28,8 @@ pub enum Error {
     Daemon(#[from] crate::daemon::DaemonError),
     #[error("no connection to daemon")]
     NoDaemon,
+    #[error(transparent)]
+    OutputRecord(#[from] crate::run::output_record::Error),
 }
 
 impl Error {
@@ -79,6 +81,8 @@ impl RunCache {
         let task_dir = self.repo_root.resolve(workspace_dir);
         let log_file_path =
             task_dir.join_components(&[".turbo", &format!("turbo-{}.log", task_id.task())]);
+        let output_record_path = task_dir
+            .join_components(&[".turbo", &format!("turbo-{}.outputs.json", task_id.task())]);
         let repo_relative_globs =
             task_definition.repo_relative_hashable_outputs(&task_id, workspace_dir);
 
@@ -98,6 +102,7 @@ impl RunCache {
             task_output_mode,
             caching_disabled,
             log_file_path,
+            output_record_path,
             daemon_client: self.daemon_client.clone(),
             ui: self.ui,
         }
@@ -112,6 +117,8 @@ pub struct TaskCache {
     task_output_mode: OutputLogsMode,
     caching_disabled: bool,
     log_file_path: AbsoluteSystemPathBuf,
+    // where we record the outputs last written for this task
+    output_record_path: AbsoluteSystemPathBuf,
     daemon_client: Option<DaemonClient<DaemonConnector>>,
     ui: UI,
     task_id: TaskId<'static>,
@@ -208,7 +215,42 @@ impl TaskCache {
             self.repo_relative_globs.inclusions.len()
         };
 
-        let has_changed_outputs = changed_output_count > 0;
+        let mut has_changed_outputs = changed_output_count > 0;
+
+        // The daemon only knows about outputs written while it's been running,
+        // so fall back to checking what was last recorded on disk.
+        if has_changed_outputs {
+            match self.recorded_outputs_unchanged() {
+                Ok(Some(time_saved)) => {
+                    has_changed_outputs = false;
+                    // Let the daemon start watching these outputs so that it
+                    // can answer for them next time
+                    if let Some(daemon_client) = &mut self.daemon_client {
+                        if let Err(err) = daemon_client
+                            .notify_outputs_written(
+                                self.hash.clone(),
+                                self.repo_relative_globs.inclusions.clone(),
+                                self.repo_relative_globs.exclusions.clone(),
+                                time_saved,
+                            )
+                            .await
+                        {
+                            debug!(
+                                "failed to notify daemon of outputs for {}: {:?}",
+                                self.task_id, err
+                            );
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    debug!(
+                        "failed to check recorded outputs for {}: {}",
+                        self.task_id, err
+                    );
+                }
+            }
+        }
 
         let cache_status = if has_changed_outputs {
             // Note that we currently don't use the output globs when restoring, but we
@@ -232,8 +274,8 @@ impl TaskCache {
 
             self.expanded_outputs = restored_files;
 
-            if let Some(daemon_client) = &mut self.daemon_client {
-                if let Err(err) = daemon_client
+            let notified = match &mut self.daemon_client {
+                Some(daemon_client) => match daemon_client
                     .notify_outputs_written(
                         self.hash.clone(),
                         self.repo_relative_globs.inclusions.clone(),
@@ -242,13 +284,22 @@ impl TaskCache {
                     )
                     .await
                 {
-                    // Don't fail the whole operation just because we failed to
-                    // watch the outputs
-                    prefixed_ui.warn(GREY.apply_to(format!(
-                        "Failed to mark outputs as cached for {}: {:?}",
-                        self.task_id, err
-                    )))
-                }
+                    Ok(_) => true,
+                    Err(err) => {
+                        // Don't fail the whole operation just because we failed to
+                        // watch the outputs
+                        prefixed_ui.warn(GREY.apply_to(format!(
+                            "Failed to mark outputs as cached for {}: {:?}",
+                            self.task_id, err
+                        )));
+                        false
+                    }
+                },
+                None => false,
+            };
+            // Outputs the daemon is watching don't need to be recorded
+            if !notified {
+                self.record_outputs(cache_status.time_saved);
             }
 
             cache_status
@@ -342,10 +393,42 @@ impl TaskCache {
             prefixed_ui.warn(format!(
                 "Failed to mark outputs as cached for {task_id}: {err}",
             ));
+            // Without the daemon watching the outputs, we record them
+            // ourselves so that restoring them can still be skipped
+            self.record_outputs(duration.as_millis() as u64);
         }
 
         self.expanded_outputs = relative_paths;
 
         Ok(())
     }
+
+    /// Returns the time saved by the outputs last recorded for this task if
+    /// they're for this hash and still on disk unchanged.
+    fn recorded_outputs_unchanged(&self) -> Result<Option<u64>, Error> {
+        let Some(record) = OutputRecord::read(&self.output_record_path)? else {
+            return Ok(None);
+        };
+        let unchanged = record.matches(
+            &self.run_cache.repo_root,
+            &self.hash,
+            &self.repo_relative_globs,
+        )?;
+        Ok(unchanged.then_some(record.time_saved()))
+    }
+
+    fn record_outputs(&self, time_saved: u64) {
+        let result = OutputRecord::new(
+            &self.run_cache.repo_root,
+            &self.hash,
+            &self.repo_relative_globs,
+            time_saved,
+        )
+        .and_then(|record| record.write(&self.output_record_path));
+        // Not being able to record outputs only means we can't skip restoring
+        // them later, so it isn't worth failing the task over.
+        if let Err(err) = result {
+            warn!("Failed to record outputs for {}: {}", self.task_id, err);
+        }
+    }
 }
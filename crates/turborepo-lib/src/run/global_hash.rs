@@ -2,12 +2,13 @@ use std::collections::{HashMap, HashSet};
 
 use anyhow::Result;
 use globwalk::WalkType;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
-use tracing::debug;
+use tracing::{debug, warn};
 use turbopath::{AbsoluteSystemPath, RelativeUnixPathBuf};
 use turborepo_env::{get_global_hashable_env_vars, DetailedMap, EnvironmentVariableMap};
 use turborepo_lockfiles::Lockfile;
-use turborepo_repository::package_manager::{self, PackageManager};
+use turborepo_repository::package_manager::{self, PackageManager, YarnRc, YARNRC};
 use turborepo_scm::SCM;
 
 use crate::{
@@ -114,6 +115,12 @@ pub fn get_global_hash_inputs<'a, L: ?Sized + Lockfile>(
         global_file_hash_map.extend(dot_env_object);
     }
 
+    if matches!(package_manager, PackageManager::Berry) {
+        if let Some(hash) = yarnrc_hash(root_path)? {
+            global_file_hash_map.insert(RelativeUnixPathBuf::new(YARNRC)?, hash);
+        }
+    }
+
     let root_external_dependencies_hash = is_monorepo.then_some(root_external_dependencies_hash);
 
     debug!(
@@ -134,6 +141,24 @@ pub fn get_global_hash_inputs<'a, L: ?Sized + Lockfile>(
     })
 }
 
+// Berry's install settings e.g. nodeLinker or packageExtensions change how
+// every dependency gets resolved and loaded, but aren't fully captured by
+// lockfile entries. We only hash those settings rather than the whole file,
+// which also holds unrelated settings and often auth tokens. Settings we can't
+// make sense of, like values yarn fills in from environment variables, mean
+// we fall back to hashing the file as is.
+fn yarnrc_hash(root_path: &AbsoluteSystemPath) -> Result<Option<String>> {
+    let settings = match YarnRc::load(root_path) {
+        Ok(yarnrc) if yarnrc == YarnRc::default() => return Ok(None),
+        Ok(yarnrc) => serde_json::to_vec(&yarnrc)?,
+        Err(e) => {
+            warn!("unable to parse {YARNRC}, hashing the whole file instead: {e}");
+            root_path.join_component(YARNRC).read()?
+        }
+    };
+    Ok(Some(hex::encode(Sha256::digest(settings))))
+}
+
 impl<'a> GlobalHashableInputs<'a> {
     pub fn calculate_global_hash_from_inputs(&mut self) -> String {
         match self.env_mode {
@@ -173,3 +198,36 @@ impl<'a> GlobalHashableInputs<'a> {
         global_hashable.hash()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use sha2::{Digest, Sha256};
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{yarnrc_hash, YARNRC};
+
+    #[test]
+    fn test_yarnrc_hash_missing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        assert_eq!(yarnrc_hash(&root).unwrap(), None);
+    }
+
+    // `Some(true)` when the whole file is hashed rather than just the
+    // install settings
+    #[test_case("npmAuthToken: secret\n", None ; "unrelated settings")]
+    #[test_case("nodeLinker: node-modules\n", Some(false) ; "install settings")]
+    #[test_case("enableGlobalCache: ${CI:-false}\n", Some(true) ; "interpolated value")]
+    fn test_yarnrc_hash(contents: &str, hashes_whole_file: Option<bool>) {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        root.join_component(YARNRC)
+            .create_with_contents(contents)
+            .unwrap();
+
+        let hash = yarnrc_hash(&root).unwrap();
+        let whole_file_hash = hex::encode(Sha256::digest(contents));
+        assert_eq!(hash.map(|hash| hash == whole_file_hash), hashes_whole_file);
+    }
+}
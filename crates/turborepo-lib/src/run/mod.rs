@@ -2,10 +2,12 @@
 
 mod cache;
 mod global_hash;
-mod scope;
+mod output_record;
+pub(crate) mod scope;
 mod summary;
 pub mod task_id;
 use std::{
+    collections::HashMap,
     io::{BufWriter, IsTerminal, Write},
     sync::Arc,
 };
@@ -15,31 +17,33 @@ pub use cache::{RunCache, TaskCache};
 use chrono::Local;
 use itertools::Itertools;
 use rayon::iter::ParallelBridge;
-use tracing::{debug, info};
-use turbopath::AbsoluteSystemPathBuf;
-use turborepo_api_client::APIAuth;
+pub use summary::{ExecutionSummary, TaskPhase, Tracker};
+use tracing::{debug, info, warn};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turborepo_api_client::{APIAuth, APIClient};
 use turborepo_cache::AsyncCache;
 use turborepo_env::EnvironmentVariableMap;
 use turborepo_repository::package_json::PackageJson;
 use turborepo_scm::SCM;
-use turborepo_ui::ColorSelector;
+use turborepo_ui::{ColorSelector, Redactor};
 
 use self::task_id::TaskName;
 use crate::{
+    boundaries,
     cli::EnvMode,
     commands::CommandBase,
-    config::TurboJson,
-    daemon::DaemonConnector,
-    engine::EngineBuilder,
+    config::{PackageManagerCheck, TurboJson},
+    daemon::{DaemonClient, DaemonConnector},
+    engine::{Engine, EngineBuilder},
     opts::{GraphOpts, Opts},
     package_graph::{PackageGraph, WorkspaceName},
-    process::ProcessManager,
+    process::{self, ProcessManager},
     run::{
         global_hash::get_global_hash_inputs,
         summary::{GlobalHashSummary, RunSummary},
     },
     task_graph::Visitor,
-    task_hash::{PackageInputsHashes, TaskHashTrackerState},
+    task_hash::{PackageInputsHashes, PrecomputedFileHashes, TaskHashTrackerState},
 };
 
 #[derive(Debug)]
@@ -50,10 +54,16 @@ pub struct Run<'a> {
 
 impl<'a> Run<'a> {
     pub fn new(base: &'a CommandBase) -> Self {
-        let processes = ProcessManager::new();
+        let processes = ProcessManager::new().with_process_record(&Self::process_dir(base));
         Self { base, processes }
     }
 
+    // Where we record the processes we spawn, in case we exit without
+    // stopping them
+    fn process_dir(base: &CommandBase) -> AbsoluteSystemPathBuf {
+        base.repo_root.join_components(&[".turbo", "processes"])
+    }
+
     fn targets(&self) -> &[String] {
         self.base.args().get_tasks()
     }
@@ -69,23 +79,18 @@ impl<'a> Run<'a> {
             PackageJson::load(&package_json_path).context("failed to read package.json")?;
         let mut opts = self.opts()?;
 
+        match process::reap_orphans(&Self::process_dir(self.base)) {
+            Ok(0) => (),
+            Ok(killed) => {
+                warn!("stopped {killed} process groups left running by a previous turbo run")
+            }
+            Err(e) => debug!("failed to clean up processes from previous runs: {e}"),
+        }
+
         let _is_structured_output = opts.run_opts.graph.is_some() || opts.run_opts.dry_run_json;
 
         let is_single_package = opts.run_opts.single_package;
 
-        let pkg_dep_graph = PackageGraph::builder(&self.base.repo_root, root_package_json.clone())
-            .with_single_package_mode(opts.run_opts.single_package)
-            .build()?;
-
-        let root_turbo_json =
-            TurboJson::load(&self.base.repo_root, &root_package_json, is_single_package)?;
-
-        opts.cache_opts.remote_cache_opts = root_turbo_json.remote_cache_options.clone();
-
-        if opts.run_opts.experimental_space_id.is_none() {
-            opts.run_opts.experimental_space_id = root_turbo_json.space_id.clone();
-        }
-
         // There's some warning handling code in Go that I'm ignoring
         let is_ci_and_not_tty = turborepo_ci::is_ci() && !std::io::stdout().is_terminal();
 
@@ -105,10 +110,53 @@ impl<'a> Run<'a> {
             daemon = Some(client);
         }
 
+        let package_jsons = match daemon.as_mut() {
+            Some(daemon) if !is_single_package => {
+                Self::daemon_package_jsons(&self.base.repo_root, daemon).await
+            }
+            _ => None,
+        };
+
+        let pkg_dep_graph = PackageGraph::builder(&self.base.repo_root, root_package_json.clone())
+            .with_single_package_mode(opts.run_opts.single_package)
+            .with_package_jsons(package_jsons)
+            .build()?;
+
+        let root_turbo_json =
+            TurboJson::load(&self.base.repo_root, &root_package_json, is_single_package)?;
+
+        opts.cache_opts.remote_cache_opts = root_turbo_json.remote_cache_options.clone();
+
+        if opts.run_opts.experimental_space_id.is_none() {
+            opts.run_opts.experimental_space_id = root_turbo_json.space_id.clone();
+        }
+
         pkg_dep_graph
             .validate()
             .context("Invalid package dependency graph")?;
 
+        if let Some(boundaries) = root_turbo_json
+            .boundaries
+            .as_ref()
+            .filter(|boundaries| boundaries.enforce_on_run)
+        {
+            let workspace_tags =
+                TurboJson::load_workspace_tags(&self.base.repo_root, &pkg_dep_graph)?;
+            boundaries::enforce(&pkg_dep_graph, &workspace_tags, boundaries)?;
+        }
+
+        if root_turbo_json.package_manager_check != PackageManagerCheck::Off {
+            if let Err(e) = pkg_dep_graph
+                .package_manager()
+                .check_version(&self.base.repo_root, &root_package_json)
+            {
+                if root_turbo_json.package_manager_check == PackageManagerCheck::Error {
+                    return Err(e.into());
+                }
+                warn!("{e}");
+            }
+        }
+
         let scm = SCM::new(&self.base.repo_root);
 
         let filtered_pkgs = {
@@ -139,24 +187,11 @@ impl<'a> Run<'a> {
 
         let env_at_execution_start = EnvironmentVariableMap::infer();
 
-        let repo_config = self.base.repo_config()?;
-        let team_id = repo_config.team_id();
-        let team_slug = repo_config.team_slug();
+        let api_client = self.base.api_client()?;
+        let api_auth = self.api_auth(&api_client, &opts).await?;
 
-        let token = self.base.user_config()?.token();
-
-        let api_auth = team_id.zip(token).map(|(team_id, token)| APIAuth {
-            team_id: team_id.to_string(),
-            token: token.to_string(),
-            team_slug: team_slug.map(|s| s.to_string()),
-        });
-
-        let async_cache = AsyncCache::new(
-            &opts.cache_opts,
-            &self.base.repo_root,
-            self.base.api_client()?,
-            api_auth,
-        )?;
+        let async_cache =
+            AsyncCache::new(&opts.cache_opts, &self.base.repo_root, api_client, api_auth)?;
 
         info!("created cache");
         let engine = EngineBuilder::new(
@@ -234,6 +269,19 @@ impl<'a> Run<'a> {
 
         debug!("global hash: {}", global_hash);
 
+        let precomputed_file_hashes = match daemon.as_mut() {
+            Some(daemon) => {
+                let workspaces = pkg_dep_graph.workspaces().collect();
+                let packages = PackageInputsHashes::file_hash_inputs(
+                    engine.tasks(),
+                    &workspaces,
+                    engine.task_definitions(),
+                );
+                Self::daemon_file_hashes(daemon, packages).await
+            }
+            None => PrecomputedFileHashes::default(),
+        };
+
         let color_selector = ColorSelector::default();
 
         let runcache = Arc::new(RunCache::new(
@@ -259,6 +307,7 @@ impl<'a> Run<'a> {
             workspaces,
             engine.task_definitions(),
             &self.base.repo_root,
+            &precomputed_file_hashes,
         )?;
 
         debug!("package inputs hashes: {:?}", package_inputs_hashes);
@@ -269,6 +318,13 @@ impl<'a> Run<'a> {
         let pkg_dep_graph = Arc::new(pkg_dep_graph);
         let engine = Arc::new(engine);
 
+        let synthesized_command = "todo".to_string();
+        let execution_summary = ExecutionSummary::new(
+            synthesized_command.clone(),
+            opts.scope_opts.pkg_inference_root.as_deref(),
+            start_at,
+        );
+
         let visitor = Visitor::new(
             pkg_dep_graph.clone(),
             runcache,
@@ -281,9 +337,22 @@ impl<'a> Run<'a> {
             false,
             self.processes.clone(),
             &self.base.repo_root,
+            &execution_summary,
         );
 
         let errors = visitor.visit(engine.clone()).await?;
+        // Every task has finished by now, so this just clears our record of
+        // the processes we spawned
+        self.processes.wait().await;
+
+        if let Some(profile) = opts.run_opts.profile {
+            let path = AbsoluteSystemPathBuf::from_unknown(self.base.cwd(), profile);
+            match execution_summary.write_profile(&path, &engine) {
+                Ok(()) => debug!("wrote profile to {}", path),
+                // The run itself was fine, so this doesn't fail it
+                Err(e) => warn!("failed to write profile to {}: {}", path, e),
+            }
+        }
 
         let exit_code = errors
             .iter()
@@ -319,27 +388,149 @@ impl<'a> Run<'a> {
             workspaces,
             engine.task_definitions(),
             &self.base.repo_root,
+            &PrecomputedFileHashes::default(),
         )?;
 
         debug!("package file hashes: {:?}", package_file_hashes);
 
+        let redactor = Self::summary_redactor(
+            &engine,
+            &pkg_dep_graph,
+            &env_at_execution_start,
+            &self.base.repo_root,
+        );
+
         let mut run_summary = RunSummary::new(
-            start_at,
+            execution_summary,
             &self.base.repo_root,
-            opts.scope_opts.pkg_inference_root.as_deref(),
             self.base.version(),
             &opts.run_opts,
             filtered_pkgs.clone(),
             env_at_execution_start,
             global_hash_summary,
-            "todo".to_string(),
-        );
+            synthesized_command,
+        )
+        .with_redactor(redactor);
 
         run_summary.close(0, &pkg_dep_graph, self.base.ui)?;
 
         Ok(exit_code)
     }
 
+    // Credentials for the remote cache. When an OIDC token source is
+    // configured it's used in place of the token from the user config, and
+    // consulted again whenever the cache rejects the token. Failing to get an
+    // OIDC token only costs us the remote cache, so it doesn't fail the run.
+    async fn api_auth(&self, api_client: &APIClient, opts: &Opts<'_>) -> Result<Option<APIAuth>> {
+        if opts.cache_opts.skip_remote {
+            return Ok(None);
+        }
+        let repo_config = self.base.repo_config()?;
+        let Some(team_id) = repo_config.team_id() else {
+            return Ok(None);
+        };
+        let team_slug = repo_config.team_slug();
+
+        let oidc = self.base.client_config()?.oidc_token_source();
+        let token = match &oidc {
+            Some(oidc) => match api_client.fetch_oidc_token(oidc).await {
+                Ok(token) => Some(token),
+                Err(e) => {
+                    warn!("failed to get OIDC token, the remote cache won't be used: {e}");
+                    return Ok(None);
+                }
+            },
+            None => self
+                .base
+                .user_config()?
+                .token()
+                .map(|token| token.to_string()),
+        };
+
+        Ok(token.map(|token| APIAuth {
+            team_id: team_id.to_string(),
+            token,
+            team_slug: team_slug.map(|s| s.to_string()),
+            oidc,
+        }))
+    }
+
+    // Package discovery served by the daemon. Any failure falls back to
+    // discovering packages locally, which also surfaces any errors.
+    async fn daemon_package_jsons(
+        repo_root: &AbsoluteSystemPath,
+        daemon: &mut DaemonClient<DaemonConnector>,
+    ) -> Option<HashMap<AbsoluteSystemPathBuf, PackageJson>> {
+        let package_files = daemon
+            .discover_packages()
+            .await
+            .map_err(|e| debug!("failed to discover packages with the daemon: {}", e))
+            .ok()?;
+        package_files
+            .into_iter()
+            .map(|(path, contents)| {
+                let package_json = serde_json::from_str(&contents).ok()?;
+                Some((repo_root.resolve(&path), package_json))
+            })
+            .collect()
+    }
+
+    // File hashes served by the daemon. Any failure falls back to hashing
+    // locally.
+    async fn daemon_file_hashes(
+        daemon: &mut DaemonClient<DaemonConnector>,
+        packages: Vec<(AnchoredSystemPathBuf, Vec<String>)>,
+    ) -> PrecomputedFileHashes {
+        match daemon.get_file_hashes(&packages).await {
+            Ok(hashes) => packages.into_iter().zip(hashes).collect(),
+            Err(e) => {
+                debug!("failed to get file hashes from the daemon: {}", e);
+                PrecomputedFileHashes::default()
+            }
+        }
+    }
+
+    // Collects the values of every task's secret env vars so they can be masked in
+    // the run summary
+    fn summary_redactor(
+        engine: &Engine,
+        pkg_dep_graph: &PackageGraph,
+        env_at_execution_start: &EnvironmentVariableMap,
+        repo_root: &AbsoluteSystemPath,
+    ) -> Redactor {
+        let mut secrets = Vec::new();
+        for (task_id, task_definition) in engine.task_definitions() {
+            if task_definition.secret_env.is_empty() {
+                continue;
+            }
+            secrets.extend(task_definition.secret_values(env_at_execution_start));
+
+            if !task_definition.load_dot_env {
+                continue;
+            }
+            let Some(workspace_dir) =
+                pkg_dep_graph.workspace_dir(&WorkspaceName::from(task_id.package()))
+            else {
+                continue;
+            };
+            // Errors loading .env files are reported when the task runs
+            if let Ok(dot_env) = task_definition.dot_env_vars(&repo_root.resolve(workspace_dir)) {
+                secrets.extend(task_definition.secret_values(&dot_env));
+            }
+        }
+
+        // The summary is serialized as JSON, so also redact the escaped forms
+        let escaped = secrets
+            .iter()
+            .filter_map(|secret| {
+                let json = serde_json::to_string(secret).ok()?;
+                Some(json[1..json.len() - 1].to_string())
+            })
+            .collect::<Vec<_>>();
+
+        Redactor::new(secrets.into_iter().chain(escaped))
+    }
+
     #[tokio::main]
     pub async fn get_hashes(&self) -> Result<(String, TaskHashTrackerState)> {
         let env_at_execution_start = EnvironmentVariableMap::infer();
@@ -406,17 +597,8 @@ impl<'a> Run<'a> {
 
         let global_hash = global_hash_inputs.calculate_global_hash_from_inputs();
 
-        let repo_config = self.base.repo_config()?;
-        let team_id = repo_config.team_id();
-        let team_slug = repo_config.team_slug();
-
-        let token = self.base.user_config()?.token();
-
-        let api_auth = team_id.zip(token).map(|(team_id, token)| APIAuth {
-            team_id: team_id.to_string(),
-            token: token.to_string(),
-            team_slug: team_slug.map(|s| s.to_string()),
-        });
+        let api_client = self.base.api_client()?;
+        let api_auth = self.api_auth(&api_client, &opts).await?;
 
         let engine = EngineBuilder::new(
             &self.base.repo_root,
@@ -452,17 +634,14 @@ impl<'a> Run<'a> {
             pkg_dep_graph.workspaces().collect(),
             engine.task_definitions(),
             &self.base.repo_root,
+            &PrecomputedFileHashes::default(),
         )?;
 
         let pkg_dep_graph = Arc::new(pkg_dep_graph);
         let engine = Arc::new(engine);
 
-        let async_cache = AsyncCache::new(
-            &opts.cache_opts,
-            &self.base.repo_root,
-            self.base.api_client()?,
-            api_auth,
-        )?;
+        let async_cache =
+            AsyncCache::new(&opts.cache_opts, &self.base.repo_root, api_client, api_auth)?;
 
         let color_selector = ColorSelector::default();
 
@@ -475,6 +654,8 @@ impl<'a> Run<'a> {
             self.base.ui,
         ));
 
+        // Nothing is executed on a dry run, so this is never used
+        let execution_summary = ExecutionSummary::new(String::new(), None, Local::now());
         let visitor = Visitor::new(
             pkg_dep_graph.clone(),
             runcache,
@@ -487,6 +668,7 @@ impl<'a> Run<'a> {
             true,
             self.processes.clone(),
             &self.base.repo_root,
+            &execution_summary,
         )
         .dry_run();
 
@@ -0,0 +1,166 @@
+//! Records of the outputs a task last left on disk.
+//!
+//! The daemon can tell us that a task's outputs haven't changed since they
+//! were written. So that restores can still be skipped when the daemon isn't
+//! used or couldn't be told about the outputs, we instead record the hashes
+//! of a task's outputs next to its log file. If the outputs on disk still
+//! match the record for the task hash being restored, there's nothing to
+//! restore.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turborepo_scm::SCM;
+
+use crate::task_graph::TaskOutputs;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to find outputs: {0}")]
+    Globwalk(#[from] globwalk::WalkError),
+    #[error("failed to hash outputs: {0}")]
+    Hash(#[from] turborepo_scm::Error),
+    #[error("failed to access output record {1}: {0}")]
+    Io(std::io::Error, AbsoluteSystemPathBuf),
+    #[error("invalid output record {1}: {0}")]
+    Json(serde_json::Error, AbsoluteSystemPathBuf),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputRecord {
+    hash: String,
+    outputs: TaskOutputs,
+    time_saved: u64,
+    files: BTreeMap<String, String>,
+}
+
+impl OutputRecord {
+    /// Records the outputs for `hash` that are currently on disk
+    pub fn new(
+        repo_root: &AbsoluteSystemPath,
+        hash: &str,
+        outputs: &TaskOutputs,
+        time_saved: u64,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            hash: hash.to_owned(),
+            outputs: outputs.clone(),
+            time_saved,
+            files: hash_outputs(repo_root, outputs)?,
+        })
+    }
+
+    /// Reads the record at `path`, returning `None` if there isn't one
+    pub fn read(path: &AbsoluteSystemPath) -> Result<Option<Self>, Error> {
+        let contents = match path.read_to_string() {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::Io(e, path.to_owned())),
+        };
+        serde_json::from_str(&contents).map_err(|e| Error::Json(e, path.to_owned()))
+    }
+
+    pub fn write(&self, path: &AbsoluteSystemPath) -> Result<(), Error> {
+        let contents = serde_json::to_string(self).map_err(|e| Error::Json(e, path.to_owned()))?;
+        path.ensure_dir()
+            .and_then(|_| path.create_with_contents(contents))
+            .map_err(|e| Error::Io(e, path.to_owned()))
+    }
+
+    pub fn time_saved(&self) -> u64 {
+        self.time_saved
+    }
+
+    /// Checks whether the outputs on disk are exactly the ones recorded for
+    /// `hash`, with the same contents.
+    pub fn matches(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+        hash: &str,
+        outputs: &TaskOutputs,
+    ) -> Result<bool, Error> {
+        if self.hash != hash || &self.outputs != outputs {
+            return Ok(false);
+        }
+        Ok(hash_outputs(repo_root, outputs)? == self.files)
+    }
+}
+
+fn hash_outputs(
+    repo_root: &AbsoluteSystemPath,
+    outputs: &TaskOutputs,
+) -> Result<BTreeMap<String, String>, Error> {
+    let files = globwalk::globwalk(
+        repo_root,
+        &outputs.inclusions,
+        &outputs.exclusions,
+        globwalk::WalkType::Files,
+    )?
+    .into_iter()
+    .map(|path| AnchoredSystemPathBuf::relative_path_between(repo_root, &path))
+    .collect::<Vec<_>>();
+    // Outputs are always hashed by reading them, there's no index to consult.
+    // Files that are removed while we walk will show up as a mismatch.
+    let hashes = SCM::Manual.hash_existing_of(repo_root, files.iter())?;
+    Ok(hashes
+        .into_iter()
+        .map(|(path, hash)| (path.to_string(), hash))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::OutputRecord;
+    use crate::task_graph::TaskOutputs;
+
+    #[test]
+    fn test_output_record_matches() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let dist = repo_root.join_components(&["packages", "a", "dist"]);
+        dist.create_dir_all().unwrap();
+        dist.join_component("index.js")
+            .create_with_contents("console.log('hi')")
+            .unwrap();
+        let outputs = TaskOutputs {
+            inclusions: vec!["packages/a/dist/**".to_string()],
+            exclusions: vec![],
+        };
+        let record_path = repo_root.join_components(&["packages", "a", ".turbo", "record.json"]);
+
+        OutputRecord::new(&repo_root, "abc", &outputs, 100)
+            .unwrap()
+            .write(&record_path)
+            .unwrap();
+        let record = OutputRecord::read(&record_path).unwrap().unwrap();
+        assert_eq!(record.time_saved(), 100);
+        assert!(record.matches(&repo_root, "abc", &outputs).unwrap());
+        assert!(!record.matches(&repo_root, "def", &outputs).unwrap());
+
+        // new and changed files don't match
+        dist.join_component("other.js")
+            .create_with_contents("")
+            .unwrap();
+        assert!(!record.matches(&repo_root, "abc", &outputs).unwrap());
+        dist.join_component("other.js").remove_file().unwrap();
+        assert!(record.matches(&repo_root, "abc", &outputs).unwrap());
+        dist.join_component("index.js")
+            .create_with_contents("console.log('bye')")
+            .unwrap();
+        assert!(!record.matches(&repo_root, "abc", &outputs).unwrap());
+    }
+
+    #[test]
+    fn test_missing_output_record() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        assert_eq!(
+            OutputRecord::read(&repo_root.join_component("record.json")).unwrap(),
+            None
+        );
+    }
+}
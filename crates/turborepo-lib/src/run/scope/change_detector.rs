@@ -49,23 +49,34 @@ impl<'a> PackageChangeDetector for SCMChangeDetector<'a> {
                 .collect());
         }
 
-        // get filtered files and add the packages that contain them
-        let filtered_changed_files = self.filter_ignored_files(changed_files.iter())?;
-        let mut changed_pkgs =
-            self.get_changed_packages(filtered_changed_files.into_iter(), self.pkg_graph)?;
-
         // if we run into issues, don't error, just assume all pacakges have changed
-        let lockfile_changes = self.get_changes_from_lockfile(&changed_files, from_ref);
-
-        if let Ok(lockfile_changes) = lockfile_changes {
-            changed_pkgs.extend(lockfile_changes);
-        } else {
+        let Ok(lockfile_changes) = self.get_changes_from_lockfile(&changed_files, from_ref) else {
             return Ok(self
                 .pkg_graph
                 .workspaces()
                 .map(|(n, _)| n.to_owned())
                 .collect());
-        }
+        };
+
+        // The lockfile lives outside of every workspace, but we've already worked out
+        // which workspaces its changes affect so it shouldn't mark the root as changed
+        let lockfile_path = self
+            .turbo_root
+            .anchor(
+                &self
+                    .pkg_graph
+                    .package_manager()
+                    .lockfile_path(self.turbo_root),
+            )
+            .expect("lockfile should be in repo");
+
+        // get filtered files and add the packages that contain them
+        let filtered_changed_files =
+            self.filter_ignored_files(changed_files.iter().filter(|f| **f != lockfile_path))?;
+        let mut changed_pkgs =
+            self.get_changed_packages(filtered_changed_files.into_iter(), self.pkg_graph)?;
+        changed_pkgs.extend(lockfile_changes);
+
         Ok(changed_pkgs)
     }
 }
@@ -148,7 +159,9 @@ impl<'a> SCMChangeDetector<'a> {
     }
 
     /// Get a list of package names that have changed due to changes in the
-    /// lockfile across the HEAD and the given ref.
+    /// lockfile across the HEAD and the given ref. Only workspaces whose
+    /// resolved external dependencies differ between the two lockfiles are
+    /// returned.
     fn get_changes_from_lockfile(
         &self,
         changed_files: &HashSet<AnchoredSystemPathBuf>,
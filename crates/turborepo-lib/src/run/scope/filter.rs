@@ -1,4 +1,5 @@
 use std::{
+    cell::OnceCell,
     collections::{HashMap, HashSet},
     path::Path,
     str::FromStr,
@@ -6,6 +7,7 @@ use std::{
 
 use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AnchoredSystemPathBuf};
+use turborepo_repository::package_json::PackageJson;
 use turborepo_scm::SCM;
 use wax::Pattern;
 
@@ -14,7 +16,10 @@ use super::{
     simple_glob::{Match, SimpleGlob},
     target_selector::{InvalidSelectorError, TargetSelector},
 };
-use crate::package_graph::{self, PackageGraph, WorkspaceName};
+use crate::{
+    config::TurboJson,
+    package_graph::{self, PackageGraph, WorkspaceName},
+};
 
 pub struct PackageInference {
     package_name: Option<String>,
@@ -92,6 +97,7 @@ pub struct FilterResolver<'a, T: PackageChangeDetector> {
     inference: Option<PackageInference>,
     scm: &'a SCM,
     change_detector: T,
+    workspace_tags: OnceCell<HashMap<WorkspaceName, Vec<String>>>,
 }
 
 impl<'a> FilterResolver<'a, SCMChangeDetector<'a>> {
@@ -127,6 +133,7 @@ impl<'a, T: PackageChangeDetector> FilterResolver<'a, T> {
             inference,
             scm,
             change_detector,
+            workspace_tags: OnceCell::new(),
         }
     }
 
@@ -202,7 +209,13 @@ impl<'a, T: PackageChangeDetector> FilterResolver<'a, T> {
         };
 
         for selector in &mut selectors {
-            inference.apply(selector);
+            if selector.intersection.is_empty() {
+                inference.apply(selector);
+            } else {
+                for operand in &mut selector.intersection {
+                    inference.apply(operand);
+                }
+            }
         }
 
         selectors
@@ -244,6 +257,11 @@ impl<'a, T: PackageChangeDetector> FilterResolver<'a, T> {
         let mut cherry_picked_packages = HashSet::new();
 
         for selector in selectors {
+            if !selector.intersection.is_empty() {
+                cherry_picked_packages.extend(self.filter_intersection(selector.intersection)?);
+                continue;
+            }
+
             let selector_packages = self.filter_graph_with_selector(&selector)?;
 
             if selector_packages.is_empty() {
@@ -313,6 +331,30 @@ impl<'a, T: PackageChangeDetector> FilterResolver<'a, T> {
         Ok(all_packages)
     }
 
+    /// returns the set of packages selected by every one of the operands.
+    /// Each operand is resolved on its own, including any dependencies or
+    /// dependents it asks for, before the results are intersected.
+    fn filter_intersection(
+        &self,
+        operands: Vec<TargetSelector>,
+    ) -> Result<HashSet<WorkspaceName>, ResolutionError> {
+        let mut operands = operands.into_iter();
+        let Some(first) = operands.next() else {
+            return Ok(Default::default());
+        };
+
+        let mut packages = self.filter_graph_with_selectors(vec![first])?;
+        for operand in operands {
+            if packages.is_empty() {
+                break;
+            }
+            let operand_packages = self.filter_graph_with_selectors(vec![operand])?;
+            packages.retain(|package| operand_packages.contains(package));
+        }
+
+        Ok(packages)
+    }
+
     fn filter_graph_with_selector(
         &self,
         selector: &TargetSelector,
@@ -471,8 +513,52 @@ impl<'a, T: PackageChangeDetector> FilterResolver<'a, T> {
             }
         }
 
-        // if neither a name pattern, parent dir, or from ref is provided, then
-        // the selector is invalid
+        if let Some(tag) = &selector.tag {
+            let workspace_tags = self.workspace_tags()?;
+            let has_tag = |name: &WorkspaceName| {
+                workspace_tags
+                    .get(name)
+                    .map_or(false, |tags| tags.contains(tag))
+            };
+
+            if !selector_valid {
+                entry_packages = self
+                    .pkg_graph
+                    .workspaces()
+                    .map(|(name, _)| name)
+                    .filter(|name| has_tag(name))
+                    .cloned()
+                    .collect();
+                selector_valid = true;
+            } else {
+                entry_packages.retain(has_tag);
+            }
+        }
+
+        if let Some(field) = &selector.package_json_field {
+            let has_field = |name: &WorkspaceName| {
+                self.pkg_graph
+                    .package_json(name)
+                    .and_then(|package_json| package_json_field(package_json, &field.name))
+                    .map_or(false, |value| field.is_match(&value))
+            };
+
+            if !selector_valid {
+                entry_packages = self
+                    .pkg_graph
+                    .workspaces()
+                    .map(|(name, _)| name)
+                    .filter(|name| has_field(name))
+                    .cloned()
+                    .collect();
+                selector_valid = true;
+            } else {
+                entry_packages.retain(has_field);
+            }
+        }
+
+        // if neither a name pattern, parent dir, from ref, tag or package.json
+        // field is provided, then the selector is invalid
         if !selector_valid {
             Err(ResolutionError::InvalidSelector(
                 InvalidSelectorError::InvalidSelector(selector.raw.clone()),
@@ -482,6 +568,12 @@ impl<'a, T: PackageChangeDetector> FilterResolver<'a, T> {
         }
     }
 
+    fn workspace_tags(&self) -> Result<&HashMap<WorkspaceName, Vec<String>>, ResolutionError> {
+        Ok(self
+            .workspace_tags
+            .get_or_try_init(|| TurboJson::load_workspace_tags(self.turbo_root, self.pkg_graph))?)
+    }
+
     fn packages_changed_in_range(
         &self,
         from_ref: &str,
@@ -502,6 +594,15 @@ impl<'a, T: PackageChangeDetector> FilterResolver<'a, T> {
     }
 }
 
+/// looks up a top-level field in a package.json, including the fields that
+/// aren't modelled by `PackageJson` such as `private` or `keywords`
+fn package_json_field(package_json: &PackageJson, field: &str) -> Option<serde_json::Value> {
+    match serde_json::to_value(package_json) {
+        Ok(serde_json::Value::Object(mut fields)) => fields.remove(field),
+        _ => None,
+    }
+}
+
 /// match the provided name pattern against the provided set of packages
 /// and return the set of packages that match the pattern
 ///
@@ -563,6 +664,8 @@ pub enum ResolutionError {
     Scm(#[from] turborepo_scm::Error),
     #[error("Unable to calculate changes: {0}")]
     ChangeDetectError(#[from] ChangeDetectError),
+    #[error("Unable to read workspace tags: {0}")]
+    WorkspaceTags(#[from] crate::config::Error),
 }
 
 #[cfg(test)]
@@ -901,6 +1004,125 @@ mod test {
         );
     }
 
+    #[test_case(
+        vec![
+            TargetSelector {
+                tag: Some("ui".to_string()),
+                ..Default::default()
+            }
+        ],
+        &["project-1", "project-5"] ;
+        "select by tag"
+    )]
+    #[test_case(
+        vec![
+            TargetSelector {
+                tag: Some("ui".to_string()),
+                include_dependents: true,
+                ..Default::default()
+            }
+        ],
+        &["project-0", "project-1", "project-5"] ;
+        "select by tag with dependents"
+    )]
+    #[test_case(
+        vec![
+            TargetSelector {
+                intersection: vec![
+                    TargetSelector {
+                        tag: Some("ui".to_string()),
+                        ..Default::default()
+                    },
+                    TargetSelector {
+                        parent_dir: AnchoredSystemPathBuf::try_from("packages/*").unwrap(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }
+        ],
+        &["project-1"] ;
+        "intersect tag with directory"
+    )]
+    #[test_case(
+        vec![
+            TargetSelector {
+                intersection: vec![
+                    TargetSelector {
+                        name_pattern: "project-1".to_string(),
+                        include_dependencies: true,
+                        ..Default::default()
+                    },
+                    TargetSelector {
+                        tag: Some("server".to_string()),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }
+        ],
+        &["project-4"] ;
+        "intersect dependencies with tag"
+    )]
+    #[test_case(
+        vec![
+            TargetSelector {
+                exclude: true,
+                intersection: vec![
+                    TargetSelector {
+                        tag: Some("ui".to_string()),
+                        ..Default::default()
+                    },
+                    TargetSelector {
+                        parent_dir: AnchoredSystemPathBuf::try_from("packages/*").unwrap(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }
+        ],
+        &["project-0", "project-2", "project-3", "project-4", "project-5", "project-6"] ;
+        "exclude intersection"
+    )]
+    fn filter_tags(selectors: Vec<TargetSelector>, expected: &[&str]) {
+        let resolver = make_project(
+            &[
+                ("packages/project-0", "packages/project-1"),
+                ("packages/project-0", "project-5"),
+                ("packages/project-1", "project-2"),
+                ("packages/project-1", "project-4"),
+            ],
+            &["project-3", "project-5/packages/project-6"],
+            None,
+            TestChangeDetector::new(&[]),
+        );
+        resolver
+            .workspace_tags
+            .set(
+                [
+                    ("project-1", vec!["ui"]),
+                    ("project-4", vec!["server"]),
+                    ("project-5", vec!["internal", "ui"]),
+                ]
+                .into_iter()
+                .map(|(name, tags)| {
+                    (
+                        WorkspaceName::from(name),
+                        tags.into_iter().map(|tag| tag.to_string()).collect(),
+                    )
+                })
+                .collect(),
+            )
+            .unwrap();
+
+        let packages = resolver.get_filtered_packages(selectors).unwrap();
+
+        assert_eq!(
+            packages,
+            expected.iter().map(|s| WorkspaceName::from(*s)).collect()
+        );
+    }
+
     #[test]
     fn match_exact() {
         let resolver = make_project(
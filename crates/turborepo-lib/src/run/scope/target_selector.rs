@@ -16,9 +16,46 @@ pub struct TargetSelector {
     pub name_pattern: String,
     pub from_ref: String,
     pub to_ref_override: String,
+    pub tag: Option<String>,
+    pub package_json_field: Option<FieldSelector>,
+    pub intersection: Vec<TargetSelector>,
     pub raw: String,
 }
 
+/// Matches a top-level package.json field. If no value is given, the field
+/// matches as long as it is present and not `false` or `null`. For array
+/// fields such as `keywords`, the field matches if any element equals the
+/// value.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct FieldSelector {
+    pub name: String,
+    pub value: Option<String>,
+}
+
+impl FieldSelector {
+    pub fn is_match(&self, field: &serde_json::Value) -> bool {
+        use serde_json::Value;
+
+        fn scalar_matches(expected: &str, field: &Value) -> bool {
+            match field {
+                Value::String(s) => s == expected,
+                Value::Bool(b) => b.to_string() == expected,
+                Value::Number(n) => n.to_string() == expected,
+                Value::Null | Value::Array(_) | Value::Object(_) => false,
+            }
+        }
+
+        match (&self.value, field) {
+            (None, Value::Null | Value::Bool(false)) => false,
+            (None, _) => true,
+            (Some(expected), Value::Array(items)) => {
+                items.iter().any(|item| scalar_matches(expected, item))
+            }
+            (Some(expected), field) => scalar_matches(expected, field),
+        }
+    }
+}
+
 impl TargetSelector {
     pub fn to_ref(&self) -> &str {
         if self.to_ref_override.is_empty() {
@@ -33,6 +70,41 @@ impl TargetSelector {
         !self.from_ref.is_empty()
             || self.parent_dir != AnchoredSystemPathBuf::default()
             || !self.name_pattern.is_empty()
+            || self.tag.is_some()
+            || self.package_json_field.is_some()
+            || !self.intersection.is_empty()
+    }
+
+    /// Parses a selector of the form `a&b&c`, where each operand is a
+    /// selector on its own. A package is selected if every operand selects it.
+    fn parse_intersection(
+        raw_selector: &str,
+        exclude: bool,
+        selector: &str,
+    ) -> Result<Self, InvalidSelectorError> {
+        let intersection = selector
+            .split('&')
+            .map(|operand| {
+                if operand.is_empty() {
+                    Err(InvalidSelectorError::EmptyIntersectionOperand(
+                        raw_selector.to_string(),
+                    ))
+                } else if operand.starts_with('!') {
+                    Err(InvalidSelectorError::ExcludeInIntersection(
+                        raw_selector.to_string(),
+                    ))
+                } else {
+                    TargetSelector::from_str(operand)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TargetSelector {
+            exclude,
+            intersection,
+            raw: raw_selector.to_string(),
+            ..Default::default()
+        })
     }
 }
 
@@ -46,6 +118,10 @@ impl FromStr for TargetSelector {
             None => (false, raw_selector),
         };
 
+        if selector.contains('&') {
+            return Self::parse_intersection(raw_selector, exclude, selector);
+        }
+
         let mut exclude_self = false;
         let include_dependencies = selector.strip_suffix("...");
 
@@ -78,6 +154,45 @@ impl FromStr for TargetSelector {
             (false, selector)
         };
 
+        if let Some(tag) = selector.strip_prefix("tag:") {
+            if tag.is_empty() {
+                return Err(InvalidSelectorError::EmptyTag);
+            }
+            return Ok(TargetSelector {
+                exclude,
+                exclude_self,
+                include_dependencies,
+                include_dependents,
+                tag: Some(tag.to_string()),
+                raw: raw_selector.to_string(),
+                ..Default::default()
+            });
+        }
+
+        if let Some(field) = selector.strip_prefix("field:") {
+            let (name, value) = match field.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (field, None),
+            };
+            if name.is_empty() || value.as_deref() == Some("") {
+                return Err(InvalidSelectorError::InvalidFieldSelector(
+                    raw_selector.to_string(),
+                ));
+            }
+            return Ok(TargetSelector {
+                exclude,
+                exclude_self,
+                include_dependencies,
+                include_dependents,
+                package_json_field: Some(FieldSelector {
+                    name: name.to_string(),
+                    value,
+                }),
+                raw: raw_selector.to_string(),
+                ..Default::default()
+            });
+        }
+
         let re = Regex::new(r"^(?P<name>[^.](?:[^{}\[\]]*[^{}\[\].])?)?(\{(?P<directory>[^}]*)})?(?P<commits>(?:\.{3})?\[[^\]]+\])?$").expect("valid");
         let captures = re.captures(selector);
 
@@ -178,8 +293,28 @@ pub enum InvalidSelectorError {
     InvalidAnchoredPath(String),
     #[error("empty path specification")]
     EmptyPathSpecification,
+    #[error("empty tag specification, use \"tag:<tag>\" to select workspaces by tag")]
+    EmptyTag,
+    #[error(
+        "invalid package.json field selector \"{0}\", use \"field:<name>\" or \
+         \"field:<name>=<value>\""
+    )]
+    InvalidFieldSelector(String),
+    #[error(
+        "selector \"{0}\" has an empty operand, intersections must be written as \
+         \"<selector>&<selector>\""
+    )]
+    EmptyIntersectionOperand(String),
+    #[error(
+        "selector \"{0}\" excludes inside an intersection, use \"!\" at the start of the selector \
+         to exclude the whole intersection"
+    )]
+    ExcludeInIntersection(String),
 
-    #[error("selector \"{0}\" must have a reference, directory, or name pattern")]
+    #[error(
+        "selector \"{0}\" must have a reference, directory, name pattern, tag (\"tag:<tag>\") or \
+         package.json field (\"field:<name>[=<value>]\")"
+    )]
     InvalidSelector(String),
 }
 
@@ -208,10 +343,11 @@ pub fn is_selector_by_location(
 mod test {
     use std::str::FromStr;
 
+    use serde_json::json;
     use test_case::test_case;
     use turbopath::AnchoredSystemPathBuf;
 
-    use super::TargetSelector;
+    use super::{FieldSelector, TargetSelector};
 
     #[test_case("foo", TargetSelector { name_pattern: "foo".to_string(), raw: "foo".to_string(), ..Default::default() }; "foo")]
     #[test_case("foo...", TargetSelector { name_pattern: "foo".to_string(), raw: "foo...".to_string(), include_dependencies: true, ..Default::default() }; "foo dot dot dot")]
@@ -235,6 +371,12 @@ mod test {
     #[test_case("foo...[master]", TargetSelector { raw: "foo...[master]".to_string(), from_ref: "master".to_string(), name_pattern: "foo".to_string(), match_dependencies: true, ..Default::default() }; "foo...[master]")]
     #[test_case("foo...[master]...", TargetSelector { raw: "foo...[master]...".to_string(), from_ref: "master".to_string(), name_pattern: "foo".to_string(), match_dependencies: true, include_dependencies: true, ..Default::default() }; "foo...[master] dot dot dot")]
     #[test_case("{foo}...[master]", TargetSelector { raw: "{foo}...[master]".to_string(), from_ref: "master".to_string(), parent_dir: AnchoredSystemPathBuf::try_from("foo").unwrap(), match_dependencies: true, ..Default::default() }; "curly brackets foo...[master]")]
+    #[test_case("tag:ui", TargetSelector { raw: "tag:ui".to_string(), tag: Some("ui".to_string()), ..Default::default() }; "tag")]
+    #[test_case("...tag:ui", TargetSelector { raw: "...tag:ui".to_string(), tag: Some("ui".to_string()), include_dependents: true, ..Default::default() }; "dot dot dot tag")]
+    #[test_case("field:private=true", TargetSelector { raw: "field:private=true".to_string(), package_json_field: Some(FieldSelector { name: "private".to_string(), value: Some("true".to_string()) }), ..Default::default() }; "field with value")]
+    #[test_case("field:keywords", TargetSelector { raw: "field:keywords".to_string(), package_json_field: Some(FieldSelector { name: "keywords".to_string(), value: None }), ..Default::default() }; "field without value")]
+    #[test_case("!tag:ui&./apps/*", TargetSelector { raw: "!tag:ui&./apps/*".to_string(), exclude: true, intersection: vec![TargetSelector { raw: "tag:ui".to_string(), tag: Some("ui".to_string()), ..Default::default() }, TargetSelector { raw: "./apps/*".to_string(), parent_dir: AnchoredSystemPathBuf::try_from("./apps/*").unwrap(), ..Default::default() }], ..Default::default() }; "excluded intersection")]
+    #[test_case("foo...&field:private=true", TargetSelector { raw: "foo...&field:private=true".to_string(), intersection: vec![TargetSelector { raw: "foo...".to_string(), name_pattern: "foo".to_string(), include_dependencies: true, ..Default::default() }, TargetSelector { raw: "field:private=true".to_string(), package_json_field: Some(FieldSelector { name: "private".to_string(), value: Some("true".to_string()) }), ..Default::default() }], ..Default::default() }; "intersection with dependencies")]
     fn parse_target_selector(raw_selector: &str, want: TargetSelector) {
         let result = TargetSelector::from_str(raw_selector);
 
@@ -252,8 +394,30 @@ mod test {
         }
     }
 
+    #[test_case(None, json!(true), true ; "present")]
+    #[test_case(None, json!(false), false ; "false")]
+    #[test_case(None, json!(null), false ; "null")]
+    #[test_case(Some("true"), json!(true), true ; "bool value")]
+    #[test_case(Some("true"), json!("true"), true ; "string value")]
+    #[test_case(Some("2"), json!(2), true ; "number value")]
+    #[test_case(Some("ui"), json!(["react", "ui"]), true ; "array contains")]
+    #[test_case(Some("ui"), json!(["react"]), false ; "array does not contain")]
+    #[test_case(Some("ui"), json!({"ui": true}), false ; "object")]
+    fn field_selector_matches(value: Option<&str>, field: serde_json::Value, expected: bool) {
+        let selector = FieldSelector {
+            name: "field".to_string(),
+            value: value.map(|v| v.to_string()),
+        };
+        assert_eq!(selector.is_match(&field), expected);
+    }
+
     #[test_case("{}" ; "curly brackets")]
     #[test_case("......[master]" ; "......[master]")]
+    #[test_case("tag:" ; "empty tag")]
+    #[test_case("field:" ; "empty field")]
+    #[test_case("field:private=" ; "empty field value")]
+    #[test_case("foo&" ; "empty intersection operand")]
+    #[test_case("foo&!bar" ; "exclude in intersection")]
     fn parse_target_selector_invalid(raw_selector: &str) {
         let result = TargetSelector::from_str(raw_selector);
 
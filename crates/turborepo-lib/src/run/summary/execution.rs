@@ -1,17 +1,27 @@
-use std::fmt;
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
 
 use chrono::{DateTime, Duration, Local};
 use serde::{ser::SerializeStruct, Serialize};
 use tokio::sync::mpsc;
-use turbopath::AnchoredSystemPath;
+use turbopath::{AbsoluteSystemPath, AnchoredSystemPath};
 
-use crate::run::task_id::TaskId;
+use super::{
+    profile::{PhaseSpan, TaskPhase, TaskTimeline, Trace},
+    Error,
+};
+use crate::{
+    engine::{Engine, TaskNode},
+    process::ResourceUsage,
+    run::task_id::TaskId,
+};
 
 // Just used to make changing the type that gets passed to the state management
 // thread easy
 type Message = Event;
 
-// Should *not* be exposed outside of run summary module
 /// The execution summary
 #[derive(Debug, Serialize)]
 pub struct ExecutionSummary<'a> {
@@ -20,6 +30,9 @@ pub struct ExecutionSummary<'a> {
     state_thread: tokio::task::JoinHandle<SummaryState>,
     #[serde(skip)]
     sender: mpsc::Sender<Message>,
+    // what each finished tracker recorded, for the profile
+    #[serde(skip)]
+    timelines: Arc<Mutex<Vec<TaskTimeline>>>,
     command: String,
     package_inference_path: Option<&'a AnchoredSystemPath>,
     started_at: DateTime<Local>,
@@ -51,8 +64,9 @@ pub struct Tracker<T> {
     sender: mpsc::Sender<Message>,
     started_at: T,
     // task_id is only used as a name for the event in the chrometracing profile
-    #[allow(dead_code)]
     task_id: TaskId<'static>,
+    phases: Vec<PhaseSpan>,
+    timelines: Arc<Mutex<Vec<TaskTimeline>>>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize)]
@@ -71,11 +85,24 @@ enum ExecutionState {
     BuildFailed { exit_code: u32, err: String },
 }
 
+impl ExecutionState {
+    fn outcome(&self) -> &'static str {
+        match self {
+            ExecutionState::Canceled => "canceled",
+            ExecutionState::Built { .. } => "built",
+            ExecutionState::Cached => "cached",
+            ExecutionState::BuildFailed { .. } => "failed",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TaskExecutionSummary {
     started_at: DateTime<Local>,
     ended_at: DateTime<Local>,
     state: ExecutionState,
+    // only available for tasks that ran to completion
+    resource_usage: Option<ResourceUsage>,
 }
 
 impl Serialize for TaskExecutionSummary {
@@ -84,6 +111,9 @@ impl Serialize for TaskExecutionSummary {
         state.serialize_field("startedAt", &self.started_at.timestamp_millis())?;
         state.serialize_field("endedAt", &self.ended_at.timestamp_millis())?;
         state.serialize_field("state", &self.state)?;
+        if let Some(resource_usage) = &self.resource_usage {
+            state.serialize_field("resourceUsage", resource_usage)?;
+        }
 
         state.end()
     }
@@ -102,6 +132,10 @@ impl TaskExecutionSummary {
     pub fn duration(&self) -> Duration {
         self.ended_at.signed_duration_since(self.started_at)
     }
+
+    pub fn resource_usage(&self) -> Option<&ResourceUsage> {
+        self.resource_usage.as_ref()
+    }
 }
 
 impl<'a> ExecutionSummary<'a> {
@@ -124,6 +158,7 @@ impl<'a> ExecutionSummary<'a> {
         Self {
             state_thread,
             sender,
+            timelines: Arc::default(),
             command,
             package_inference_path,
             started_at,
@@ -136,9 +171,30 @@ impl<'a> ExecutionSummary<'a> {
             sender: self.sender.clone(),
             task_id,
             started_at: (),
+            phases: Vec::new(),
+            timelines: self.timelines.clone(),
         }
     }
 
+    /// Writes a Chrome trace of every task that has finished to `path`
+    pub fn write_profile(&self, path: &AbsoluteSystemPath, engine: &Engine) -> Result<(), Error> {
+        let timelines = self.timelines.lock().expect("lock poisoned");
+        let trace = Trace::new(&self.command, self.started_at, &timelines, |task_id| {
+            engine
+                .dependencies(task_id)
+                .into_iter()
+                .flatten()
+                .filter_map(|node| match node {
+                    TaskNode::Task(task_id) => Some(task_id),
+                    TaskNode::Root => None,
+                })
+                .collect()
+        });
+        path.ensure_dir()?;
+        path.create_with_contents(serde_json::to_string(&trace)?)?;
+        Ok(())
+    }
+
     pub async fn finish(self) -> Result<SummaryState, tokio::task::JoinError> {
         let Self {
             state_thread,
@@ -154,11 +210,26 @@ impl<'a> ExecutionSummary<'a> {
     }
 }
 
+impl<T> Tracker<T> {
+    /// Records that `phase` of the task ran from `started_at` until now
+    pub fn record_phase(&mut self, phase: TaskPhase, started_at: DateTime<Local>) {
+        self.phases.push(PhaseSpan {
+            phase,
+            started_at,
+            ended_at: Local::now(),
+        });
+    }
+}
+
 impl Tracker<()> {
     // Start the tracker
     pub async fn start(self) -> Tracker<DateTime<Local>> {
         let Tracker {
-            sender, task_id, ..
+            sender,
+            task_id,
+            phases,
+            timelines,
+            ..
         } = self;
         let started_at = Local::now();
         sender
@@ -169,70 +240,85 @@ impl Tracker<()> {
             sender,
             started_at,
             task_id,
+            phases,
+            timelines,
         }
     }
 }
 
 impl Tracker<chrono::DateTime<Local>> {
     pub fn cancel(self) -> TaskExecutionSummary {
-        let Self { started_at, .. } = self;
-        TaskExecutionSummary {
-            started_at,
-            ended_at: Local::now(),
-            state: ExecutionState::Canceled,
-        }
+        self.finish(ExecutionState::Canceled, None)
     }
 
     pub async fn cached(self) -> TaskExecutionSummary {
-        let Self {
-            sender, started_at, ..
-        } = self;
-        sender
+        self.sender
             .send(Event::Cached)
             .await
             .expect("summary state thread finished");
-        TaskExecutionSummary {
-            started_at,
-            ended_at: Local::now(),
-            state: ExecutionState::Cached,
-        }
+        self.finish(ExecutionState::Cached, None)
     }
 
-    pub async fn build_succeeded(self, exit_code: u32) -> TaskExecutionSummary {
-        let Self {
-            sender, started_at, ..
-        } = self;
-        sender
+    pub async fn build_succeeded(
+        self,
+        exit_code: u32,
+        resource_usage: Option<ResourceUsage>,
+    ) -> TaskExecutionSummary {
+        self.sender
             .send(Event::Built)
             .await
             .expect("summary state thread finished");
-        TaskExecutionSummary {
-            started_at,
-            ended_at: Local::now(),
-            state: ExecutionState::Built { exit_code },
-        }
+        self.finish(ExecutionState::Built { exit_code }, resource_usage)
     }
 
     pub async fn build_failed(
         self,
         exit_code: u32,
         error: impl fmt::Display,
+        resource_usage: Option<ResourceUsage>,
     ) -> TaskExecutionSummary {
-        let Self {
-            sender, started_at, ..
-        } = self;
-        sender
+        self.sender
             .send(Event::BuildFailed)
             .await
             .expect("summary state thread finished");
-        TaskExecutionSummary {
-            started_at,
-            ended_at: Local::now(),
-            state: ExecutionState::BuildFailed {
+        self.finish(
+            ExecutionState::BuildFailed {
                 exit_code,
                 err: error.to_string(),
             },
-        }
+            resource_usage,
+        )
+    }
+
+    // Adds the task to the timelines used for the profile
+    fn finish(
+        self,
+        state: ExecutionState,
+        resource_usage: Option<ResourceUsage>,
+    ) -> TaskExecutionSummary {
+        let Self {
+            started_at,
+            task_id,
+            phases,
+            timelines,
+            ..
+        } = self;
+        let summary = TaskExecutionSummary {
+            started_at,
+            ended_at: Local::now(),
+            state,
+            resource_usage,
+        };
+        timelines.lock().expect("lock poisoned").push(TaskTimeline {
+            task_id,
+            started_at,
+            ended_at: summary.ended_at,
+            phases,
+            outcome: summary.state.outcome(),
+            exit_code: summary.exit_code(),
+            resource_usage,
+        });
+        summary
     }
 }
 
@@ -248,7 +334,7 @@ mod test {
             let tracker = summary.tracker(TaskId::new("foo", "build"));
             tasks.push(tokio::spawn(async move {
                 let tracker = tracker.start().await;
-                let summary = tracker.build_succeeded(0).await;
+                let summary = tracker.build_succeeded(0, None).await;
                 assert_eq!(summary.exit_code(), Some(0));
             }));
         }
@@ -264,7 +350,7 @@ mod test {
             let tracker = summary.tracker(TaskId::new("baz", "build"));
             tasks.push(tokio::spawn(async move {
                 let tracker = tracker.start().await;
-                let summary = tracker.build_failed(1, "big bad error").await;
+                let summary = tracker.build_failed(1, "big bad error", None).await;
                 assert_eq!(summary.exit_code(), Some(1));
             }));
         }
@@ -297,7 +383,7 @@ mod test {
         let tracker = tracker.start().await;
 
         tokio::time::sleep(sleep_duration.to_std().unwrap()).await;
-        let summary = tracker.build_succeeded(0).await;
+        let summary = tracker.build_succeeded(0, None).await;
         assert!(
             post_construction_time < summary.started_at,
             "tracker start time should start when start is called"
@@ -307,4 +393,52 @@ mod test {
             "tracker duration should be at least as long as the time between calls"
         );
     }
+
+    #[tokio::test]
+    async fn test_resource_usage() {
+        let summary = ExecutionSummary::new("turbo run build".to_string(), None, Local::now());
+        let usage = ResourceUsage {
+            max_rss_bytes: 1024,
+            user_time_ms: 20,
+            system_time_ms: 10,
+            read_bytes: 512,
+            written_bytes: 4096,
+        };
+
+        let tracker = summary.tracker(TaskId::new("foo", "build")).start().await;
+        let built = tracker.build_succeeded(0, Some(usage)).await;
+        assert_eq!(built.resource_usage(), Some(&usage));
+        let json = serde_json::to_value(&built).unwrap();
+        assert_eq!(json["resourceUsage"]["maxRssBytes"], 1024);
+        assert_eq!(json["resourceUsage"]["writtenBytes"], 4096);
+
+        let tracker = summary.tracker(TaskId::new("bar", "build")).start().await;
+        let cached = tracker.cached().await;
+        let json = serde_json::to_value(&cached).unwrap();
+        assert!(json.get("resourceUsage").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_timelines() {
+        let summary = ExecutionSummary::new("turbo run build".to_string(), None, Local::now());
+
+        let mut tracker = summary.tracker(TaskId::new("foo", "build"));
+        tracker.record_phase(TaskPhase::Hash, Local::now());
+        let mut tracker = tracker.start().await;
+        tracker.record_phase(TaskPhase::Execution, Local::now());
+        tracker.build_failed(1, "big bad error", None).await;
+
+        let timelines = summary.timelines.lock().unwrap();
+        assert_eq!(timelines.len(), 1);
+        let timeline = &timelines[0];
+        assert_eq!(timeline.task_id, TaskId::new("foo", "build"));
+        assert_eq!(timeline.outcome, "failed");
+        assert_eq!(timeline.exit_code, Some(1));
+        let phases = timeline
+            .phases
+            .iter()
+            .map(|span| span.phase)
+            .collect::<Vec<_>>();
+        assert_eq!(phases, vec![TaskPhase::Hash, TaskPhase::Execution]);
+    }
 }
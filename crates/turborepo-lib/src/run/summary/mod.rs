@@ -1,29 +1,31 @@
 #[allow(dead_code)]
 mod execution;
 mod global_hash;
+mod profile;
 mod scm;
 mod spaces;
 mod task;
 
 use std::{collections::HashSet, io, io::Write};
 
-use chrono::Local;
+pub use execution::{ExecutionSummary, Tracker};
 pub use global_hash::GlobalHashSummary;
 use itertools::Itertools;
+pub use profile::TaskPhase;
 use serde::Serialize;
 use svix_ksuid::{Ksuid, KsuidLike};
 use tabwriter::TabWriter;
 use thiserror::Error;
-use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 use turborepo_ci::Vendor;
 use turborepo_env::EnvironmentVariableMap;
-use turborepo_ui::{color, cprintln, cwriteln, BOLD, BOLD_CYAN, GREY, UI};
+use turborepo_ui::{color, cprintln, cwriteln, Redactor, BOLD, BOLD_CYAN, GREY, UI};
 
 use crate::{
     cli::EnvMode,
     opts::RunOpts,
     package_graph::{PackageGraph, WorkspaceName},
-    run::summary::{execution::ExecutionSummary, scm::SCMState, task::TaskSummary},
+    run::summary::{scm::SCMState, task::TaskSummary},
 };
 
 #[derive(Debug, Error)]
@@ -68,6 +70,8 @@ pub struct RunSummary<'a> {
     should_save: bool,
     run_type: RunType,
     synthesized_command: String,
+    // Masks the values of secret env vars
+    redactor: Redactor,
 }
 
 #[derive(Debug, Serialize)]
@@ -90,9 +94,8 @@ pub struct RunSummaryInner<'a> {
 impl<'a> RunSummary<'a> {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        start_at: chrono::DateTime<Local>,
+        execution_summary: ExecutionSummary<'a>,
         repo_root: &'a AbsoluteSystemPath,
-        package_inference_root: Option<&'a AnchoredSystemPath>,
         turbo_version: &'static str,
         run_opts: &RunOpts,
         packages: HashSet<WorkspaceName>,
@@ -113,12 +116,6 @@ impl<'a> RunSummary<'a> {
             RunType::Real
         };
 
-        let execution_summary = ExecutionSummary::new(
-            synthesized_command.clone(),
-            package_inference_root,
-            start_at,
-        );
-
         RunSummary {
             inner: RunSummaryInner {
                 id: Ksuid::new(None, None),
@@ -140,9 +137,17 @@ impl<'a> RunSummary<'a> {
             should_save,
 
             synthesized_command,
+            redactor: Redactor::default(),
         }
     }
 
+    /// Redacts any secrets known to `redactor` when the summary is printed or
+    /// saved
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
     pub fn close(
         &mut self,
         _exit_code: u32,
@@ -261,7 +266,13 @@ impl<'a> RunSummary<'a> {
                 cwriteln!(tab_writer, ui, GREY, "  Package\t=\t{}", package)?;
             }
 
-            cwriteln!(tab_writer, ui, GREY, "  Command\t=\t{}", task.command)?;
+            cwriteln!(
+                tab_writer,
+                ui,
+                GREY,
+                "  Command\t=\t{}",
+                self.redactor.redact(&task.command)
+            )?;
             cwriteln!(
                 tab_writer,
                 ui,
@@ -391,10 +402,11 @@ impl<'a> RunSummary<'a> {
 
     fn save(&self) -> Result<(), Error> {
         let json = serde_json::to_string_pretty(&self.inner)?;
+        let json = self.redactor.redact(&json);
 
         let summary_path = self.get_path();
         summary_path.ensure_dir()?;
 
-        Ok(summary_path.create_with_contents(json)?)
+        Ok(summary_path.create_with_contents(json.as_bytes())?)
     }
 }
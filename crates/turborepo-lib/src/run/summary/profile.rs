@@ -0,0 +1,372 @@
+//! `profile`
+//!
+//! Renders the task timelines recorded by the execution summary as a Chrome
+//! trace, which can be loaded into Perfetto or chrome://tracing. Tasks are
+//! laid out on one track per concurrency slot, with their phases nested
+//! inside them and flow arrows drawn from each dependency to its dependents.
+
+use std::{collections::HashMap, fmt};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{process::ResourceUsage, run::task_id::TaskId};
+
+// All events belong to the one turbo process
+const PID: u32 = 1;
+
+/// The parts of a task that show up as their own spans in the profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskPhase {
+    Hash,
+    Execution,
+}
+
+impl fmt::Display for TaskPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TaskPhase::Hash => "hash",
+            TaskPhase::Execution => "execution",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PhaseSpan {
+    pub phase: TaskPhase,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+}
+
+/// Everything recorded for a task by the time its tracker finished
+#[derive(Debug)]
+pub struct TaskTimeline {
+    pub task_id: TaskId<'static>,
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub phases: Vec<PhaseSpan>,
+    pub outcome: &'static str,
+    pub exit_code: Option<u32>,
+    pub resource_usage: Option<ResourceUsage>,
+}
+
+impl TaskTimeline {
+    // Phases such as hashing can happen before the tracker is started, so a
+    // task spans its phases as well as its own start and end.
+    fn start(&self) -> DateTime<Local> {
+        self.phases
+            .iter()
+            .map(|span| span.started_at)
+            .fold(self.started_at, Ord::min)
+    }
+
+    fn end(&self) -> DateTime<Local> {
+        self.phases
+            .iter()
+            .map(|span| span.ended_at)
+            .fold(self.ended_at, Ord::max)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trace {
+    trace_events: Vec<TraceEvent>,
+    display_time_unit: &'static str,
+}
+
+// See https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+// for the meaning of each event type.
+#[derive(Debug, Serialize)]
+#[serde(tag = "ph")]
+enum TraceEvent {
+    #[serde(rename = "M")]
+    Metadata {
+        name: &'static str,
+        pid: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tid: Option<usize>,
+        args: MetadataArgs,
+    },
+    #[serde(rename = "X")]
+    Complete {
+        name: String,
+        cat: &'static str,
+        ts: i64,
+        dur: i64,
+        pid: u32,
+        tid: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        args: Option<TaskArgs>,
+    },
+    #[serde(rename = "s")]
+    FlowStart {
+        name: &'static str,
+        cat: &'static str,
+        id: usize,
+        ts: i64,
+        pid: u32,
+        tid: usize,
+    },
+    #[serde(rename = "f")]
+    FlowEnd {
+        name: &'static str,
+        cat: &'static str,
+        id: usize,
+        ts: i64,
+        pid: u32,
+        tid: usize,
+        // binds the arrow to the slice that encloses it
+        bp: &'static str,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct MetadataArgs {
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskArgs {
+    outcome: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<u32>,
+    #[serde(flatten)]
+    resource_usage: Option<ResourceUsage>,
+}
+
+impl Trace {
+    /// Builds a trace of `timelines`, with timestamps relative to
+    /// `started_at`. `dependencies` gives the tasks each task depends on.
+    pub fn new<'b>(
+        command: &str,
+        started_at: DateTime<Local>,
+        timelines: &'b [TaskTimeline],
+        dependencies: impl Fn(&TaskId<'static>) -> Vec<&'b TaskId<'static>>,
+    ) -> Self {
+        let micros = |time: DateTime<Local>| {
+            time.signed_duration_since(started_at)
+                .num_microseconds()
+                .unwrap_or_default()
+        };
+
+        let slots = assign_slots(timelines);
+        let slot_count = slots.iter().map(|slot| slot + 1).max().unwrap_or_default();
+
+        let mut trace_events = vec![TraceEvent::Metadata {
+            name: "process_name",
+            pid: PID,
+            tid: None,
+            args: MetadataArgs {
+                name: command.to_string(),
+            },
+        }];
+        trace_events.extend((0..slot_count).map(|slot| TraceEvent::Metadata {
+            name: "thread_name",
+            pid: PID,
+            tid: Some(slot),
+            args: MetadataArgs {
+                name: format!("slot {}", slot + 1),
+            },
+        }));
+
+        for (timeline, &tid) in timelines.iter().zip(&slots) {
+            let ts = micros(timeline.start());
+            trace_events.push(TraceEvent::Complete {
+                name: timeline.task_id.to_string(),
+                cat: "task",
+                ts,
+                dur: micros(timeline.end()) - ts,
+                pid: PID,
+                tid,
+                args: Some(TaskArgs {
+                    outcome: timeline.outcome,
+                    exit_code: timeline.exit_code,
+                    resource_usage: timeline.resource_usage,
+                }),
+            });
+            trace_events.extend(timeline.phases.iter().map(|span| {
+                let ts = micros(span.started_at);
+                TraceEvent::Complete {
+                    name: span.phase.to_string(),
+                    cat: "phase",
+                    ts,
+                    dur: micros(span.ended_at) - ts,
+                    pid: PID,
+                    tid,
+                    args: None,
+                }
+            }));
+        }
+
+        let positions = timelines
+            .iter()
+            .zip(&slots)
+            .map(|(timeline, &slot)| (&timeline.task_id, (timeline, slot)))
+            .collect::<HashMap<_, _>>();
+        let mut id = 0;
+        for (timeline, &tid) in timelines.iter().zip(&slots) {
+            for dependency in dependencies(&timeline.task_id) {
+                // Dependencies that didn't run this time have nothing to
+                // point from
+                let Some((dependency, dependency_tid)) = positions.get(dependency) else {
+                    continue;
+                };
+                id += 1;
+                // The flow has to start within the dependency's slice for it
+                // to be bound to it, so we back off from the very end.
+                let start = micros(dependency.start());
+                trace_events.push(TraceEvent::FlowStart {
+                    name: "dependency",
+                    cat: "dependency",
+                    id,
+                    ts: (micros(dependency.end()) - 1).max(start),
+                    pid: PID,
+                    tid: *dependency_tid,
+                });
+                trace_events.push(TraceEvent::FlowEnd {
+                    name: "dependency",
+                    cat: "dependency",
+                    id,
+                    ts: micros(timeline.start()),
+                    pid: PID,
+                    tid,
+                    bp: "e",
+                });
+            }
+        }
+
+        Self {
+            trace_events,
+            display_time_unit: "ms",
+        }
+    }
+}
+
+// Gives each task the lowest slot that is free when it starts, so the number
+// of slots matches the most tasks that were running at once.
+fn assign_slots(timelines: &[TaskTimeline]) -> Vec<usize> {
+    let mut order = (0..timelines.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| timelines[i].start());
+
+    let mut slots = vec![0; timelines.len()];
+    let mut slot_ends: Vec<DateTime<Local>> = Vec::new();
+    for i in order {
+        let timeline = &timelines[i];
+        let slot = match slot_ends
+            .iter()
+            .position(|&ended_at| ended_at <= timeline.start())
+        {
+            Some(slot) => slot,
+            None => {
+                slot_ends.push(timeline.end());
+                slot_ends.len() - 1
+            }
+        };
+        slot_ends[slot] = timeline.end();
+        slots[i] = slot;
+    }
+    slots
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, Duration, Local};
+    use serde_json::Value;
+
+    use super::{assign_slots, PhaseSpan, TaskPhase, TaskTimeline, Trace};
+    use crate::run::task_id::TaskId;
+
+    fn timeline(
+        task_id: TaskId<'static>,
+        run_start: DateTime<Local>,
+        start_ms: i64,
+        end_ms: i64,
+    ) -> TaskTimeline {
+        let started_at = run_start + Duration::milliseconds(start_ms);
+        let ended_at = run_start + Duration::milliseconds(end_ms);
+        TaskTimeline {
+            task_id,
+            started_at,
+            ended_at,
+            phases: vec![PhaseSpan {
+                phase: TaskPhase::Execution,
+                started_at,
+                ended_at,
+            }],
+            outcome: "built",
+            exit_code: Some(0),
+            resource_usage: None,
+        }
+    }
+
+    #[test]
+    fn test_assign_slots() {
+        let now = Local::now();
+        let timelines = vec![
+            timeline(TaskId::new("a", "build"), now, 0, 10),
+            timeline(TaskId::new("b", "build"), now, 5, 20),
+            // starts once `a` has finished, so it can reuse its slot
+            timeline(TaskId::new("c", "build"), now, 10, 15),
+            timeline(TaskId::new("d", "build"), now, 12, 30),
+        ];
+        assert_eq!(assign_slots(&timelines), vec![0, 1, 0, 2]);
+    }
+
+    #[test]
+    fn test_trace() {
+        let now = Local::now();
+        let lib = TaskId::new("lib", "build");
+        let app = TaskId::new("app", "build");
+        let mut lib_timeline = timeline(lib.clone(), now, 0, 10);
+        // hashing happens before the task is started
+        lib_timeline.phases.insert(
+            0,
+            PhaseSpan {
+                phase: TaskPhase::Hash,
+                started_at: now - Duration::milliseconds(2),
+                ended_at: now,
+            },
+        );
+        let timelines = vec![lib_timeline, timeline(app.clone(), now, 10, 25)];
+
+        let trace = Trace::new("turbo run build", now, &timelines, |task_id| {
+            if task_id == &app {
+                vec![&timelines[0].task_id]
+            } else {
+                vec![]
+            }
+        });
+        let json = serde_json::to_value(&trace).unwrap();
+        let events = json["traceEvents"].as_array().unwrap();
+        let with_phase = |ph: &str| {
+            events
+                .iter()
+                .filter(|event| event["ph"] == ph)
+                .collect::<Vec<&Value>>()
+        };
+
+        let tasks = with_phase("X")
+            .into_iter()
+            .filter(|event| event["cat"] == "task")
+            .collect::<Vec<_>>();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0]["name"], "lib#build");
+        assert_eq!(tasks[0]["ts"], -2000);
+        assert_eq!(tasks[0]["dur"], 12000);
+        assert_eq!(tasks[0]["args"]["outcome"], "built");
+        // lib finished when app started, so they share a slot
+        assert_eq!(tasks[0]["tid"], tasks[1]["tid"]);
+        assert_eq!(with_phase("X").len(), 5);
+
+        let starts = with_phase("s");
+        let ends = with_phase("f");
+        assert_eq!(starts.len(), 1);
+        assert_eq!(ends.len(), 1);
+        assert_eq!(starts[0]["id"], ends[0]["id"]);
+        assert_eq!(starts[0]["ts"], 9999);
+        assert_eq!(ends[0]["ts"], 10000);
+        assert_eq!(ends[0]["bp"], "e");
+    }
+}
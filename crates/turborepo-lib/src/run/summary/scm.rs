@@ -7,6 +7,7 @@ use turborepo_scm::SCM;
 #[derive(Debug, Deserialize, Serialize)]
 enum SCMType {
     Git,
+    Sapling,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -36,9 +37,12 @@ impl SCMState {
             }
         }
 
-        // Fall back to using `git`
+        // Fall back to asking source control
         if state.branch.is_none() && state.sha.is_none() {
             let scm = SCM::new(dir);
+            if let SCM::Sapling(_) = scm {
+                state.ty = SCMType::Sapling;
+            }
 
             if state.branch.is_none() {
                 state.branch = scm.get_current_branch(dir).ok();
@@ -3,7 +3,10 @@ mod visitor;
 use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
-use turbopath::{AnchoredSystemPath, RelativeUnixPathBuf};
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, RelativeUnixPathBuf,
+};
+use turborepo_env::EnvironmentVariableMap;
 pub use visitor::{Error, Visitor};
 
 use crate::{
@@ -50,6 +53,8 @@ pub struct TaskDefinitionStable {
     pub(crate) env: Vec<String>,
     pub(crate) pass_through_env: Option<Vec<String>>,
     pub(crate) dot_env: Vec<RelativeUnixPathBuf>,
+    pub(crate) load_dot_env: bool,
+    pub(crate) secret_env: Vec<String>,
 }
 
 impl Default for TaskDefinitionStable {
@@ -65,6 +70,8 @@ impl Default for TaskDefinitionStable {
             env: Vec::new(),
             pass_through_env: None,
             dot_env: Vec::new(),
+            load_dot_env: false,
+            secret_env: Vec::new(),
         }
     }
 }
@@ -82,6 +89,14 @@ pub struct TaskDefinition {
 
     pub(crate) dot_env: Vec<RelativeUnixPathBuf>,
 
+    // LoadDotEnv injects the variables from the DotEnv files into the task's
+    // environment, rather than only hashing the files
+    pub(crate) load_dot_env: bool,
+
+    // SecretEnv lists the variables whose values are redacted from task logs and
+    // run summaries
+    pub(crate) secret_env: Vec<String>,
+
     // TopologicalDependencies are tasks from package dependencies.
     // E.g. "build" is a topological dependency in:
     // dependsOn: ['^build'].
@@ -151,6 +166,8 @@ impl Default for TaskDefinition {
             output_mode: Default::default(),
             persistent: Default::default(),
             dot_env: Default::default(),
+            load_dot_env: Default::default(),
+            secret_env: Default::default(),
         }
     }
 }
@@ -169,7 +186,59 @@ macro_rules! set_field {
     }};
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum DotEnvError {
+    #[error("unable to read {path}: {source}")]
+    Io {
+        path: AbsoluteSystemPathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("unable to parse {path}: {source}")]
+    Parse {
+        path: AbsoluteSystemPathBuf,
+        #[source]
+        source: turborepo_env::Error,
+    },
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
+}
+
 impl TaskDefinition {
+    /// Reads the variables defined in this task's `dotEnv` files, which are
+    /// relative to `workspace_dir`. Files listed first take precedence and
+    /// files that don't exist are skipped, matching how they are hashed.
+    pub fn dot_env_vars(
+        &self,
+        workspace_dir: &AbsoluteSystemPath,
+    ) -> Result<EnvironmentVariableMap, DotEnvError> {
+        let mut vars = EnvironmentVariableMap::default();
+        for dot_env in self.dot_env.iter().rev() {
+            let path = workspace_dir.join_unix_path(dot_env)?;
+            let contents = match path.read_to_string() {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(source) => return Err(DotEnvError::Io { path, source }),
+            };
+            let file_vars = turborepo_env::parse_dot_env(&contents)
+                .map_err(|source| DotEnvError::Parse { path, source })?;
+            vars.union(&file_vars);
+        }
+
+        Ok(vars)
+    }
+
+    /// Values of this task's `secretEnv` variables that are set in `env`
+    pub fn secret_values<'a>(
+        &'a self,
+        env: &'a EnvironmentVariableMap,
+    ) -> impl Iterator<Item = String> + 'a {
+        self.secret_env
+            .iter()
+            .filter_map(|name| env.get(name))
+            .cloned()
+    }
+
     pub fn hashable_outputs(&self, task_name: &TaskId) -> TaskOutputs {
         let mut inclusion_outputs = vec![format!(".turbo/turbo-{}.log", task_name.task())];
         inclusion_outputs.extend_from_slice(&self.outputs.inclusions[..]);
@@ -234,6 +303,8 @@ impl TaskDefinition {
                 env,
                 pass_through_env,
                 dot_env,
+                load_dot_env,
+                secret_env,
             },
             _experimental,
         ) = other.split();
@@ -248,6 +319,8 @@ impl TaskDefinition {
         set_field!(self, meta, env, "Env");
         set_field!(self, meta, pass_through_env, "PassThroughEnv");
         set_field!(self, meta, dot_env, "DotEnv");
+        set_field!(self, meta, load_dot_env, "LoadDotEnv");
+        set_field!(self, meta, secret_env, "SecretEnv");
     }
 }
 
@@ -264,9 +337,42 @@ impl FromIterator<BookkeepingTaskDefinition> for TaskDefinition {
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
 
     use super::*;
 
+    #[test]
+    fn test_dot_env_vars() {
+        let dir = tempdir().unwrap();
+        let workspace_dir = AbsoluteSystemPath::from_std_path(dir.path()).unwrap();
+        workspace_dir
+            .join_component(".env.local")
+            .create_with_contents("API_URL=http://localhost\nTOKEN=local")
+            .unwrap();
+        workspace_dir
+            .join_component(".env")
+            .create_with_contents("API_URL=https://example.com\nREGION=us-east-1")
+            .unwrap();
+
+        let task_defn = TaskDefinition {
+            dot_env: [".env.local", ".env", ".env.missing"]
+                .into_iter()
+                .map(|path| RelativeUnixPathBuf::new(path).unwrap())
+                .collect(),
+            secret_env: vec!["TOKEN".to_string(), "UNSET".to_string()],
+            ..Default::default()
+        };
+
+        let vars = task_defn.dot_env_vars(workspace_dir).unwrap();
+        assert_eq!(vars.len(), 3);
+        assert_eq!(vars["API_URL"], "http://localhost");
+        assert_eq!(vars["REGION"], "us-east-1");
+        assert_eq!(
+            task_defn.secret_values(&vars).collect::<Vec<_>>(),
+            vec!["local".to_string()]
+        );
+    }
+
     #[test]
     fn test_relative_output_globs() {
         let task_defn = TaskDefinition {
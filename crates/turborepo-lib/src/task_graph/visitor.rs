@@ -6,15 +6,16 @@ use std::{
     time::Duration,
 };
 
+use chrono::Local;
 use console::{Style, StyledObject};
 use futures::{stream::FuturesUnordered, StreamExt};
 use regex::Regex;
 use tokio::{process::Command, sync::mpsc};
-use tracing::{debug, error};
+use tracing::{debug, error, field, Instrument, Span};
 use turbopath::AbsoluteSystemPath;
 use turborepo_env::{EnvironmentVariableMap, ResolvedEnvMode};
 use turborepo_ui::{
-    ColorSelector, OutputClient, OutputSink, OutputWriter, PrefixedUI, PrefixedWriter, UI,
+    ColorSelector, OutputClient, OutputSink, OutputWriter, PrefixedUI, PrefixedWriter, Redactor, UI,
 };
 
 use crate::{
@@ -22,11 +23,12 @@ use crate::{
     engine::{Engine, ExecutionOptions, StopExecution},
     opts::Opts,
     package_graph::{PackageGraph, WorkspaceName},
-    process::{ChildExit, ProcessManager},
+    process::{ChildExit, ProcessManager, ResourceUsage},
     run::{
         task_id::{self, TaskId},
-        RunCache,
+        ExecutionSummary, RunCache, TaskPhase,
     },
+    task_graph::DotEnvError,
     task_hash::{self, PackageInputsHashes, TaskHashTrackerState, TaskHasher},
 };
 
@@ -36,12 +38,14 @@ pub struct Visitor<'a> {
     package_graph: Arc<PackageGraph>,
     opts: &'a Opts<'a>,
     task_hasher: TaskHasher<'a>,
+    env_at_execution_start: &'a EnvironmentVariableMap,
     global_env_mode: EnvMode,
     sink: OutputSink<StdWriter>,
     color_cache: ColorSelector,
     ui: UI,
     manager: ProcessManager,
     repo_root: &'a AbsoluteSystemPath,
+    execution_summary: &'a ExecutionSummary<'a>,
     dry: bool,
 }
 
@@ -81,6 +85,7 @@ impl<'a> Visitor<'a> {
         silent: bool,
         manager: ProcessManager,
         repo_root: &'a AbsoluteSystemPath,
+        execution_summary: &'a ExecutionSummary<'a>,
     ) -> Self {
         let task_hasher = TaskHasher::new(
             package_inputs_hashes,
@@ -96,12 +101,14 @@ impl<'a> Visitor<'a> {
             package_graph,
             opts,
             task_hasher,
+            env_at_execution_start,
             global_env_mode,
             sink,
             color_cache,
             ui,
             manager,
             repo_root,
+            execution_summary,
             dry: false,
         }
     }
@@ -115,6 +122,13 @@ impl<'a> Visitor<'a> {
         };
         let mut tasks = FuturesUnordered::new();
         let errors = Arc::new(Mutex::new(Vec::new()));
+        // Only one task can read our stdin, so we only hand it over when
+        // there's a single persistent task that could want it
+        let persistent_tasks = engine
+            .task_definitions()
+            .values()
+            .filter(|task_definition| task_definition.persistent)
+            .count();
 
         while let Some(message) = node_stream.recv().await {
             let crate::engine::Message { info, callback } = message;
@@ -173,6 +187,7 @@ impl<'a> Visitor<'a> {
 
             let dependency_set = engine.dependencies(&info).ok_or(Error::MissingDefinition)?;
 
+            let hash_started_at = Local::now();
             let task_hash = self.task_hasher.calculate_task_hash(
                 &info,
                 task_definition,
@@ -198,6 +213,45 @@ impl<'a> Visitor<'a> {
             // bail if the script doesn't exist
             let Some(_command) = command else { continue };
 
+            let mut tracker = self.execution_summary.tracker(info.clone());
+            tracker.record_phase(TaskPhase::Hash, hash_started_at);
+
+            let workspace_directory = self.repo_root.resolve(workspace_dir);
+
+            let dot_env_vars = if task_definition.load_dot_env {
+                task_definition.dot_env_vars(&workspace_directory)
+            } else {
+                Ok(EnvironmentVariableMap::default())
+            };
+            let mut dot_env = match dot_env_vars {
+                Ok(dot_env) => dot_env,
+                // A broken .env file only affects the task that loads it, so
+                // we fail the task and let the rest of the run carry on.
+                Err(e) => {
+                    tracker.start().await.build_failed(1, &e, None).await;
+                    errors
+                        .lock()
+                        .expect("lock poisoned")
+                        .push(TaskError::from_dot_env(self.display_task_id(&info), e));
+                    callback
+                        .send(if self.opts.run_opts.continue_on_error {
+                            Ok(())
+                        } else {
+                            Err(StopExecution)
+                        })
+                        .ok();
+                    continue;
+                }
+            };
+            // Variables that are already set take precedence over .env files
+            dot_env.difference(self.env_at_execution_start);
+
+            let redactor = Redactor::new(
+                task_definition
+                    .secret_values(self.env_at_execution_start)
+                    .chain(task_definition.secret_values(&dot_env)),
+            );
+
             let output_client = self.output_client();
             let continue_on_error = self.opts.run_opts.continue_on_error;
             let prefix = self.prefix(&info);
@@ -205,110 +259,162 @@ impl<'a> Visitor<'a> {
             let ui = self.ui;
             let manager = self.manager.clone();
             let package_manager = self.package_graph.package_manager().clone();
-            let workspace_directory = self.repo_root.resolve(workspace_dir);
             let errors = errors.clone();
             let task_id_for_display = self.display_task_id(&info);
+            let use_pty = self.opts.run_opts.pty;
+            let should_forward_stdin =
+                use_pty && task_definition.persistent && persistent_tasks == 1;
+            let task_span = tracing::info_span!(
+                "execute_task",
+                task = %task_id_for_display,
+                max_rss_bytes = field::Empty,
+                user_time_ms = field::Empty,
+                system_time_ms = field::Empty,
+                read_bytes = field::Empty,
+                written_bytes = field::Empty,
+            );
+
+            tasks.push(tokio::spawn(
+                async move {
+                    let task_id = info;
+                    let _task_cache = task_cache;
+                    let mut tracker = tracker.start().await;
+                    let mut prefixed_ui = Self::prefixed_ui(
+                        ui,
+                        is_github_actions,
+                        &output_client,
+                        pretty_prefix.clone(),
+                    );
+
+                    let mut cmd = Command::new(package_manager.to_string());
+                    cmd.args(["run", task_id.task()]);
+                    cmd.current_dir(workspace_directory.as_path());
+                    cmd.envs(dot_env.iter());
+                    cmd.stdout(Stdio::piped());
+                    cmd.stderr(Stdio::piped());
+
+                    let execution_started_at = Local::now();
+                    let spawned = if use_pty {
+                        manager.spawn_pty(cmd, Duration::from_millis(500))
+                    } else {
+                        manager.spawn(cmd, Duration::from_millis(500))
+                    };
+                    let mut process = match spawned {
+                        Some(Ok(child)) => child,
+                        // Turbo was unable to spawn a process
+                        Some(Err(e)) => {
+                            // Note: we actually failed to spawn, but this matches the Go output
+                            prefixed_ui.error(format!("command finished with error: {e}"));
+                            tracker.record_phase(TaskPhase::Execution, execution_started_at);
+                            tracker.build_failed(1, &e, None).await;
+                            errors
+                                .lock()
+                                .expect("lock poisoned")
+                                .push(TaskError::from_spawn(task_id_for_display.clone(), e));
+                            callback
+                                .send(if continue_on_error {
+                                    Ok(())
+                                } else {
+                                    Err(StopExecution)
+                                })
+                                .ok();
+                            return;
+                        }
+                        // Turbo is shutting down
+                        None => {
+                            tracker.cancel();
+                            callback.send(Ok(())).ok();
+                            return;
+                        }
+                    };
 
-            tasks.push(tokio::spawn(async move {
-                let task_id = info;
-                let _task_cache = task_cache;
-                let mut prefixed_ui =
-                    Self::prefixed_ui(ui, is_github_actions, &output_client, pretty_prefix.clone());
-
-                let mut cmd = Command::new(package_manager.to_string());
-                cmd.args(["run", task_id.task()]);
-                cmd.current_dir(workspace_directory.as_path());
-                cmd.stdout(Stdio::piped());
-                cmd.stderr(Stdio::piped());
-
-                let mut process = match manager.spawn(cmd, Duration::from_millis(500)) {
-                    Some(Ok(child)) => child,
-                    // Turbo was unable to spawn a process
-                    Some(Err(e)) => {
-                        // Note: we actually failed to spawn, but this matches the Go output
-                        prefixed_ui.error(format!("command finished with error: {e}"));
-                        errors
-                            .lock()
-                            .expect("lock poisoned")
-                            .push(TaskError::from_spawn(task_id_for_display.clone(), e));
-                        callback
-                            .send(if continue_on_error {
-                                Ok(())
-                            } else {
-                                Err(StopExecution)
-                            })
-                            .ok();
-                        return;
-                    }
-                    // Turbo is shutting down
-                    None => {
-                        callback.send(Ok(())).ok();
-                        return;
-                    }
-                };
-
-                let exit_status = match process
-                    .wait_with_piped_outputs(
-                        PrefixedWriter::new(ui, pretty_prefix.clone(), output_client.stdout()),
-                        PrefixedWriter::new(ui, pretty_prefix.clone(), output_client.stderr()),
-                    )
-                    .await
-                {
-                    Ok(Some(exit_status)) => exit_status,
-                    Err(e) => {
-                        error!("unable to pipe outputs from command: {e}");
-                        callback.send(Err(StopExecution)).ok();
-                        manager.stop().await;
-                        return;
-                    }
-                    Ok(None) => {
-                        // TODO: how can this happen? we only update the
-                        // exit status with Some and it is only initialized with
-                        // None. Is it still running?
-                        error!("unable to determine why child exited");
-                        callback.send(Err(StopExecution)).ok();
-                        return;
+                    if should_forward_stdin {
+                        if let Some(input) = process.pty_input() {
+                            forward_stdin(input);
+                        }
                     }
-                };
-
-                match exit_status {
-                    // The task was successful, nothing special needs to happen.
-                    ChildExit::Finished(Some(0)) => (),
-                    ChildExit::Finished(Some(code)) => {
-                        let error =
-                            TaskErrorCause::from_execution(process.label().to_string(), code);
-                        if continue_on_error {
-                            prefixed_ui.warn("command finished with error, but continuing...");
-                            callback.send(Ok(())).ok();
-                        } else {
-                            prefixed_ui.error(format!("command finished with error: {error}"));
+
+                    let result = process
+                        .wait_with_piped_outputs(
+                            PrefixedWriter::new(ui, pretty_prefix.clone(), output_client.stdout())
+                                .with_redactor(redactor.clone()),
+                            PrefixedWriter::new(ui, pretty_prefix.clone(), output_client.stderr())
+                                .with_redactor(redactor),
+                        )
+                        .await;
+                    tracker.record_phase(TaskPhase::Execution, execution_started_at);
+
+                    let exit_status = match result {
+                        Ok(Some(exit_status)) => exit_status,
+                        Err(e) => {
+                            error!("unable to pipe outputs from command: {e}");
+                            tracker.cancel();
                             callback.send(Err(StopExecution)).ok();
                             manager.stop().await;
+                            return;
                         }
-                        errors.lock().expect("lock poisoned").push(TaskError {
-                            task_id: task_id_for_display.clone(),
-                            cause: error,
-                        });
-                        return;
+                        Ok(None) => {
+                            // TODO: how can this happen? we only update the
+                            // exit status with Some and it is only initialized with
+                            // None. Is it still running?
+                            error!("unable to determine why child exited");
+                            tracker.cancel();
+                            callback.send(Err(StopExecution)).ok();
+                            return;
+                        }
+                    };
+
+                    let resource_usage = process.resource_usage();
+                    if let Some(usage) = &resource_usage {
+                        debug!("task {} resource usage: {:?}", task_id, usage);
+                        record_resource_usage(&Span::current(), usage);
                     }
-                    // All of these indicate a failure where we don't know how to recover
-                    ChildExit::Finished(None)
-                    | ChildExit::Killed
-                    | ChildExit::KilledExternal
-                    | ChildExit::Failed => {
-                        callback.send(Err(StopExecution)).ok();
+
+                    match exit_status {
+                        ChildExit::Finished(Some(0)) => {
+                            tracker.build_succeeded(0, resource_usage).await;
+                        }
+                        ChildExit::Finished(Some(code)) => {
+                            let error =
+                                TaskErrorCause::from_execution(process.label().to_string(), code);
+                            tracker
+                                .build_failed(code as u32, &error, resource_usage)
+                                .await;
+                            if continue_on_error {
+                                prefixed_ui.warn("command finished with error, but continuing...");
+                                callback.send(Ok(())).ok();
+                            } else {
+                                prefixed_ui.error(format!("command finished with error: {error}"));
+                                callback.send(Err(StopExecution)).ok();
+                                manager.stop().await;
+                            }
+                            errors.lock().expect("lock poisoned").push(TaskError {
+                                task_id: task_id_for_display.clone(),
+                                cause: error,
+                            });
+                            return;
+                        }
+                        // All of these indicate a failure where we don't know how to recover
+                        ChildExit::Finished(None)
+                        | ChildExit::Killed
+                        | ChildExit::KilledExternal
+                        | ChildExit::Failed => {
+                            tracker.cancel();
+                            callback.send(Err(StopExecution)).ok();
+                            return;
+                        }
+                    }
+
+                    if let Err(e) = output_client.finish() {
+                        error!("unable to flush output client: {e}");
+                        callback.send(Err(StopExecution)).unwrap();
                         return;
                     }
-                }
 
-                if let Err(e) = output_client.finish() {
-                    error!("unable to flush output client: {e}");
-                    callback.send(Err(StopExecution)).unwrap();
-                    return;
+                    callback.send(Ok(())).unwrap();
                 }
-
-                callback.send(Ok(())).unwrap();
-            }));
+                .instrument(task_span),
+            ));
         }
 
         // Wait for the engine task to finish and for all of our tasks to finish
@@ -451,6 +557,28 @@ fn turbo_regex() -> &'static Regex {
     RE.get_or_init(|| Regex::new(r"(?:^|\s)turbo(?:$|\s)").unwrap())
 }
 
+// Attaches a task's resource usage to its span, so that it is included in
+// profiles
+fn record_resource_usage(span: &Span, usage: &ResourceUsage) {
+    span.record("max_rss_bytes", usage.max_rss_bytes);
+    span.record("user_time_ms", usage.user_time_ms);
+    span.record("system_time_ms", usage.system_time_ms);
+    span.record("read_bytes", usage.read_bytes);
+    span.record("written_bytes", usage.written_bytes);
+}
+
+// Copies our stdin to a task's input. Reading stdin blocks, so this gets its
+// own thread rather than tying up the runtime. It stops once the task goes
+// away and writing to it fails.
+fn forward_stdin(mut input: std::fs::File) {
+    std::thread::spawn(move || {
+        let stdin = std::io::stdin();
+        if let Err(e) = std::io::copy(&mut stdin.lock(), &mut input) {
+            debug!("stopped forwarding stdin: {e}");
+        }
+    });
+}
+
 // Error that comes from the execution of the task
 #[derive(Debug, thiserror::Error, Clone)]
 #[error("{task_id}: {cause}")]
@@ -466,6 +594,8 @@ enum TaskErrorCause {
     Spawn { msg: String },
     #[error("command {command} exited ({exit_code})")]
     Exit { command: String, exit_code: i32 },
+    #[error("unable to load environment variables: {msg}")]
+    DotEnv { msg: String },
 }
 
 impl TaskError {
@@ -491,6 +621,15 @@ impl TaskError {
             cause: TaskErrorCause::Exit { command, exit_code },
         }
     }
+
+    fn from_dot_env(task_id: String, err: DotEnvError) -> Self {
+        Self {
+            task_id,
+            cause: TaskErrorCause::DotEnv {
+                msg: err.to_string(),
+            },
+        }
+    }
 }
 
 impl TaskErrorCause {
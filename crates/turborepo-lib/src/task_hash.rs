@@ -3,13 +3,14 @@ use std::{
     sync::{Arc, Mutex},
 };
 
+use itertools::Itertools;
 use rayon::prelude::*;
 use serde::Serialize;
 use thiserror::Error;
 use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, AnchoredSystemPathBuf};
 use turborepo_env::{BySource, DetailedMap, EnvironmentVariableMap, ResolvedEnvMode};
-use turborepo_scm::SCM;
+use turborepo_scm::{GitHashes, SCM};
 
 use crate::{
     engine::TaskNode,
@@ -53,6 +54,10 @@ impl TaskHashable<'_> {
     }
 }
 
+/// File hashes computed ahead of time, such as by the daemon, keyed by package
+/// path and inputs
+pub type PrecomputedFileHashes = HashMap<(AnchoredSystemPathBuf, Vec<String>), GitHashes>;
+
 #[derive(Debug, Default)]
 pub struct PackageInputsHashes {
     hashes: HashMap<TaskId<'static>, String>,
@@ -60,12 +65,36 @@ pub struct PackageInputsHashes {
 }
 
 impl PackageInputsHashes {
+    /// Collects the distinct package paths and inputs that
+    /// `calculate_file_hashes` hashes for the given tasks.
+    pub fn file_hash_inputs<'a>(
+        all_tasks: impl Iterator<Item = &'a TaskNode>,
+        workspaces: &HashMap<&WorkspaceName, &WorkspaceInfo>,
+        task_definitions: &HashMap<TaskId<'static>, TaskDefinition>,
+    ) -> Vec<(AnchoredSystemPathBuf, Vec<String>)> {
+        all_tasks
+            .filter_map(|task| {
+                let TaskNode::Task(task_id) = task else {
+                    return None;
+                };
+                let task_definition = task_definitions.get(task_id)?;
+                let pkg = workspaces.get(&task_id.to_workspace_name())?;
+                Some((
+                    pkg.package_path().to_owned(),
+                    task_definition.inputs.clone(),
+                ))
+            })
+            .unique()
+            .collect()
+    }
+
     pub fn calculate_file_hashes<'a>(
         scm: &SCM,
         all_tasks: impl ParallelIterator<Item = &'a TaskNode>,
         workspaces: HashMap<&WorkspaceName, &WorkspaceInfo>,
         task_definitions: &HashMap<TaskId<'static>, TaskDefinition>,
         repo_root: &AbsoluteSystemPath,
+        precomputed: &PrecomputedFileHashes,
     ) -> Result<PackageInputsHashes, Error> {
         let (hashes, expanded_hashes): (HashMap<_, _>, HashMap<_, _>) = all_tasks
             .filter_map(|task| {
@@ -96,13 +125,18 @@ impl PackageInputsHashes {
                     .parent()
                     .unwrap_or_else(|| AnchoredSystemPath::new("").unwrap());
 
-                let mut hash_object = match scm.get_package_file_hashes(
-                    repo_root,
-                    package_path,
-                    &task_definition.inputs,
-                ) {
-                    Ok(hash_object) => hash_object,
-                    Err(err) => return Some(Err(err.into())),
+                let precomputed_hashes =
+                    precomputed.get(&(package_path.to_owned(), task_definition.inputs.clone()));
+                let mut hash_object = match precomputed_hashes {
+                    Some(hash_object) => hash_object.clone(),
+                    None => match scm.get_package_file_hashes(
+                        repo_root,
+                        package_path,
+                        &task_definition.inputs,
+                    ) {
+                        Ok(hash_object) => hash_object,
+                        Err(err) => return Some(Err(err.into())),
+                    },
                 };
 
                 if !task_definition.dot_env.is_empty() {
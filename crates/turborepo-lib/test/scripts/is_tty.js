@@ -0,0 +1,2 @@
+const describe = (stream) => (stream.isTTY ? "tty" : "pipe");
+console.log(`stdout: ${describe(process.stdout)}, stderr: ${describe(process.stderr)}`);
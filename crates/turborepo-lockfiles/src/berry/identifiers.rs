@@ -247,6 +247,29 @@ impl<'a> Locator<'a> {
             })
     }
 
+    /// The start of the file name yarn gives this package's archive in its
+    /// cache. Archives are named `{ident}-{protocol}-{version}-{hash}`, but
+    /// the hash depends on yarn internals so we only match what comes before
+    /// it.
+    pub fn cache_prefix(&self) -> String {
+        let mut prefix = match self.ident.scope.as_deref() {
+            Some(scope) => format!("@{scope}-{}-", self.ident.name),
+            None => format!("{}-", self.ident.name),
+        };
+        let (protocol, selector) = self
+            .reference
+            .split_once(':')
+            .unwrap_or(("exotic", &self.reference));
+        prefix.push_str(protocol);
+        prefix.push('-');
+        // Yarn only includes the version if the selector is an exact semver version
+        if semver::Version::parse(selector).is_ok() {
+            prefix.push_str(selector);
+            prefix.push('-');
+        }
+        prefix
+    }
+
     pub fn patched_locator(&self) -> Option<Locator> {
         // THis has an issue of cutting off the last char
         Locator::from_patch_reference(&self.reference)
@@ -419,4 +442,31 @@ mod test {
             assert_eq!(patch_locator, expected, "{}", tc.locator);
         }
     }
+
+    #[test]
+    fn test_locator_cache_prefix() {
+        struct TestCase {
+            locator: &'static str,
+            prefix: &'static str,
+        }
+        let test_cases = [
+            TestCase {
+                locator: "react@npm:18.2.0",
+                prefix: "react-npm-18.2.0-",
+            },
+            TestCase {
+                locator: "@babel/core@npm:7.20.12",
+                prefix: "@babel-core-npm-7.20.12-",
+            },
+            TestCase {
+                locator: "resolve@patch:resolve@npm%3A1.22.1#~builtin<compat/resolve>::version=1.\
+                          22.1&hash=07638b",
+                prefix: "resolve-patch-",
+            },
+        ];
+        for tc in test_cases {
+            let locator = Locator::try_from(tc.locator).unwrap();
+            assert_eq!(locator.cache_prefix(), tc.prefix, "{}", tc.locator);
+        }
+    }
 }
@@ -19,6 +19,10 @@ use turbopath::RelativeUnixPathBuf;
 
 use self::resolution::{parse_resolution, Resolution};
 use super::Lockfile;
+use crate::{
+    convert::{Graph, Resolved},
+    PackageDetails,
+};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -230,6 +234,106 @@ impl BerryLockfile {
         Ok(LockfileData { metadata, packages })
     }
 
+    pub(crate) fn from_graph(graph: &Graph) -> Result<Self, Error> {
+        let mut packages = Map::new();
+
+        for node in graph.packages.values() {
+            let name = &node.details.name;
+            let descriptors = node
+                .ranges()
+                .into_iter()
+                .map(|range| match range.contains(':') {
+                    true => format!("{name}@{range}"),
+                    false => format!("{name}@npm:{range}"),
+                })
+                .collect::<Vec<_>>();
+            let dependencies = node
+                .details
+                .dependencies
+                .iter()
+                .chain(&node.details.optional_dependencies)
+                .filter(|(dependency, _)| node.dependencies.contains_key(*dependency))
+                .map(|(dependency, range)| (dependency.clone(), SemverString(range.clone())))
+                .collect::<Map<_, _>>();
+            let dependencies_meta = node
+                .details
+                .optional_dependencies
+                .keys()
+                .filter(|dependency| node.is_optional(dependency))
+                .map(|dependency| (dependency.clone(), DependencyMeta::optional()))
+                .collect::<Map<_, _>>();
+            packages.insert(
+                descriptors.join(", "),
+                BerryPackage {
+                    version: SemverString(node.details.version.clone()),
+                    resolution: format!("{name}@npm:{}", node.details.version),
+                    dependencies: (!dependencies.is_empty()).then_some(dependencies),
+                    dependencies_meta: (!dependencies_meta.is_empty()).then_some(dependencies_meta),
+                    language_name: Some("node".into()),
+                    link_type: Some("hard".into()),
+                    ..Default::default()
+                },
+            );
+        }
+
+        for workspace in graph.workspaces {
+            let path = match workspace.path.as_str() {
+                "" => ".",
+                path => path,
+            };
+            let locator = format!("{}@workspace:{path}", workspace.name);
+            // Other workspaces refer to this workspace with the ranges they
+            // declare for it
+            let mut descriptors = graph
+                .workspaces
+                .iter()
+                .flat_map(|dependent| {
+                    dependent.all_dependencies().filter(|(name, _)| {
+                        matches!(
+                            graph.workspace_dependency(&dependent.path, name),
+                            Some(Resolved::Workspace(dependency)) if dependency == &workspace.path
+                        )
+                    })
+                })
+                .map(|(name, range)| format!("{name}@{range}"))
+                .chain(iter::once(locator.clone()))
+                .collect::<Vec<_>>();
+            descriptors.sort();
+            descriptors.dedup();
+
+            let dependencies = workspace
+                .all_dependencies()
+                .map(|(name, range)| (name.clone(), SemverString(range.clone())))
+                .collect::<Map<_, _>>();
+            let dependencies_meta = workspace
+                .optional_dependencies
+                .keys()
+                .map(|dependency| (dependency.clone(), DependencyMeta::optional()))
+                .collect::<Map<_, _>>();
+            packages.insert(
+                descriptors.join(", "),
+                BerryPackage {
+                    version: SemverString("0.0.0-use.local".into()),
+                    resolution: locator,
+                    dependencies: (!dependencies.is_empty()).then_some(dependencies),
+                    dependencies_meta: (!dependencies_meta.is_empty()).then_some(dependencies_meta),
+                    language_name: Some("unknown".into()),
+                    link_type: Some("soft".into()),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let data = LockfileData {
+            metadata: Metadata {
+                version: 6,
+                cache_key: None,
+            },
+            packages,
+        };
+        Self::new(data, None)
+    }
+
     /// Produces a new lockfile containing only the given workspaces and
     /// packages
     pub fn subgraph(
@@ -532,6 +636,44 @@ impl Lockfile for BerryLockfile {
         }))
     }
 
+    fn package_details(&self, key: &str) -> Result<Option<PackageDetails>, crate::Error> {
+        let locator = Locator::try_from(key).map_err(Error::from)?;
+        let Some(package) = self.locator_package.get(&locator) else {
+            return Ok(None);
+        };
+
+        let protocol = locator
+            .reference
+            .split_once(':')
+            .map(|(protocol, _)| protocol)
+            .filter(|protocol| *protocol != "npm")
+            .map(|protocol| protocol.to_string());
+        let is_optional = |name: &str| {
+            package
+                .dependencies_meta
+                .as_ref()
+                .and_then(|meta| meta.get(name))
+                .and_then(|meta| meta.optional)
+                .unwrap_or(false)
+        };
+        let (optional_dependencies, dependencies): (Map<_, _>, Map<_, _>) = package
+            .dependencies
+            .iter()
+            .flatten()
+            .map(|(name, range)| (name.clone(), range.as_ref().to_string()))
+            .partition(|(name, _)| is_optional(name));
+
+        Ok(Some(PackageDetails {
+            name: locator.ident.to_string(),
+            version: package.version.as_ref().to_string(),
+            resolved: None,
+            integrity: None,
+            protocol,
+            dependencies,
+            optional_dependencies,
+        }))
+    }
+
     fn encode(&self) -> Result<Vec<u8>, crate::Error> {
         Ok(self.lockfile()?.to_string().into_bytes())
     }
@@ -548,6 +690,10 @@ impl Lockfile for BerryLockfile {
         Ok(patches)
     }
 
+    fn lockfile_version(&self) -> Option<String> {
+        Some(self.data.metadata.version.to_string())
+    }
+
     fn global_change_key(&self) -> Vec<u8> {
         let mut buf = vec![b'b', b'e', b'r', b'r', b'y', 0];
 
@@ -564,6 +710,15 @@ impl Lockfile for BerryLockfile {
     }
 }
 
+impl DependencyMeta {
+    fn optional() -> Self {
+        Self {
+            optional: Some(true),
+            unplugged: None,
+        }
+    }
+}
+
 impl LockfileData {
     pub fn from_bytes(s: &[u8]) -> Result<Self, Error> {
         serde_yaml::from_slice(s).map_err(Error::from)
@@ -606,6 +761,28 @@ pub fn berry_subgraph(
     Ok(new_contents)
 }
 
+/// Returns the prefixes of the archives in yarn's cache that are needed to
+/// install the given lockfile keys. Patched packages also need the archive of
+/// the package they patch.
+pub fn berry_cache_prefixes<'a>(
+    keys: impl IntoIterator<Item = &'a str>,
+) -> Result<HashSet<String>, Error> {
+    let mut prefixes = HashSet::new();
+    for key in keys {
+        let locator = Locator::try_from(key)?;
+        if let Some(original) = locator.patched_locator() {
+            prefixes.insert(original.cache_prefix());
+        }
+        prefixes.insert(locator.cache_prefix());
+    }
+    Ok(prefixes)
+}
+
+/// Returns the package name of a lockfile key
+pub fn berry_package_name(key: &str) -> Result<String, Error> {
+    Ok(Locator::try_from(key)?.ident.to_string())
+}
+
 pub fn berry_global_change(prev_contents: &[u8], curr_contents: &[u8]) -> Result<bool, Error> {
     let prev_data = LockfileData::from_bytes(prev_contents)?;
     let curr_data = LockfileData::from_bytes(curr_contents)?;
@@ -980,4 +1157,24 @@ mod test {
              resolve@patch:resolve@^1.22.2#~builtin<compat/resolve>"
         );
     }
+
+    #[test]
+    fn test_cache_prefixes() {
+        let prefixes = berry_cache_prefixes([
+            "@babel/core@npm:7.20.12",
+            "resolve@patch:resolve@npm%3A1.22.3#~builtin<compat/resolve>::version=1.22.3&\
+             hash=c3c19d",
+        ])
+        .unwrap();
+        let mut prefixes = prefixes.into_iter().collect::<Vec<_>>();
+        prefixes.sort();
+        assert_eq!(
+            prefixes,
+            vec![
+                "@babel-core-npm-7.20.12-".to_string(),
+                "resolve-npm-1.22.3-".to_string(),
+                "resolve-patch-".to_string(),
+            ]
+        );
+    }
 }
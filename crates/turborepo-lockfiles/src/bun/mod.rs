@@ -1,45 +1,19 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
-use serde::Deserialize;
-
-use crate::Lockfile;
-
-mod de;
-
-type Map<K, V> = std::collections::BTreeMap<K, V>;
-
-#[derive(Debug, thiserror::Error)]
-pub enum Error {
-    #[error("unable to parse: {0}")]
-    SymlParse(String),
-    #[error("unable to convert to structured syml: {0}")]
-    SymlStructure(#[from] serde_json::Error),
-    #[error("unexpected non-utf8 yarn.lock")]
-    NonUTF8(#[from] std::str::Utf8Error),
-    #[error("Turborepo cannot serialize Bun lockfiles.")]
-    NotImplemented(),
-}
+use crate::{Lockfile, PackageDetails, Yarn1Lockfile};
 
+/// The yarn v1 style text form of a `bun.lockb`, as printed by `bun
+/// bun.lockb`. Apart from its header this is a yarn v1 lockfile, so parsing
+/// and encoding are left to `Yarn1Lockfile`.
 pub struct BunLockfile {
-    inner: Map<String, Entry>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Entry {
-    name: Option<String>,
-    version: String,
-    uid: Option<String>,
-    resolved: Option<String>,
-    integrity: Option<String>,
-    registry: Option<String>,
-    dependencies: Option<Map<String, String>>,
-    optional_dependencies: Option<Map<String, String>>,
+    // The `# bun ./bun.lockb --hash: ...` comment Bun adds to the header
+    hash_comment: Option<String>,
+    inner: Yarn1Lockfile,
 }
 
 impl BunLockfile {
     pub fn from_bytes(input: &[u8]) -> Result<Self, super::Error> {
-        let input = std::str::from_utf8(input).map_err(Error::from)?;
+        let input = std::str::from_utf8(input).map_err(crate::yarn1::Error::from)?;
         Self::from_str(input)
     }
 }
@@ -48,44 +22,48 @@ impl FromStr for BunLockfile {
     type Err = super::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let value = de::parse_syml(s)?;
-        let inner = serde_json::from_value(value)?;
-        Ok(Self { inner })
+        let inner = Yarn1Lockfile::from_str(s)?;
+        let hash_comment = s
+            .lines()
+            .take_while(|line| line.starts_with('#'))
+            .find(|line| line.starts_with("# bun "))
+            .map(|line| line.to_string());
+        Ok(Self {
+            hash_comment,
+            inner,
+        })
+    }
+}
+
+impl fmt::Display for BunLockfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            "# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.\n# yarn lockfile \
+             v1\n",
+        )?;
+        if let Some(hash_comment) = &self.hash_comment {
+            f.write_fmt(format_args!("{hash_comment}\n"))?;
+        }
+        f.write_str("\n")?;
+        self.inner.write_entries(f, true)
     }
 }
 
 impl Lockfile for BunLockfile {
     fn resolve_package(
         &self,
-        _workspace_path: &str,
+        workspace_path: &str,
         name: &str,
         version: &str,
     ) -> Result<Option<crate::Package>, crate::Error> {
-        for key in possible_keys(name, version) {
-            if let Some(entry) = self.inner.get(&key) {
-                return Ok(Some(crate::Package {
-                    key,
-                    version: entry.version.clone(),
-                }));
-            }
-        }
-
-        Ok(None)
+        self.inner.resolve_package(workspace_path, name, version)
     }
 
     fn all_dependencies(
         &self,
         key: &str,
     ) -> Result<Option<std::collections::HashMap<String, String>>, crate::Error> {
-        let Some(entry) = self.inner.get(key) else {
-            return Ok(None);
-        };
-
-        let all_deps: std::collections::HashMap<_, _> = entry.dependency_entries().collect();
-        Ok(match all_deps.is_empty() {
-            false => Some(all_deps),
-            true => None,
-        })
+        self.inner.all_dependencies(key)
     }
 
     fn subgraph(
@@ -93,51 +71,95 @@ impl Lockfile for BunLockfile {
         _workspace_packages: &[String],
         packages: &[String],
     ) -> Result<Box<dyn Lockfile>, super::Error> {
-        let mut inner = Map::new();
-
-        for (key, entry) in packages.iter().filter_map(|key| {
-            let entry = self.inner.get(key)?;
-            Some((key, entry))
-        }) {
-            inner.insert(key.clone(), entry.clone());
-        }
-
-        Ok(Box::new(Self { inner }))
+        // The hash is of the binary lockfile's contents so it no longer applies
+        // to the subgraph
+        Ok(Box::new(Self {
+            hash_comment: None,
+            inner: self.inner.subgraph_lockfile(packages),
+        }))
     }
 
     fn encode(&self) -> Result<Vec<u8>, crate::Error> {
-        Err(crate::Error::Bun(Error::NotImplemented()))
+        Ok(self.to_string().into_bytes())
     }
 
-    fn global_change_key(&self) -> Vec<u8> {
-        vec![b'b', b'u', b'n', 0]
+    fn package_details(&self, key: &str) -> Result<Option<PackageDetails>, crate::Error> {
+        self.inner.package_details(key)
     }
-}
 
-impl Entry {
-    fn dependency_entries(&self) -> impl Iterator<Item = (String, String)> + '_ {
-        self.dependencies
-            .iter()
-            .flatten()
-            .chain(self.optional_dependencies.iter().flatten())
-            .map(|(k, v)| (k.clone(), v.clone()))
+    fn global_change_key(&self) -> Vec<u8> {
+        vec![b'b', b'u', b'n', 0]
     }
 }
 
-const PROTOCOLS: &[&str] = ["", "npm:", "file:", "workspace:", "yarn:"].as_slice();
-
-fn possible_keys<'a>(name: &'a str, version: &'a str) -> impl Iterator<Item = String> + 'a {
-    PROTOCOLS
-        .iter()
-        .copied()
-        .map(move |protocol| format!("{name}@{protocol}{version}"))
-}
-
 #[cfg(test)]
 mod test {
+    use pretty_assertions::assert_eq;
+
     use super::*;
+
+    const BUN: &str = include_str!("../../fixtures/bun.lock");
     const FULL: &str = include_str!("../../fixtures/yarn1full.lock");
 
+    #[test]
+    fn test_roundtrip() {
+        let lockfile = BunLockfile::from_str(BUN).unwrap();
+        assert_eq!(BUN, lockfile.to_string());
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let lockfile = BunLockfile::from_str(BUN).unwrap();
+        let subgraph = lockfile
+            .subgraph(
+                &["apps/docs".into()],
+                &[
+                    "js-tokens@^3.0.0 || ^4.0.0".into(),
+                    "js-tokens@^4.0.0".into(),
+                    "loose-envify@^1.1.0".into(),
+                ],
+            )
+            .unwrap();
+        let encoded = String::from_utf8(subgraph.encode().unwrap()).unwrap();
+        assert_eq!(
+            encoded,
+            r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+"js-tokens@^3.0.0 || ^4.0.0", "js-tokens@^4.0.0":
+  version "4.0.0"
+  resolved "https://registry.npmjs.org/js-tokens/-/js-tokens-4.0.0.tgz"
+  integrity sha512-RdJUflcE3cUzKiMqQgsCu06FHv9hiqKO+iu4yqxSDQ9/pYHhd4cR/0EM4b9GbRQ1OoM2UCHqLlFd7oeYDbpvDQ==
+
+"loose-envify@^1.1.0":
+  version "1.4.0"
+  resolved "https://registry.npmjs.org/loose-envify/-/loose-envify-1.4.0.tgz"
+  integrity sha512-lyuxPGr/Wfhrlem2CL/UcnUc1zcqKAImBDzukY7Y5F/yQiNdko6+fRLevlw1HgMySw7f611UIY408EtxRSoK3Q==
+  dependencies:
+    js-tokens "^3.0.0 || ^4.0.0"
+"#
+        );
+    }
+
+    #[test]
+    fn test_subgraph_roundtrip() {
+        let lockfile = BunLockfile::from_str(BUN).unwrap();
+        // Bun quotes every key so they can be read back as JSON strings
+        let keys = BUN
+            .lines()
+            .filter_map(|line| line.strip_suffix(':'))
+            .filter(|line| !line.starts_with(' '))
+            .flat_map(|line| line.split(", "))
+            .map(|key| serde_json::from_str::<String>(key).unwrap())
+            .collect::<Vec<_>>();
+        let subgraph = lockfile.subgraph(&[], &keys).unwrap();
+        let encoded = String::from_utf8(subgraph.encode().unwrap()).unwrap();
+        let reparsed = BunLockfile::from_str(&encoded).unwrap();
+        assert_eq!(lockfile.inner.to_string(), reparsed.inner.to_string());
+        assert_eq!(reparsed.hash_comment, None);
+    }
+
     #[test]
     fn test_key_splitting() {
         let lockfile = BunLockfile::from_str(FULL).unwrap();
@@ -147,7 +169,7 @@ mod test {
             "@babel/types@^7.19.0",
         ] {
             assert!(
-                lockfile.inner.contains_key(key),
+                lockfile.package_details(key).unwrap().is_some(),
                 "missing {} in lockfile",
                 key
             );
@@ -0,0 +1,561 @@
+//! Conversion between lockfile formats.
+//!
+//! The source lockfile is walked starting from each workspace's dependencies
+//! to build a format independent graph of resolved packages which is then
+//! written out in the target format. Every package keeps the version it was
+//! resolved to so the target package manager doesn't need to re-resolve it.
+//!
+//! Anything without an equivalent in the target format, e.g. patches or git
+//! dependencies, is left out and reported back to the caller. The target
+//! package manager will resolve those on the next install.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+};
+
+use crate::{BerryLockfile, Error, Lockfile, NpmLockfile, PnpmLockfile, Yarn1Lockfile};
+
+type Map<K, V> = BTreeMap<K, V>;
+
+/// A package as it appears in a lockfile, independent of the lockfile's
+/// format
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PackageDetails {
+    pub name: String,
+    pub version: String,
+    // URL of the package tarball
+    pub resolved: Option<String>,
+    // Subresource integrity of the package tarball
+    pub integrity: Option<String>,
+    // Set if the package doesn't come from a registry e.g. "git" or "patch"
+    pub protocol: Option<String>,
+    // Dependency names to the ranges the package declares for them
+    pub dependencies: Map<String, String>,
+    pub optional_dependencies: Map<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockfileFormat {
+    Npm,
+    Pnpm,
+    Yarn1,
+    Berry,
+}
+
+/// The dependencies a workspace declares in its package.json
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WorkspaceManifest {
+    // Unix style path of the workspace relative to the repository root, empty
+    // for the root workspace
+    pub path: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub dependencies: Map<String, String>,
+    pub dev_dependencies: Map<String, String>,
+    pub optional_dependencies: Map<String, String>,
+}
+
+#[derive(Debug)]
+pub struct Conversion {
+    pub contents: Vec<u8>,
+    // Descriptions of everything that couldn't be carried over
+    pub unsupported: Vec<String>,
+}
+
+/// Converts `lockfile` to the `target` format
+pub fn convert(
+    lockfile: &dyn Lockfile,
+    workspaces: &[WorkspaceManifest],
+    target: LockfileFormat,
+) -> Result<Conversion, Error> {
+    let mut builder = GraphBuilder::new(lockfile, workspaces);
+    for patch in lockfile.patches()? {
+        builder
+            .unsupported
+            .insert(format!("patch {patch} cannot be converted"));
+    }
+    builder.resolve_workspaces()?;
+    let GraphBuilder {
+        graph, unsupported, ..
+    } = builder;
+
+    let contents = match target {
+        LockfileFormat::Npm => NpmLockfile::from_graph(&graph).encode()?,
+        LockfileFormat::Pnpm => PnpmLockfile::from_graph(&graph).encode()?,
+        LockfileFormat::Yarn1 => Yarn1Lockfile::from_graph(&graph).encode()?,
+        LockfileFormat::Berry => BerryLockfile::from_graph(&graph)?.encode()?,
+    };
+
+    Ok(Conversion {
+        contents,
+        unsupported: unsupported.into_iter().collect(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct PackageId {
+    pub name: String,
+    pub version: String,
+}
+
+/// What a workspace dependency resolved to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Resolved {
+    // Path of the workspace that is depended on
+    Workspace(String),
+    Package(PackageId),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Node {
+    pub details: PackageDetails,
+    // All of the ranges that were resolved to this package
+    pub ranges: BTreeSet<String>,
+    pub dependencies: Map<String, PackageId>,
+}
+
+pub(crate) struct Graph<'a> {
+    pub workspaces: &'a [WorkspaceManifest],
+    // Keyed by workspace path and then by dependency name
+    pub workspace_dependencies: Map<&'a str, Map<&'a str, Resolved>>,
+    pub packages: Map<PackageId, Node>,
+}
+
+struct GraphBuilder<'a> {
+    lockfile: &'a dyn Lockfile,
+    graph: Graph<'a>,
+    workspace_paths: HashMap<&'a str, &'a str>,
+    // Lockfile keys that have been visited, None if the package can't be converted
+    visited: HashMap<String, Option<PackageId>>,
+    unsupported: BTreeSet<String>,
+}
+
+impl<'a> GraphBuilder<'a> {
+    fn new(lockfile: &'a dyn Lockfile, workspaces: &'a [WorkspaceManifest]) -> Self {
+        Self {
+            lockfile,
+            graph: Graph {
+                workspaces,
+                workspace_dependencies: Map::new(),
+                packages: Map::new(),
+            },
+            workspace_paths: workspaces
+                .iter()
+                .map(|workspace| (workspace.name.as_str(), workspace.path.as_str()))
+                .collect(),
+            visited: HashMap::new(),
+            unsupported: BTreeSet::new(),
+        }
+    }
+
+    fn resolve_workspaces(&mut self) -> Result<(), Error> {
+        for workspace in self.graph.workspaces {
+            let mut resolved_dependencies = Map::new();
+            for (name, range) in workspace.all_dependencies() {
+                if let Some(path) = self.workspace_paths.get(name.as_str()) {
+                    resolved_dependencies
+                        .insert(name.as_str(), Resolved::Workspace(path.to_string()));
+                    continue;
+                }
+
+                let Some(package) = self
+                    .lockfile
+                    .resolve_package(&workspace.path, name, range)?
+                else {
+                    self.unsupported.insert(format!(
+                        "{name}@{range} required by {} is missing from the lockfile",
+                        workspace.name
+                    ));
+                    continue;
+                };
+                if let Some(id) = self.visit(&workspace.path, &package.key)? {
+                    self.add_range(&id, range);
+                    resolved_dependencies.insert(name.as_str(), Resolved::Package(id));
+                }
+            }
+            self.graph
+                .workspace_dependencies
+                .insert(workspace.path.as_str(), resolved_dependencies);
+        }
+        Ok(())
+    }
+
+    // Adds the package stored under `key` and all of its dependencies to the graph
+    fn visit(&mut self, workspace_path: &str, key: &str) -> Result<Option<PackageId>, Error> {
+        if let Some(id) = self.visited.get(key) {
+            return Ok(id.clone());
+        }
+
+        let details = self
+            .lockfile
+            .package_details(key)?
+            .ok_or_else(|| Error::MissingPackage(key.to_string()))?;
+        let id = PackageId {
+            name: details.name.clone(),
+            version: details.version.clone(),
+        };
+        if let Some(protocol) = &details.protocol {
+            self.unsupported.insert(format!(
+                "{id} uses the {protocol} protocol which cannot be converted"
+            ));
+            self.visited.insert(key.to_string(), None);
+            return Ok(None);
+        }
+        self.visited.insert(key.to_string(), Some(id.clone()));
+        if self.graph.packages.contains_key(&id) {
+            return Ok(Some(id));
+        }
+
+        let ranges = details
+            .dependencies
+            .iter()
+            .chain(&details.optional_dependencies)
+            .map(|(name, range)| (name.clone(), range.clone()))
+            .collect::<HashMap<_, _>>();
+        self.graph.packages.insert(
+            id.clone(),
+            Node {
+                details,
+                ranges: BTreeSet::new(),
+                dependencies: Map::new(),
+            },
+        );
+
+        let mut dependencies = Map::new();
+        for (name, version) in self.lockfile.all_dependencies(key)?.into_iter().flatten() {
+            let Some(package) = self
+                .lockfile
+                .resolve_package(workspace_path, &name, &version)?
+            else {
+                continue;
+            };
+            let Some(dependency) = self.visit(workspace_path, &package.key)? else {
+                continue;
+            };
+            if let Some(range) = ranges.get(&dependency.name) {
+                self.add_range(&dependency, range);
+            }
+            dependencies.insert(dependency.name.clone(), dependency);
+        }
+        if let Some(node) = self.graph.packages.get_mut(&id) {
+            node.dependencies = dependencies;
+        }
+
+        Ok(Some(id))
+    }
+
+    fn add_range(&mut self, id: &PackageId, range: &str) {
+        if let Some(node) = self.graph.packages.get_mut(id) {
+            node.ranges.insert(range.to_string());
+        }
+    }
+}
+
+impl WorkspaceManifest {
+    pub(crate) fn all_dependencies(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.dependencies
+            .iter()
+            .chain(&self.dev_dependencies)
+            .chain(&self.optional_dependencies)
+    }
+}
+
+impl<'a> Graph<'a> {
+    pub(crate) fn root(&self) -> Option<&'a WorkspaceManifest> {
+        self.workspaces
+            .iter()
+            .find(|workspace| workspace.path.is_empty())
+    }
+
+    pub(crate) fn workspace_dependency(&self, workspace: &str, name: &str) -> Option<&Resolved> {
+        self.workspace_dependencies.get(workspace)?.get(name)
+    }
+}
+
+impl Node {
+    /// The range this package was depended on with. Packages that were only
+    /// reached through dependencies without declared ranges use their exact
+    /// version.
+    pub(crate) fn ranges(&self) -> Vec<&str> {
+        match self.ranges.is_empty() {
+            true => vec![self.details.version.as_str()],
+            false => self.ranges.iter().map(|range| range.as_str()).collect(),
+        }
+    }
+
+    /// The package's tarball URL, falling back to the URL on the given
+    /// registry if the source lockfile didn't include one
+    pub(crate) fn resolved(&self, registry: &str) -> String {
+        self.details.resolved.clone().unwrap_or_else(|| {
+            let name = &self.details.name;
+            let basename = name.rsplit('/').next().unwrap_or(name);
+            format!(
+                "{registry}/{name}/-/{basename}-{}.tgz",
+                self.details.version
+            )
+        })
+    }
+
+    pub(crate) fn is_optional(&self, dependency: &str) -> bool {
+        self.details.optional_dependencies.contains_key(dependency)
+            && !self.details.dependencies.contains_key(dependency)
+    }
+}
+
+impl fmt::Display for PackageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+/// Determines the protocol of a package from its resolved URL, returns None for
+/// packages that were fetched from a registry
+pub(crate) fn resolved_protocol(resolved: &str) -> Option<String> {
+    if resolved.starts_with("https://") || resolved.starts_with("http://") {
+        return None;
+    }
+    let protocol = resolved
+        .split_once(':')
+        .map_or(resolved, |(protocol, _)| protocol);
+    Some(match protocol.strip_prefix("git+") {
+        Some(_) => "git".to_string(),
+        None => protocol.to_string(),
+    })
+}
+
+/// Relative path from the workspace at `from` to the workspace at `to`, both
+/// given relative to the repository root
+pub(crate) fn relative_path(from: &str, to: &str) -> String {
+    let from = from
+        .split('/')
+        .filter(|c| !c.is_empty())
+        .collect::<Vec<_>>();
+    let to = to.split('/').filter(|c| !c.is_empty()).collect::<Vec<_>>();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let components = std::iter::repeat("..")
+        .take(from.len() - common)
+        .chain(to[common..].iter().copied())
+        .collect::<Vec<_>>();
+    match components.is_empty() {
+        true => ".".to_string(),
+        false => components.join("/"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+
+    const YARN1: &str = r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+js-tokens@^4.0.0:
+  version "4.0.0"
+  resolved "https://registry.yarnpkg.com/js-tokens/-/js-tokens-4.0.0.tgz#19203fb59991df98e3a287050d4647cdeaf32499"
+  integrity sha512-RdJUflcE3cUzKiMqQgsCu06FHv9hiqKO+iu4yqxSDQ9/pYHhd4cR/0EM4b9GbRQ1OoM2UCHqLlFd7oeYDbpvDQ==
+
+loose-envify@^1.1.0:
+  version "1.4.0"
+  resolved "https://registry.yarnpkg.com/loose-envify/-/loose-envify-1.4.0.tgz#71ee51fa7be4caec1a63839f7e682d8132d30caf"
+  integrity sha512-lyuxPGr/Wfhrlem2CL/UcnUc1zcqKAImBDzukY7Y5F/yQiNdko6+fRLevlw1HgMySw7f611UIY408EtxRSoK3Q==
+  dependencies:
+    js-tokens "^4.0.0"
+
+my-fork@user/my-fork:
+  version "1.0.0"
+  resolved "git+https://github.com/user/my-fork.git#5f1ab1d0b6e0cc1c2a2b4fd4b6c1f61d0e3c7c3e"
+
+react@^18.2.0:
+  version "18.2.0"
+  resolved "https://registry.yarnpkg.com/react/-/react-18.2.0.tgz#555bd98592883255fa00de14f1151a917b5d77d5"
+  integrity sha512-/3IjMdb2L9QbBdWiW5e3P2/npwMBaU9mHCSCUzNln0ZCYbcfTsGbTJrU/kGemdH2IWmB2ioZ+zkxtmq6g09fGQ==
+  dependencies:
+    loose-envify "^1.1.0"
+"#;
+
+    fn workspaces() -> Vec<WorkspaceManifest> {
+        vec![
+            WorkspaceManifest {
+                path: "".into(),
+                name: "monorepo".into(),
+                dev_dependencies: [("my-fork".to_string(), "user/my-fork".to_string())]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+            WorkspaceManifest {
+                path: "apps/web".into(),
+                name: "web".into(),
+                version: Some("1.0.0".into()),
+                dependencies: [
+                    ("react".to_string(), "^18.2.0".to_string()),
+                    ("ui".to_string(), "*".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+                ..Default::default()
+            },
+            WorkspaceManifest {
+                path: "packages/ui".into(),
+                name: "ui".into(),
+                version: Some("0.0.0".into()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn convert_yarn1(target: LockfileFormat) -> Conversion {
+        let lockfile = Yarn1Lockfile::from_bytes(YARN1.as_bytes()).unwrap();
+        convert(&lockfile, &workspaces(), target).unwrap()
+    }
+
+    #[test_case("apps/web", "packages/ui", "../../packages/ui" ; "sibling")]
+    #[test_case("", "packages/ui", "packages/ui" ; "from root")]
+    #[test_case("packages/ui", "", "../.." ; "to root")]
+    #[test_case("packages/ui", "packages/ui", "." ; "same")]
+    fn test_relative_path(from: &str, to: &str, expected: &str) {
+        assert_eq!(relative_path(from, to), expected);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let conversion = convert_yarn1(LockfileFormat::Pnpm);
+        assert_eq!(
+            conversion.unsupported,
+            vec!["my-fork@1.0.0 uses the git protocol which cannot be converted".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_yarn1_to_pnpm() {
+        let conversion = convert_yarn1(LockfileFormat::Pnpm);
+        let lockfile = PnpmLockfile::from_bytes(&conversion.contents).unwrap();
+        assert_eq!(
+            lockfile
+                .resolve_package("apps/web", "react", "^18.2.0")
+                .unwrap(),
+            Some(crate::Package::new("/react@18.2.0", "18.2.0"))
+        );
+        assert_eq!(
+            lockfile.all_dependencies("/loose-envify@1.4.0").unwrap(),
+            Some(
+                [("js-tokens".to_string(), "4.0.0".to_string())]
+                    .into_iter()
+                    .collect()
+            )
+        );
+
+        let value: serde_yaml::Value = serde_yaml::from_slice(&conversion.contents).unwrap();
+        assert_eq!(
+            value["importers"]["apps/web"]["dependencies"]["ui"]["version"].as_str(),
+            Some("link:../../packages/ui")
+        );
+        assert_eq!(
+            value["packages"]["/react@18.2.0"]["resolution"]["integrity"].as_str(),
+            Some(
+                "sha512-/3IjMdb2L9QbBdWiW5e3P2/npwMBaU9mHCSCUzNln0ZCYbcfTsGbTJrU/\
+                 kGemdH2IWmB2ioZ+zkxtmq6g09fGQ=="
+            )
+        );
+    }
+
+    #[test]
+    fn test_yarn1_to_npm() {
+        let conversion = convert_yarn1(LockfileFormat::Npm);
+        let lockfile: serde_json::Value = serde_json::from_slice(&conversion.contents).unwrap();
+        let packages = lockfile["packages"].as_object().unwrap();
+        assert_eq!(
+            packages.keys().map(|k| k.as_str()).collect::<Vec<_>>(),
+            vec![
+                "",
+                "apps/web",
+                "node_modules/js-tokens",
+                "node_modules/loose-envify",
+                "node_modules/react",
+                "node_modules/ui",
+                "node_modules/web",
+                "packages/ui",
+            ]
+        );
+        assert_eq!(packages["node_modules/web"]["resolved"], "apps/web");
+        assert_eq!(packages["node_modules/web"]["link"], true);
+        assert_eq!(
+            packages["node_modules/react"]["dependencies"],
+            serde_json::json!({ "loose-envify": "^1.1.0" })
+        );
+    }
+
+    #[test]
+    fn test_yarn1_to_berry() {
+        let conversion = convert_yarn1(LockfileFormat::Berry);
+        assert_eq!(
+            String::from_utf8(conversion.contents).unwrap(),
+            r#"# This file is generated by running "yarn install" inside your project.
+# Manual changes might be lost - proceed with caution!
+
+__metadata:
+  version: 6
+
+"js-tokens@npm:^4.0.0":
+  version: 4.0.0
+  resolution: "js-tokens@npm:4.0.0"
+  languageName: node
+  linkType: hard
+
+"loose-envify@npm:^1.1.0":
+  version: 1.4.0
+  resolution: "loose-envify@npm:1.4.0"
+  dependencies:
+    js-tokens: ^4.0.0
+  languageName: node
+  linkType: hard
+
+"monorepo@workspace:.":
+  version: 0.0.0-use.local
+  resolution: "monorepo@workspace:."
+  dependencies:
+    my-fork: user/my-fork
+  languageName: unknown
+  linkType: soft
+
+"react@npm:^18.2.0":
+  version: 18.2.0
+  resolution: "react@npm:18.2.0"
+  dependencies:
+    loose-envify: ^1.1.0
+  languageName: node
+  linkType: hard
+
+"ui@*, ui@workspace:packages/ui":
+  version: 0.0.0-use.local
+  resolution: "ui@workspace:packages/ui"
+  languageName: unknown
+  linkType: soft
+
+"web@workspace:apps/web":
+  version: 0.0.0-use.local
+  resolution: "web@workspace:apps/web"
+  dependencies:
+    react: ^18.2.0
+    ui: "*"
+  languageName: unknown
+  linkType: soft
+"#
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_through_pnpm() {
+        let pnpm = convert_yarn1(LockfileFormat::Pnpm);
+        let pnpm = PnpmLockfile::from_bytes(&pnpm.contents).unwrap();
+        let yarn1 = convert(&pnpm, &workspaces(), LockfileFormat::Yarn1).unwrap();
+        let yarn1 = String::from_utf8(yarn1.contents).unwrap();
+        assert!(yarn1.contains("react@^18.2.0:\n  version \"18.2.0\""));
+        assert!(yarn1.contains("  dependencies:\n    loose-envify \"1.4.0\""));
+    }
+}
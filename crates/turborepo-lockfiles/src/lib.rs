@@ -2,6 +2,7 @@
 
 mod berry;
 mod bun;
+mod convert;
 mod error;
 mod npm;
 mod pnpm;
@@ -11,6 +12,7 @@ use std::collections::{HashMap, HashSet};
 
 pub use berry::{Error as BerryError, *};
 pub use bun::BunLockfile;
+pub use convert::{convert, Conversion, LockfileFormat, PackageDetails, WorkspaceManifest};
 pub use error::Error;
 pub use npm::*;
 pub use pnpm::{pnpm_global_change, pnpm_subgraph, PnpmLockfile};
@@ -48,11 +50,23 @@ pub trait Lockfile: Send + Sync {
 
     fn encode(&self) -> Result<Vec<u8>, Error>;
 
+    /// Describes the package stored under `key` independently of the lockfile
+    /// format, used for converting between formats
+    fn package_details(&self, _key: &str) -> Result<Option<PackageDetails>, Error> {
+        Ok(None)
+    }
+
     /// All patch files referenced in the lockfile
     fn patches(&self) -> Result<Vec<RelativeUnixPathBuf>, Error> {
         Ok(Vec::new())
     }
 
+    /// The version of the lockfile format, `None` if the format isn't
+    /// versioned
+    fn lockfile_version(&self) -> Option<String> {
+        None
+    }
+
     /// Present a global change key which is compared against two lockfiles
     ///
     /// Impl notes: please prefix this key with some magic identifier
@@ -83,7 +97,20 @@ pub fn transitive_closure<L: Lockfile + ?Sized>(
     workspace_path: &str,
     unresolved_deps: HashMap<String, String>,
 ) -> Result<HashSet<Package>, Error> {
-    let mut transitive_deps = HashSet::new();
+    let transitive_deps = named_transitive_closure(lockfile, workspace_path, unresolved_deps)?;
+    Ok(transitive_deps.into_keys().collect())
+}
+
+/// Calculates the transitive closure of a workspace's dependencies along with
+/// the name each package was depended on as. Lockfile keys don't have a
+/// consistent format across package managers so this is the only reliable
+/// way of getting a package's name.
+pub fn named_transitive_closure<L: Lockfile + ?Sized>(
+    lockfile: &L,
+    workspace_path: &str,
+    unresolved_deps: HashMap<String, String>,
+) -> Result<HashMap<Package, String>, Error> {
+    let mut transitive_deps = HashMap::new();
     transitive_closure_helper(
         lockfile,
         workspace_path,
@@ -98,7 +125,7 @@ fn transitive_closure_helper<L: Lockfile + ?Sized>(
     lockfile: &L,
     workspace_path: &str,
     unresolved_deps: HashMap<String, impl AsRef<str>>,
-    resolved_deps: &mut HashSet<Package>,
+    resolved_deps: &mut HashMap<Package, String>,
 ) -> Result<(), Error> {
     for (name, specifier) in unresolved_deps {
         let pkg = lockfile.resolve_package(workspace_path, &name, specifier.as_ref())?;
@@ -107,12 +134,12 @@ fn transitive_closure_helper<L: Lockfile + ?Sized>(
             None => {
                 continue;
             }
-            Some(pkg) if resolved_deps.contains(&pkg) => {
+            Some(pkg) if resolved_deps.contains_key(&pkg) => {
                 continue;
             }
             Some(pkg) => {
                 let all_deps = lockfile.all_dependencies(&pkg.key)?;
-                resolved_deps.insert(pkg);
+                resolved_deps.insert(pkg, name);
                 if let Some(deps) = all_deps {
                     transitive_closure_helper(lockfile, workspace_path, deps, resolved_deps)?;
                 }
@@ -1,9 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
-use super::{Error, Lockfile, Package};
+use super::{Error, Lockfile, Package, PackageDetails};
+use crate::convert::{resolved_protocol, Graph, PackageId, Resolved};
 
 type Map<K, V> = std::collections::BTreeMap<K, V>;
 
@@ -14,6 +15,8 @@ type Map<K, V> = std::collections::BTreeMap<K, V>;
 pub struct NpmLockfile {
     #[serde(rename = "lockfileVersion")]
     lockfile_version: i32,
+    // Missing from v1 lockfiles, which we fill in from 'dependencies'
+    #[serde(default)]
     packages: Map<String, NpmPackage>,
     // We parse this so it doesn't end up in 'other' and we don't need to worry
     // about accidentally serializing it.
@@ -25,7 +28,7 @@ pub struct NpmLockfile {
     other: Map<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 struct NpmPackage {
     version: Option<String>,
@@ -44,6 +47,23 @@ struct NpmPackage {
     other: Map<String, Value>,
 }
 
+// A package in the nested 'dependencies' layout used by lockfile v1. Each
+// entry lists the packages installed in its own node_modules.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct NpmV1Dependency {
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolved: Option<String>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    requires: Map<String, String>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    dependencies: Map<String, NpmV1Dependency>,
+    // Fields such as integrity, dev, and optional that we pass through
+    #[serde(flatten)]
+    other: Map<String, Value>,
+}
+
 impl Lockfile for NpmLockfile {
     fn resolve_package(
         &self,
@@ -51,7 +71,8 @@ impl Lockfile for NpmLockfile {
         name: &str,
         _version: &str,
     ) -> Result<Option<Package>, Error> {
-        if !self.packages.contains_key(workspace_path) {
+        // v1 lockfiles only record workspaces that are installed via a file: dependency
+        if !self.packages.contains_key(workspace_path) && !self.is_v1() {
             return Err(Error::MissingWorkspace(workspace_path.to_string()));
         }
 
@@ -114,7 +135,11 @@ impl Lockfile for NpmLockfile {
             pruned_packages.insert("".into(), root.clone());
         }
         for workspace in workspace_packages {
-            let pkg = self.get_package(workspace)?;
+            let pkg = match self.packages.get(workspace) {
+                Some(pkg) => pkg,
+                None if self.is_v1() => continue,
+                None => return Err(Error::MissingPackage(workspace.to_string())),
+            };
             pruned_packages.insert(workspace.to_string(), pkg.clone());
 
             for (key, entry) in &self.packages {
@@ -125,7 +150,10 @@ impl Lockfile for NpmLockfile {
             }
         }
         Ok(Box::new(Self {
-            lockfile_version: 3,
+            lockfile_version: match self.is_v1() {
+                true => 1,
+                false => 3,
+            },
             packages: pruned_packages,
             dependencies: Map::default(),
             other: self.other.clone(),
@@ -133,9 +161,54 @@ impl Lockfile for NpmLockfile {
     }
 
     fn encode(&self) -> Result<Vec<u8>, crate::Error> {
+        if self.is_v1() {
+            let mut lockfile = serde_json::Map::new();
+            lockfile.extend(self.other.clone());
+            lockfile.insert("lockfileVersion".into(), json!(self.lockfile_version));
+            lockfile.insert(
+                "dependencies".into(),
+                serde_json::to_value(self.v1_dependencies())?,
+            );
+            return Ok(serde_json::to_vec_pretty(&lockfile)?);
+        }
         Ok(serde_json::to_vec_pretty(&self)?)
     }
 
+    fn package_details(&self, key: &str) -> Result<Option<PackageDetails>, Error> {
+        let Some(pkg) = self.packages.get(key) else {
+            return Ok(None);
+        };
+        let name = pkg
+            .other
+            .get("name")
+            .and_then(|name| name.as_str())
+            .or_else(|| key.rsplit_once("node_modules/").map(|(_, name)| name))
+            .unwrap_or(key)
+            .to_string();
+        let protocol = match pkg.other.get("link") {
+            Some(Value::Bool(true)) => Some("link".to_string()),
+            _ => pkg.resolved.as_deref().and_then(resolved_protocol),
+        };
+
+        Ok(Some(PackageDetails {
+            name,
+            version: pkg.version.clone().unwrap_or_default(),
+            resolved: pkg.resolved.clone(),
+            integrity: pkg
+                .other
+                .get("integrity")
+                .and_then(|integrity| integrity.as_str())
+                .map(|integrity| integrity.to_string()),
+            protocol,
+            dependencies: pkg.dependencies.clone(),
+            optional_dependencies: pkg.optional_dependencies.clone(),
+        }))
+    }
+
+    fn lockfile_version(&self) -> Option<String> {
+        Some(self.lockfile_version.to_string())
+    }
+
     fn global_change_key(&self) -> Vec<u8> {
         let mut buf = vec![b'n', b'p', b'm', 0];
 
@@ -153,22 +226,334 @@ impl Lockfile for NpmLockfile {
 }
 
 impl NpmLockfile {
+    pub(crate) fn from_graph(graph: &Graph) -> Self {
+        let mut packages = Map::new();
+
+        for workspace in graph.workspaces {
+            let mut other = Map::new();
+            other.insert("name".to_string(), json!(workspace.name));
+            if workspace.path.is_empty() {
+                let workspace_paths = graph
+                    .workspaces
+                    .iter()
+                    .filter(|workspace| !workspace.path.is_empty())
+                    .map(|workspace| workspace.path.as_str())
+                    .collect::<Vec<_>>();
+                other.insert("workspaces".to_string(), json!(workspace_paths));
+            } else {
+                packages.insert(
+                    format!("node_modules/{}", workspace.name),
+                    NpmPackage {
+                        resolved: Some(workspace.path.clone()),
+                        other: [("link".to_string(), Value::Bool(true))]
+                            .into_iter()
+                            .collect(),
+                        ..Default::default()
+                    },
+                );
+            }
+            packages.insert(
+                workspace.path.clone(),
+                NpmPackage {
+                    version: workspace.version.clone(),
+                    dependencies: workspace.dependencies.clone(),
+                    dev_dependencies: workspace.dev_dependencies.clone(),
+                    optional_dependencies: workspace.optional_dependencies.clone(),
+                    other,
+                    ..Default::default()
+                },
+            );
+        }
+
+        // Install packages breadth first so the shallowest dependency on a package
+        // gets hoisted to the top level node_modules
+        let mut queue = graph
+            .workspaces
+            .iter()
+            .flat_map(|workspace| {
+                graph
+                    .workspace_dependencies
+                    .get(workspace.path.as_str())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(_, resolved)| match resolved {
+                        Resolved::Package(id) => Some((workspace.path.clone(), id)),
+                        Resolved::Workspace(_) => None,
+                    })
+            })
+            .collect::<VecDeque<_>>();
+        let mut installed: HashMap<String, &PackageId> = HashMap::new();
+        while let Some((parent, id)) = queue.pop_front() {
+            let (Some(path), Some(node)) = (
+                Self::install_path(&installed, &parent, id),
+                graph.packages.get(id),
+            ) else {
+                continue;
+            };
+            installed.insert(path.clone(), id);
+
+            let mut other = Map::new();
+            if let Some(integrity) = &node.details.integrity {
+                other.insert("integrity".to_string(), json!(integrity));
+            }
+            let resolved = node.resolved("https://registry.npmjs.org");
+            packages.insert(
+                path.clone(),
+                NpmPackage {
+                    version: Some(node.details.version.clone()),
+                    // npm doesn't include the hash in the resolved URL
+                    resolved: Some(
+                        resolved
+                            .split_once('#')
+                            .map_or(resolved.as_str(), |(url, _)| url)
+                            .to_string(),
+                    ),
+                    dependencies: node.details.dependencies.clone(),
+                    optional_dependencies: node.details.optional_dependencies.clone(),
+                    other,
+                    ..Default::default()
+                },
+            );
+
+            queue.extend(
+                node.dependencies
+                    .values()
+                    .map(|dependency| (path.clone(), dependency)),
+            );
+        }
+
+        Self {
+            lockfile_version: 3,
+            packages,
+            dependencies: Map::new(),
+            other: [
+                (
+                    "name".to_string(),
+                    json!(graph.root().map(|root| &root.name)),
+                ),
+                ("requires".to_string(), Value::Bool(true)),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    // Finds where a package should be installed so it is visible from `parent`.
+    // Returns None if the package is already visible or if it can't be installed
+    // without shadowing a different version.
+    fn install_path(
+        installed: &HashMap<String, &PackageId>,
+        parent: &str,
+        id: &PackageId,
+    ) -> Option<String> {
+        let path_in = |dir: &str| match dir.is_empty() {
+            true => format!("node_modules/{}", id.name),
+            false => format!("{dir}/node_modules/{}", id.name),
+        };
+
+        let mut dir = Some(parent);
+        while let Some(current) = dir {
+            match installed.get(&path_in(current)) {
+                Some(existing) if *existing == id => return None,
+                // A different version is visible from the parent, so this one needs to
+                // be nested directly under the parent
+                Some(_) => {
+                    let nested = path_in(parent);
+                    return (!installed.contains_key(&nested)).then_some(nested);
+                }
+                None => (),
+            }
+            dir = match current.is_empty() {
+                true => None,
+                false => Some(
+                    Self::npm_path_parent(current)
+                        .map_or("", |parent| parent.trim_end_matches('/')),
+                ),
+            };
+        }
+
+        // Nothing is visible so it can be hoisted all the way to the top
+        Some(path_in(""))
+    }
+
     pub fn load(content: &[u8]) -> Result<Self, Error> {
-        let lockfile: NpmLockfile = serde_json::from_slice(content)?;
+        let mut lockfile: NpmLockfile = serde_json::from_slice(content)?;
+
+        // v1 lockfiles describe node_modules as a tree instead of listing each
+        // install location so we flatten it into the 'packages' layout.
+        // See https://docs.npmjs.com/cli/v6/configuring-npm/package-lock-json
+        if lockfile.is_v1() {
+            let dependencies = lockfile
+                .dependencies
+                .iter()
+                .map(|(name, dependency)| {
+                    Ok((name.clone(), serde_json::from_value(dependency.clone())?))
+                })
+                .collect::<Result<Map<_, _>, Error>>()?;
+            lockfile.packages = lockfile.v1_packages(&dependencies);
+            return Ok(lockfile);
+        }
 
         // We don't support lockfiles without 'packages' as older versions
         // required reading through the contents of node_modules in order
         // to resolve dependencies.
         // See https://github.com/npm/cli/blob/9609e9eed87c735f0319ac0af265f4d406cbf800/workspaces/arborist/lib/shrinkwrap.js#L674
-        if lockfile.lockfile_version <= 1
-            || (lockfile.packages.is_empty() && !lockfile.dependencies.is_empty())
-        {
+        if lockfile.packages.is_empty() && !lockfile.dependencies.is_empty() {
             Err(Error::UnsupportedNpmVersion)
         } else {
             Ok(lockfile)
         }
     }
 
+    fn is_v1(&self) -> bool {
+        self.lockfile_version <= 1
+    }
+
+    // Converts the nested v1 dependency tree to the flat 'packages' layout used
+    // by v2+ lockfiles
+    fn v1_packages(&self, dependencies: &Map<String, NpmV1Dependency>) -> Map<String, NpmPackage> {
+        let mut packages = Map::new();
+        packages.insert(
+            "".to_string(),
+            NpmPackage {
+                version: self
+                    .other
+                    .get("version")
+                    .and_then(|version| version.as_str())
+                    .map(|version| version.to_string()),
+                other: self
+                    .other
+                    .get("name")
+                    .map(|name| ("name".to_string(), name.clone()))
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+        );
+
+        let mut stack = dependencies
+            .iter()
+            .map(|(name, dependency)| (format!("node_modules/{name}"), dependency))
+            .collect::<Vec<_>>();
+        while let Some((key, dependency)) = stack.pop() {
+            // Workspaces are installed as file: dependencies of the root, anything
+            // nested under them is installed in the workspace's node_modules
+            let workspace_path = dependency.version.strip_prefix("file:");
+            let parent = workspace_path.unwrap_or(&key);
+            stack.extend(
+                dependency.dependencies.iter().map(|(name, dependency)| {
+                    (format!("{parent}/node_modules/{name}"), dependency)
+                }),
+            );
+
+            if let Some(path) = workspace_path {
+                packages.insert(
+                    path.to_string(),
+                    NpmPackage {
+                        dependencies: dependency.requires.clone(),
+                        ..Default::default()
+                    },
+                );
+                packages.insert(
+                    key,
+                    NpmPackage {
+                        resolved: Some(path.to_string()),
+                        other: [("link".to_string(), Value::Bool(true))]
+                            .into_iter()
+                            .collect(),
+                        ..Default::default()
+                    },
+                );
+                continue;
+            }
+
+            packages.insert(
+                key,
+                NpmPackage {
+                    version: Some(dependency.version.clone()),
+                    resolved: dependency.resolved.clone(),
+                    dependencies: dependency.requires.clone(),
+                    other: dependency.other.clone(),
+                    ..Default::default()
+                },
+            );
+        }
+
+        packages
+    }
+
+    // Rebuilds the nested v1 dependency tree from the flat 'packages' layout
+    fn v1_dependencies(&self) -> Map<String, NpmV1Dependency> {
+        // Workspace paths to where they are linked in the root node_modules
+        let links = self
+            .packages
+            .iter()
+            .filter(|(_, package)| package.other.get("link") == Some(&Value::Bool(true)))
+            .filter_map(|(key, package)| Some((package.resolved.as_deref()?, key.as_str())))
+            .collect::<HashMap<_, _>>();
+
+        let mut dependencies: Map<String, NpmV1Dependency> = Map::new();
+        for (key, package) in &self.packages {
+            let path = match key.strip_prefix("node_modules/") {
+                Some(path) => path.to_string(),
+                None => {
+                    // Packages installed in a workspace's node_modules get nested
+                    // under the workspace's entry
+                    let Some((link, path)) = key
+                        .split_once("/node_modules/")
+                        .and_then(|(workspace, path)| Some((links.get(workspace)?, path)))
+                    else {
+                        continue;
+                    };
+                    let link = link.strip_prefix("node_modules/").unwrap_or(link);
+                    format!("{link}/node_modules/{path}")
+                }
+            };
+            let mut names = path.split("/node_modules/").collect::<Vec<_>>();
+            let Some(name) = names.pop() else {
+                continue;
+            };
+            let parent = names
+                .into_iter()
+                .fold(&mut dependencies, |dependencies, name| {
+                    &mut dependencies
+                        .entry(name.to_string())
+                        .or_default()
+                        .dependencies
+                });
+
+            let dependency = match (package.other.get("link"), &package.resolved) {
+                (Some(Value::Bool(true)), Some(path)) => NpmV1Dependency {
+                    version: format!("file:{path}"),
+                    requires: self
+                        .packages
+                        .get(path)
+                        .map(|workspace| workspace.dependencies.clone())
+                        .unwrap_or_default(),
+                    ..Default::default()
+                },
+                _ => NpmV1Dependency {
+                    version: package.version.clone().unwrap_or_default(),
+                    resolved: package.resolved.clone(),
+                    requires: package
+                        .dependencies
+                        .iter()
+                        .chain(&package.optional_dependencies)
+                        .map(|(name, range)| (name.clone(), range.clone()))
+                        .collect(),
+                    other: package.other.clone(),
+                    ..Default::default()
+                },
+            };
+            // A package might be visited after packages nested under it
+            parent
+                .entry(name.to_string())
+                .or_default()
+                .merge(dependency);
+        }
+        dependencies
+    }
+
     fn get_package(&self, package: impl AsRef<str>) -> Result<&NpmPackage, Error> {
         let pkg_str = package.as_ref();
         self.packages
@@ -202,6 +587,16 @@ impl NpmLockfile {
     }
 }
 
+impl NpmV1Dependency {
+    // Fills in this entry while keeping any nested dependencies that were
+    // already added to it
+    fn merge(&mut self, other: NpmV1Dependency) {
+        let dependencies = std::mem::take(&mut self.dependencies);
+        *self = other;
+        self.dependencies = dependencies;
+    }
+}
+
 impl NpmPackage {
     pub fn dep_keys(&self) -> impl Iterator<Item = &String> {
         self.dependencies
@@ -425,6 +820,77 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_v1_resolve_package() -> Result<(), Error> {
+        let lockfile = NpmLockfile::load(include_bytes!("../fixtures/npm-lock-v1.json"))?;
+        let tests = [
+            ("", "react", "node_modules/react", "18.2.0"),
+            ("apps/web", "react", "apps/web/node_modules/react", "17.0.2"),
+            ("packages/ui", "react", "node_modules/react", "18.2.0"),
+        ];
+
+        for (workspace, name, key, version) in &tests {
+            let pkg = lockfile.resolve_package(workspace, name, "")?;
+            assert_eq!(pkg, Some(Package::new(*key, *version)));
+        }
+
+        assert_eq!(
+            lockfile.all_dependencies("apps/web/node_modules/react")?,
+            Some(
+                [
+                    ("node_modules/loose-envify".to_string(), "1.4.0".to_string()),
+                    (
+                        "node_modules/object-assign".to_string(),
+                        "4.1.1".to_string()
+                    ),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_v1_roundtrip() -> Result<(), Error> {
+        let contents = include_bytes!("../fixtures/npm-lock-v1.json");
+        let lockfile = NpmLockfile::load(contents)?;
+        assert_eq!(
+            serde_json::from_slice::<Value>(&lockfile.encode()?)?,
+            serde_json::from_slice::<Value>(contents)?,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_v1_subgraph() -> Result<(), Error> {
+        let lockfile = NpmLockfile::load(include_bytes!("../fixtures/npm-lock-v1.json"))?;
+        let pruned = lockfile.subgraph(
+            &["apps/web".into()],
+            &[
+                "apps/web/node_modules/react".into(),
+                "node_modules/js-tokens".into(),
+                "node_modules/loose-envify".into(),
+                "node_modules/object-assign".into(),
+            ],
+        )?;
+        let pruned: Value = serde_json::from_slice(&pruned.encode()?)?;
+
+        assert_eq!(pruned["lockfileVersion"], json!(1));
+        let dependencies = pruned["dependencies"].as_object().unwrap();
+        assert_eq!(
+            dependencies.keys().collect::<Vec<_>>(),
+            vec!["js-tokens", "loose-envify", "object-assign", "web"]
+        );
+        assert_eq!(dependencies["web"]["version"], json!("file:apps/web"));
+        assert_eq!(
+            dependencies["web"]["dependencies"]["react"]["version"],
+            json!("17.0.2")
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_workspace_peer_dependencies() -> Result<(), Error> {
         let lockfile =
@@ -4,9 +4,18 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use turbopath::RelativeUnixPathBuf;
 
-use super::{dep_path::DepPath, Error, LockfileVersion};
+use super::{dep_path::DepPath, Error, LockfileVersion, VersionFormat};
+use crate::{
+    convert::{relative_path, resolved_protocol, Graph, Resolved},
+    PackageDetails,
+};
 
 type Map<K, V> = std::collections::BTreeMap<K, V>;
+// The dependencies and optional dependencies of a package
+type DependencyMaps<'a> = (
+    &'a Option<Map<String, String>>,
+    &'a Option<Map<String, String>>,
+);
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -27,6 +36,10 @@ pub struct PnpmLockfile {
     importers: Map<String, ProjectSnapshot>,
     #[serde(skip_serializing_if = "Option::is_none")]
     packages: Option<Map<String, PackageSnapshot>>,
+    // Starting with v9 the dependencies of each package are stored separately
+    // from the package metadata
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshots: Option<Map<String, DependencySnapshot>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     time: Option<Map<String, String>>,
 }
@@ -104,6 +117,18 @@ pub struct PackageSnapshot {
     other: Map<String, serde_yaml::Value>,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencySnapshot {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dependencies: Option<Map<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    optional_dependencies: Option<Map<String, String>>,
+
+    #[serde(flatten)]
+    other: Map<String, serde_yaml::Value>,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct DependenciesMeta {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -151,9 +176,44 @@ impl PnpmLockfile {
     }
 
     fn get_packages(&self, key: &str) -> Option<&PackageSnapshot> {
+        if self.is_v9() {
+            // Only dependency paths that have a snapshot are valid keys
+            self.get_snapshot(key)?;
+        }
         self.packages
             .as_ref()
-            .and_then(|packages| packages.get(key))
+            .and_then(|packages| packages.get(self.metadata_key(key).as_ref()))
+    }
+
+    fn get_snapshot(&self, key: &str) -> Option<&DependencySnapshot> {
+        self.snapshots
+            .as_ref()
+            .and_then(|snapshots| snapshots.get(key))
+    }
+
+    // v9 lockfiles store package metadata under the dependency path without any
+    // peer or patch suffix
+    fn metadata_key<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        if !self.is_v9() {
+            return key.into();
+        }
+        match DepPath::try_from(key) {
+            Ok(dp) if dp.peer_suffix.is_some() => format!("{}@{}", dp.name, dp.version).into(),
+            _ => key.into(),
+        }
+    }
+
+    // Returns the dependencies and optional dependencies of the package stored
+    // under `key`
+    fn get_dependencies(&self, key: &str) -> Option<DependencyMaps> {
+        match self.is_v9() {
+            true => self
+                .get_snapshot(key)
+                .map(|snapshot| (&snapshot.dependencies, &snapshot.optional_dependencies)),
+            false => self
+                .get_packages(key)
+                .map(|package| (&package.dependencies, &package.optional_dependencies)),
+        }
     }
 
     fn get_workspace(&self, workspace_path: &str) -> Result<&ProjectSnapshot, crate::Error> {
@@ -172,10 +232,23 @@ impl PnpmLockfile {
         matches!(self.lockfile_version.format, super::VersionFormat::String)
     }
 
+    fn is_v9(&self) -> bool {
+        // Lockfile v7 introduced the layout that pnpm 9 ships as v9
+        self.is_v6()
+            && self
+                .lockfile_version
+                .version
+                .split('.')
+                .next()
+                .and_then(|major| major.parse::<u32>().ok())
+                .map_or(false, |major| major >= 7)
+    }
+
     fn format_key(&self, name: &str, version: &str) -> String {
-        match self.is_v6() {
-            true => format!("/{name}@{version}"),
-            false => format!("/{name}/{version}"),
+        match (self.is_v9(), self.is_v6()) {
+            (true, _) => format!("{name}@{version}"),
+            (false, true) => format!("/{name}@{version}"),
+            (false, false) => format!("/{name}/{version}"),
         }
     }
 
@@ -235,12 +308,130 @@ impl PnpmLockfile {
         }
     }
 
-    fn prune_patches(
+    pub(crate) fn from_graph(graph: &Graph) -> Self {
+        let importers = graph
+            .workspaces
+            .iter()
+            .map(|workspace| {
+                let dependencies = |deps: &Map<String, String>| {
+                    let deps = deps
+                        .iter()
+                        .filter_map(|(name, specifier)| {
+                            let version = match graph.workspace_dependency(&workspace.path, name)? {
+                                Resolved::Workspace(path) => {
+                                    format!("link:{}", relative_path(&workspace.path, path))
+                                }
+                                Resolved::Package(id) => id.version.clone(),
+                            };
+                            Some((
+                                name.clone(),
+                                Dependency {
+                                    specifier: specifier.clone(),
+                                    version,
+                                },
+                            ))
+                        })
+                        .collect::<Map<_, _>>();
+                    (!deps.is_empty()).then_some(deps)
+                };
+                let key = match workspace.path.as_str() {
+                    "" => ".".to_string(),
+                    path => path.to_string(),
+                };
+                let snapshot = ProjectSnapshot {
+                    dependencies: DependencyInfo::V6 {
+                        dependencies: dependencies(&workspace.dependencies),
+                        optional_dependencies: dependencies(&workspace.optional_dependencies),
+                        dev_dependencies: dependencies(&workspace.dev_dependencies),
+                    },
+                    dependencies_meta: None,
+                    publish_directory: None,
+                };
+                (key, snapshot)
+            })
+            .collect();
+
+        let packages = graph
+            .packages
+            .iter()
+            .map(|(id, node)| {
+                let versions = |deps: &Map<String, String>| {
+                    let deps = deps
+                        .keys()
+                        .filter_map(|name| {
+                            let dependency = node.dependencies.get(name)?;
+                            Some((name.clone(), dependency.version.clone()))
+                        })
+                        .collect::<Map<_, _>>();
+                    (!deps.is_empty()).then_some(deps)
+                };
+                let integrity = node.details.integrity.clone();
+                // Without an integrity pnpm needs to be told where to fetch the tarball from
+                let tarball = integrity
+                    .is_none()
+                    .then(|| node.resolved("https://registry.npmjs.org"));
+                let snapshot = PackageSnapshot {
+                    resolution: PackageResolution {
+                        type_field: None,
+                        integrity,
+                        tarball,
+                        directory: None,
+                        repo: None,
+                        commit: None,
+                    },
+                    id: None,
+                    name: None,
+                    version: None,
+                    dependencies: versions(&node.details.dependencies),
+                    optional_dependencies: versions(&node.details.optional_dependencies),
+                    patched: None,
+                    other: Map::new(),
+                };
+                (format!("/{}@{}", id.name, id.version), snapshot)
+            })
+            .collect::<Map<_, _>>();
+
+        Self {
+            lockfile_version: LockfileVersion {
+                version: "6.0".into(),
+                format: VersionFormat::String,
+            },
+            settings: None,
+            never_built_dependencies: None,
+            only_built_dependencies: None,
+            overrides: None,
+            package_extensions_checksum: None,
+            patched_dependencies: None,
+            importers,
+            packages: (!packages.is_empty()).then_some(packages),
+            snapshots: None,
+            time: None,
+        }
+    }
+
+    // Copies the entries for `key` into the pruned packages and snapshots
+    fn prune_package(
+        &self,
+        key: &str,
+        pruned_packages: &mut Map<String, PackageSnapshot>,
+        pruned_snapshots: &mut Map<String, DependencySnapshot>,
+    ) -> Result<(), crate::Error> {
+        let entry = self
+            .get_packages(key)
+            .ok_or_else(|| crate::Error::MissingPackage(key.to_string()))?;
+        pruned_packages.insert(self.metadata_key(key).into_owned(), entry.clone());
+        if let Some(snapshot) = self.get_snapshot(key) {
+            pruned_snapshots.insert(key.to_string(), snapshot.clone());
+        }
+        Ok(())
+    }
+
+    fn prune_patches<'a>(
         patches: &Map<String, PatchFile>,
-        pruned_packages: &Map<String, PackageSnapshot>,
+        pruned_packages: impl Iterator<Item = &'a String>,
     ) -> Result<Map<String, PatchFile>, Error> {
         let mut pruned_patches = Map::new();
-        for dependency in pruned_packages.keys() {
+        for dependency in pruned_packages {
             let dp = DepPath::try_from(dependency.as_str())?;
             let patch_key = format!("{}@{}", dp.name, dp.version);
             if let Some(patch) = patches
@@ -305,20 +496,55 @@ impl crate::Lockfile for PnpmLockfile {
         &self,
         key: &str,
     ) -> Result<Option<std::collections::HashMap<String, String>>, crate::Error> {
-        let Some(entry) = self.packages.as_ref().and_then(|pkgs| pkgs.get(key)) else {
+        let Some((dependencies, optional_dependencies)) = self.get_dependencies(key) else {
             return Ok(None);
         };
         Ok(Some(
-            entry
-                .dependencies
+            dependencies
                 .iter()
                 .flatten()
-                .chain(entry.optional_dependencies.iter().flatten())
+                .chain(optional_dependencies.iter().flatten())
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect(),
         ))
     }
 
+    fn package_details(&self, key: &str) -> Result<Option<PackageDetails>, crate::Error> {
+        let (Some(package), Some((dependencies, optional_dependencies))) =
+            (self.get_packages(key), self.get_dependencies(key))
+        else {
+            return Ok(None);
+        };
+        let dp = DepPath::try_from(key).map_err(Error::from)?;
+        let resolution = &package.resolution;
+        let protocol = match &resolution.type_field {
+            Some(type_field) => Some(type_field.clone()),
+            None => resolution.tarball.as_deref().and_then(resolved_protocol),
+        };
+        // Dependency versions can carry a peer suffix which isn't part of the version
+        let versions = |deps: &Option<Map<String, String>>| {
+            deps.iter()
+                .flatten()
+                .map(|(name, version)| {
+                    let version = version.split_once('(').map_or(version.as_str(), |(v, _)| v);
+                    (name.clone(), version.to_string())
+                })
+                .collect()
+        };
+        Ok(Some(PackageDetails {
+            name: package.name.clone().unwrap_or_else(|| dp.name.to_string()),
+            version: package
+                .version
+                .clone()
+                .unwrap_or_else(|| dp.version.to_string()),
+            resolved: resolution.tarball.clone(),
+            integrity: resolution.integrity.clone(),
+            protocol,
+            dependencies: versions(dependencies),
+            optional_dependencies: versions(optional_dependencies),
+        }))
+    }
+
     fn subgraph(
         &self,
         workspace_packages: &[String],
@@ -332,11 +558,9 @@ impl crate::Lockfile for PnpmLockfile {
             .collect::<Map<_, _>>();
 
         let mut pruned_packages = Map::new();
+        let mut pruned_snapshots = Map::new();
         for package in packages {
-            let entry = self
-                .get_packages(package.as_str())
-                .ok_or_else(|| crate::Error::MissingPackage(package.clone()))?;
-            pruned_packages.insert(package.clone(), entry.clone());
+            self.prune_package(package, &mut pruned_packages, &mut pruned_snapshots)?;
         }
         for importer in importers.values() {
             // Find all injected packages in each workspace and include it in
@@ -356,17 +580,22 @@ impl crate::Lockfile for PnpmLockfile {
                     .find_resolution(dependency)
                     .ok_or_else(|| Error::MissingInjectedPackage(dependency.clone()))?;
 
-                let entry = self
-                    .get_packages(version)
-                    .ok_or_else(|| crate::Error::MissingPackage(version.into()))?;
-                pruned_packages.insert(version.to_string(), entry.clone());
+                let key = match self.get_packages(version) {
+                    Some(_) => version.to_string(),
+                    None => self.format_key(dependency, version),
+                };
+                self.prune_package(&key, &mut pruned_packages, &mut pruned_snapshots)?;
             }
         }
 
         let patches = self
             .patched_dependencies
             .as_ref()
-            .map(|patches| Self::prune_patches(patches, &pruned_packages))
+            .map(|patches| match self.is_v9() {
+                // Patch hashes are only present in the snapshot keys
+                true => Self::prune_patches(patches, pruned_snapshots.keys()),
+                false => Self::prune_patches(patches, pruned_packages.keys()),
+            })
             .transpose()?;
 
         Ok(Box::new(Self {
@@ -375,6 +604,10 @@ impl crate::Lockfile for PnpmLockfile {
                 false => Some(pruned_packages),
                 true => None,
             },
+            snapshots: match pruned_snapshots.is_empty() {
+                false => Some(pruned_snapshots),
+                true => None,
+            },
             lockfile_version: self.lockfile_version.clone(),
             never_built_dependencies: self.never_built_dependencies.clone(),
             only_built_dependencies: self.only_built_dependencies.clone(),
@@ -401,6 +634,10 @@ impl crate::Lockfile for PnpmLockfile {
         Ok(patches)
     }
 
+    fn lockfile_version(&self) -> Option<String> {
+        Some(self.lockfile_version.version.clone())
+    }
+
     fn global_change_key(&self) -> Vec<u8> {
         let mut buf = vec![b'p', b'n', b'p', b'm', 0];
 
@@ -482,6 +719,7 @@ mod tests {
     const PNPM7: &[u8] = include_bytes!("../../fixtures/pnpm7-workspace.yaml").as_slice();
     const PNPM8: &[u8] = include_bytes!("../../fixtures/pnpm8.yaml").as_slice();
     const PNPM8_6: &[u8] = include_bytes!("../../fixtures/pnpm-v6.1.yaml").as_slice();
+    const PNPM9: &[u8] = include_bytes!("../../fixtures/pnpm9.yaml").as_slice();
     const PNPM_ABSOLUTE: &[u8] = include_bytes!("../../fixtures/pnpm-absolute.yaml").as_slice();
     const PNPM_ABSOLUTE_V6: &[u8] =
         include_bytes!("../../fixtures/pnpm-absolute-v6.yaml").as_slice();
@@ -497,7 +735,7 @@ mod tests {
 
     #[test]
     fn test_roundtrip() {
-        for fixture in &[PNPM6, PNPM7, PNPM8, PNPM8_6] {
+        for fixture in &[PNPM6, PNPM7, PNPM8, PNPM8_6, PNPM9] {
             let lockfile = PnpmLockfile::from_bytes(fixture).unwrap();
             let serialized_lockfile = serde_yaml::to_string(&lockfile).unwrap();
             let lockfile_from_serialized =
@@ -706,6 +944,36 @@ mod tests {
         }))
         ; "pnpm override"
     )]
+    #[test_case(
+        PNPM9,
+        "apps/web",
+        "react-dom",
+        "^18.2.0",
+        Ok(Some(crate::Package {
+            key: "react-dom@18.2.0(react@18.2.0)".into(),
+            version: "18.2.0(react@18.2.0)".into(),
+        }))
+        ; "v9 peer package"
+    )]
+    #[test_case(
+        PNPM9,
+        "apps/web",
+        "loose-envify",
+        "1.4.0",
+        Ok(Some(crate::Package {
+            key: "loose-envify@1.4.0".into(),
+            version: "1.4.0".into(),
+        }))
+        ; "v9 transitive package"
+    )]
+    #[test_case(
+        PNPM9,
+        "apps/web",
+        "ui",
+        "workspace:*",
+        Ok(None)
+        ; "v9 workspace"
+    )]
     fn test_resolve_package(
         lockfile: &[u8],
         workspace_path: &str,
@@ -893,4 +1161,64 @@ c:
             ]
         )
     }
+
+    #[test]
+    fn test_v9_all_dependencies() {
+        let lockfile = PnpmLockfile::from_bytes(PNPM9).unwrap();
+        assert_eq!(
+            lockfile
+                .all_dependencies("react-dom@18.2.0(react@18.2.0)")
+                .unwrap(),
+            Some(
+                [
+                    ("loose-envify".to_string(), "1.4.0".to_string()),
+                    ("react".to_string(), "18.2.0".to_string()),
+                    ("scheduler".to_string(), "0.23.0".to_string()),
+                ]
+                .into_iter()
+                .collect()
+            )
+        );
+        assert_eq!(lockfile.all_dependencies("react-dom@18.2.0").unwrap(), None);
+    }
+
+    #[test]
+    fn test_v9_subgraph() {
+        let lockfile = PnpmLockfile::from_bytes(PNPM9).unwrap();
+        let pruned = lockfile
+            .subgraph(
+                &["apps/web".into()],
+                &[
+                    "is-odd@3.0.1(patch_hash=nn7vzvscv6ds6i4xpbzgwmp2i4)".into(),
+                    "is-number@6.0.0".into(),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            pruned.patches().unwrap(),
+            vec![RelativeUnixPathBuf::new("patches/is-odd@3.0.1.patch").unwrap()]
+        );
+
+        let pruned = PnpmLockfile::from_bytes(&pruned.encode().unwrap()).unwrap();
+        assert_eq!(
+            pruned.packages.as_ref().unwrap().keys().collect::<Vec<_>>(),
+            vec!["is-number@6.0.0", "is-odd@3.0.1"]
+        );
+        assert_eq!(
+            pruned
+                .snapshots
+                .as_ref()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![
+                "is-number@6.0.0",
+                "is-odd@3.0.1(patch_hash=nn7vzvscv6ds6i4xpbzgwmp2i4)"
+            ]
+        );
+        assert_eq!(
+            pruned.importers.keys().collect::<Vec<_>>(),
+            vec![".", "apps/web"]
+        );
+    }
 }
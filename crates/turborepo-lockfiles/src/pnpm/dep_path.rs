@@ -67,6 +67,23 @@ impl<'a> TryFrom<&'a str> for DepPath<'a> {
 // The conversion only replaces the '@' separator with '/', we avoid this
 // conversion by allowing for a '@' or a '/' to be used as a separator.
 fn parse_dep_path(i: &str) -> IResult<&str, DepPath> {
+    alt((parse_v9_dep_path, parse_prefixed_dep_path))(i)
+}
+
+// v9 dependency paths drop the leading '/' and always use '@' as a separator
+fn parse_v9_dep_path(i: &str) -> IResult<&str, DepPath> {
+    let (i, name) = parse_name(i)?;
+    let (i, _) = nom::character::complete::char('@')(i)?;
+    let (i, version) = parse_version(i)?;
+    let (i, peer_suffix) = opt(parse_new_peer_suffix)(i)?;
+    let (_, _) = nom::combinator::eof(i)?;
+    Ok((
+        "",
+        DepPath::new(name, version).with_peer_suffix(peer_suffix),
+    ))
+}
+
+fn parse_prefixed_dep_path(i: &str) -> IResult<&str, DepPath> {
     let (i, host) = parse_host(i)?;
     let (i, _) = nom::character::complete::char('/')(i)?;
     let (i, name) = parse_name(i)?;
@@ -143,6 +160,9 @@ mod tests {
     #[test_case("/is-even@1.0.0_foobar", DepPath::new("is-even", "1.0.0").with_peer_suffix(Some("foobar")); "v6 dep path with suffix")]
     #[test_case("/foo@1.0.0(bar@1.0.0)(baz@1.0.0)", DepPath::new("foo", "1.0.0").with_peer_suffix(Some("(bar@1.0.0)(baz@1.0.0)")); "v6 with multiple peers")]
     #[test_case("/@babel/helper-string-parser@7.19.4(patch_hash=wjhgmpzh47qmycrzgpeyoyh3ce)(@babel/core@7.21.0)", DepPath::new("@babel/helper-string-parser", "7.19.4").with_peer_suffix(Some("(patch_hash=wjhgmpzh47qmycrzgpeyoyh3ce)(@babel/core@7.21.0)")); "v6 with scope")]
+    #[test_case("foo@1.0.0", DepPath::new("foo", "1.0.0"); "basic v9 dep path")]
+    #[test_case("@scope/foo@1.0.0(bar@1.0.0)", DepPath::new("@scope/foo", "1.0.0").with_peer_suffix(Some("(bar@1.0.0)")); "v9 with scope and peer")]
+    #[test_case("is-odd@3.0.1(patch_hash=nn7vzvscv6ds6i4xpbzgwmp2i4)", DepPath::new("is-odd", "3.0.1").with_peer_suffix(Some("(patch_hash=nn7vzvscv6ds6i4xpbzgwmp2i4)")); "v9 with patch")]
     fn dep_path_parse_tests(s: &str, expected: DepPath) {
         let (rest, actual) = parse_dep_path(s).unwrap();
         assert_eq!(rest, "");
@@ -2,7 +2,10 @@ use std::str::FromStr;
 
 use serde::Deserialize;
 
-use crate::Lockfile;
+use crate::{
+    convert::{resolved_protocol, Graph},
+    Lockfile, PackageDetails,
+};
 
 mod de;
 mod ser;
@@ -41,6 +44,43 @@ impl Yarn1Lockfile {
         let input = std::str::from_utf8(input).map_err(Error::from)?;
         Self::from_str(input)
     }
+
+    // A lockfile with only the entries for `packages`
+    pub(crate) fn subgraph_lockfile(&self, packages: &[String]) -> Self {
+        let mut inner = Map::new();
+
+        for (key, entry) in packages.iter().filter_map(|key| {
+            let entry = self.inner.get(key)?;
+            Some((key, entry))
+        }) {
+            inner.insert(key.clone(), entry.clone());
+        }
+
+        Self { inner }
+    }
+}
+
+impl Yarn1Lockfile {
+    pub(crate) fn from_graph(graph: &Graph) -> Self {
+        let mut inner = Map::new();
+        for node in graph.packages.values() {
+            let non_empty = |deps: &Map<String, String>| (!deps.is_empty()).then(|| deps.clone());
+            let entry = Entry {
+                version: node.details.version.clone(),
+                resolved: Some(node.resolved("https://registry.yarnpkg.com")),
+                integrity: node.details.integrity.clone(),
+                dependencies: non_empty(&node.details.dependencies),
+                optional_dependencies: non_empty(&node.details.optional_dependencies),
+                ..Default::default()
+            };
+            // Workspaces don't appear in yarn1 lockfiles so we only need to add the
+            // external packages
+            for range in node.ranges() {
+                inner.insert(format!("{}@{range}", node.details.name), entry.clone());
+            }
+        }
+        Self { inner }
+    }
 }
 
 impl FromStr for Yarn1Lockfile {
@@ -92,22 +132,21 @@ impl Lockfile for Yarn1Lockfile {
         _workspace_packages: &[String],
         packages: &[String],
     ) -> Result<Box<dyn Lockfile>, super::Error> {
-        let mut inner = Map::new();
-
-        for (key, entry) in packages.iter().filter_map(|key| {
-            let entry = self.inner.get(key)?;
-            Some((key, entry))
-        }) {
-            inner.insert(key.clone(), entry.clone());
-        }
-
-        Ok(Box::new(Self { inner }))
+        Ok(Box::new(self.subgraph_lockfile(packages)))
     }
 
     fn encode(&self) -> Result<Vec<u8>, crate::Error> {
         Ok(self.to_string().into_bytes())
     }
 
+    fn package_details(&self, key: &str) -> Result<Option<PackageDetails>, crate::Error> {
+        Ok(self.inner.get(key).map(|entry| entry.details(key)))
+    }
+
+    fn lockfile_version(&self) -> Option<String> {
+        Some("1".to_string())
+    }
+
     fn global_change_key(&self) -> Vec<u8> {
         vec![b'y', b'a', b'r', b'n', 0]
     }
@@ -127,6 +166,30 @@ impl Entry {
             .chain(self.optional_dependencies.iter().flatten())
             .map(|(k, v)| (k.clone(), v.clone()))
     }
+
+    fn details(&self, key: &str) -> PackageDetails {
+        PackageDetails {
+            name: self
+                .name
+                .clone()
+                .unwrap_or_else(|| package_name(key).to_string()),
+            version: self.version.clone(),
+            resolved: self.resolved.clone(),
+            integrity: self.integrity.clone(),
+            protocol: self.resolved.as_deref().and_then(resolved_protocol),
+            dependencies: self.dependencies.clone().unwrap_or_default(),
+            optional_dependencies: self.optional_dependencies.clone().unwrap_or_default(),
+        }
+    }
+}
+
+// Extracts the package name from a key of the form `name@range`
+fn package_name(key: &str) -> &str {
+    // Skip the first character so we don't split on the @ of a scoped package
+    match key.get(1..).and_then(|rest| rest.find('@')) {
+        Some(index) => &key[..index + 1],
+        None => key,
+    }
 }
 
 const PROTOCOLS: &[&str] = ["", "npm:", "file:", "workspace:", "yarn:"].as_slice();
@@ -17,14 +17,14 @@ impl Yarn1Lockfile {
         }
         reverse_lookup
     }
-}
 
-impl fmt::Display for Yarn1Lockfile {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(
-            "# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.\n# yarn lockfile \
-             v1\n\n",
-        )?;
+    // Writes out each entry under all of the keys that resolve to it. Bun
+    // always quotes keys where yarn only quotes them when needed.
+    pub(crate) fn write_entries(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        quote_all_keys: bool,
+    ) -> fmt::Result {
         let reverse_lookup = self.reverse_lookup();
         let mut added_keys: HashSet<&str> = HashSet::with_capacity(self.inner.len());
         for (key, entry) in self.inner.iter() {
@@ -40,7 +40,13 @@ impl fmt::Display for Yarn1Lockfile {
             // Keys must be sorted before they get wrapped
             keys.sort();
 
-            let wrapped_keys = keys.into_iter().map(maybe_wrap).collect::<Vec<_>>();
+            let wrapped_keys = keys
+                .into_iter()
+                .map(|key| match quote_all_keys {
+                    true => wrap(key).into(),
+                    false => maybe_wrap(key),
+                })
+                .collect::<Vec<_>>();
             let key_line = wrapped_keys.join(", ");
 
             f.write_fmt(format_args!("\n{}:\n{}\n", key_line, entry))?;
@@ -49,6 +55,16 @@ impl fmt::Display for Yarn1Lockfile {
     }
 }
 
+impl fmt::Display for Yarn1Lockfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            "# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.\n# yarn lockfile \
+             v1\n\n",
+        )?;
+        self.write_entries(f, false)
+    }
+}
+
 impl fmt::Display for Entry {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut leading = LeadingNewline::new();
@@ -87,7 +103,7 @@ impl fmt::Display for Entry {
         }
         if let Some(registry) = &self.registry {
             f.write_fmt(format_args!(
-                "{}{INDENT}integrity {}",
+                "{}{INDENT}registry {}",
                 leading.leading(),
                 maybe_wrap(registry)
             ))?;
@@ -151,15 +167,17 @@ fn encode_map<'a, I: Iterator<Item = (&'a str, &'a str)>>(
 
 fn maybe_wrap(s: &str) -> Cow<str> {
     match should_wrap_key(s) {
-        // yarn uses JSON.stringify to escape strings
-        // we approximate this behavior using serde_json
-        true => serde_json::to_string(s)
-            .expect("failed at encoding string as json")
-            .into(),
+        true => wrap(s).into(),
         false => s.into(),
     }
 }
 
+// yarn uses JSON.stringify to escape strings
+// we approximate this behavior using serde_json
+fn wrap(s: &str) -> String {
+    serde_json::to_string(s).expect("failed at encoding string as json")
+}
+
 // Determines if we need to wrap a key
 fn should_wrap_key(s: &str) -> bool {
     // Wrap if it starts with a syml keyword
@@ -2,6 +2,7 @@ mod bun;
 mod npm;
 mod pnpm;
 mod yarn;
+mod yarnrc;
 
 use std::{
     backtrace,
@@ -13,6 +14,7 @@ use std::{
 use globwalk::fix_glob_pattern;
 use itertools::{Either, Itertools};
 use lazy_regex::{lazy_regex, Lazy};
+use node_semver::Version;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -21,6 +23,7 @@ use turborepo_lockfiles::Lockfile;
 use wax::{Any, Glob, Pattern};
 use which::which;
 
+pub use self::yarnrc::{NodeLinker, YarnRc, YARNRC};
 use crate::{
     package_json::PackageJson,
     package_manager::{bun::BunDetector, npm::NpmDetector, pnpm::PnpmDetector, yarn::YarnDetector},
@@ -93,6 +96,7 @@ pub struct WorkspaceGlobs {
     directory_inclusions: Any<'static>,
     directory_exclusions: Any<'static>,
     package_json_inclusions: Vec<String>,
+    pub raw_inclusions: Vec<String>,
     pub raw_exclusions: Vec<String>,
 }
 
@@ -152,12 +156,16 @@ impl WorkspaceGlobs {
             .map(glob_with_contextual_error)
             .collect::<Result<Vec<_>, _>>()?;
         Ok(Self {
-            directory_inclusions: any_with_contextual_error(inclusion_globs, raw_inclusions)?,
+            directory_inclusions: any_with_contextual_error(
+                inclusion_globs,
+                raw_inclusions.clone(),
+            )?,
             directory_exclusions: any_with_contextual_error(
                 exclusion_globs,
                 raw_exclusions.clone(),
             )?,
             package_json_inclusions,
+            raw_inclusions,
             raw_exclusions,
         })
     }
@@ -267,10 +275,31 @@ pub enum Error {
     Glob(String, Box<wax::BuildError>),
     #[error(transparent)]
     Lockfile(#[from] turborepo_lockfiles::Error),
+    #[error("could not determine the installed {0} version: {1:?}")]
+    UnknownVersion(&'static str, String),
+    #[error(
+        "{manager}@{installed} is installed, but package.json requires \"packageManager\": \
+         \"{manager}@{expected}\". Install the required version or run `corepack enable` to have \
+         it used automatically."
+    )]
+    VersionMismatch {
+        manager: &'static str,
+        expected: Box<Version>,
+        installed: Box<Version>,
+    },
+    #[error(
+        "package.json pins \"packageManager\": \"{pinned}\", but this repository uses {detected}"
+    )]
+    ManagerMismatch {
+        pinned: String,
+        detected: &'static str,
+    },
 }
 
+// Corepack allows for a hash of the package manager to follow the version e.g.
+// `pnpm@8.6.0+sha256.abc`, the hash isn't part of the version.
 static PACKAGE_MANAGER_PATTERN: Lazy<Regex> =
-    lazy_regex!(r"(?P<manager>bun|npm|pnpm|yarn)@(?P<version>\d+\.\d+\.\d+(-.+)?)");
+    lazy_regex!(r"(?P<manager>bun|npm|pnpm|yarn)@(?P<version>\d+\.\d+\.\d+(-[^+]+)?)");
 
 impl PackageManager {
     /// Returns the set of globs for the workspace.
@@ -288,6 +317,19 @@ impl PackageManager {
                 .for_each(|inclusion| exclusions.push(format!("{inclusion}/node_modules/**")));
         }
 
+        // Berry's cache and unplugged folders can be moved out of .yarn, we need to
+        // exclude them so we don't pick up the package.json of unplugged packages
+        if *self == PackageManager::Berry {
+            let yarnrc = YarnRc::load(root_path)?;
+            exclusions.extend(
+                [yarnrc.cache_folder(), yarnrc.unplugged_folder()]
+                    .into_iter()
+                    .flatten()
+                    .filter(|folder| !folder.starts_with(".yarn/"))
+                    .map(|folder| folder.to_string()),
+            );
+        }
+
         let globs = WorkspaceGlobs::new(inclusions, exclusions)?;
         Ok(globs)
     }
@@ -522,6 +564,91 @@ impl PackageManager {
     pub fn lockfile_path(&self, turbo_root: &AbsoluteSystemPath) -> AbsoluteSystemPathBuf {
         turbo_root.join_component(self.lockfile_name())
     }
+
+    /// The name of the binary used to invoke the package manager
+    pub fn command(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Pnpm | PackageManager::Pnpm6 => "pnpm",
+            PackageManager::Yarn | PackageManager::Berry => "yarn",
+            PackageManager::Bun => "bun",
+        }
+    }
+
+    /// Returns the version of the package manager that runs in the repository.
+    /// The binary is invoked from the repository root so any Corepack shims or
+    /// `yarnPath` settings resolve the version the same way they would for
+    /// a user.
+    pub fn get_installed_version(&self, repo_root: &AbsoluteSystemPath) -> Result<Version, Error> {
+        let output = Command::new(which(self.command())?)
+            .arg("--version")
+            .current_dir(repo_root)
+            // Corepack prompts before downloading a pinned version
+            .env("COREPACK_ENABLE_DOWNLOAD_PROMPT", "0")
+            .output()?;
+        let stdout = String::from_utf8(output.stdout)?;
+        parse_version_output(&stdout)
+            .ok_or_else(|| Error::UnknownVersion(self.command(), stdout.trim().to_string()))
+    }
+
+    /// Returns the version pinned by the `packageManager` field of the root
+    /// package.json if it pins this package manager.
+    pub fn get_pinned_version(
+        &self,
+        root_package_json: &PackageJson,
+    ) -> Result<Option<Version>, Error> {
+        let Some(package_manager) = &root_package_json.package_manager else {
+            return Ok(None);
+        };
+        let (manager, version) = Self::parse_package_manager_string(package_manager)?;
+        if manager != self.command() {
+            return Ok(None);
+        }
+        Ok(Some(version.parse()?))
+    }
+
+    /// Verifies that the installed package manager is the version pinned by
+    /// the `packageManager` field. Repositories without a pinned version
+    /// always pass, a pin for a different package manager never does.
+    pub fn check_version(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+        root_package_json: &PackageJson,
+    ) -> Result<(), Error> {
+        let Some(package_manager) = &root_package_json.package_manager else {
+            return Ok(());
+        };
+        let (manager, version) = Self::parse_package_manager_string(package_manager)?;
+        if manager != self.command() {
+            return Err(Error::ManagerMismatch {
+                pinned: package_manager.clone(),
+                detected: self.command(),
+            });
+        }
+        let expected: Version = version.parse()?;
+        let installed = self.get_installed_version(repo_root)?;
+        if installed != expected {
+            return Err(Error::VersionMismatch {
+                manager: self.command(),
+                expected: Box::new(expected),
+                installed: Box::new(installed),
+            });
+        }
+        Ok(())
+    }
+}
+
+// Package managers may print warnings before the version, the version is
+// always on the last line
+fn parse_version_output(output: &str) -> Option<Version> {
+    output
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .last()?
+        .trim_start_matches('v')
+        .parse()
+        .ok()
 }
 
 #[cfg(test)]
@@ -626,6 +753,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_berry_custom_cache_ignores() -> Result<(), Error> {
+        let tmpdir = tempdir()?;
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmpdir.path())?;
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(r#"{"workspaces": ["packages/*"]}"#)?;
+        repo_root
+            .join_component(".yarnrc.yml")
+            .create_with_contents(
+                "cacheFolder: ./yarn-cache\npnpUnpluggedFolder: /tmp/unplugged\n",
+            )?;
+
+        let globs = PackageManager::Berry.get_workspace_globs(&repo_root)?;
+        let ignores: HashSet<String> = HashSet::from_iter(globs.raw_exclusions);
+        let expected: HashSet<String> = ["**/node_modules", "**/.git", "**/.yarn", "yarn-cache"]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(ignores, expected);
+        Ok(())
+    }
+
     #[test]
     fn test_parse_package_manager_string() {
         let tests = vec![
@@ -692,6 +842,13 @@ mod tests {
                 expected_version: "111.0.1".to_owned(),
                 expected_error: false,
             },
+            TestCase {
+                name: "ignores corepack hashes".to_owned(),
+                package_manager: "pnpm@8.6.0-rc.1+sha256.abc123".to_owned(),
+                expected_manager: "pnpm".to_owned(),
+                expected_version: "8.6.0-rc.1".to_owned(),
+                expected_error: false,
+            },
             TestCase {
                 name: "supports bun".to_owned(),
                 package_manager: "bun@1.0.1".to_owned(),
@@ -745,6 +902,57 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_get_pinned_version() -> Result<(), Error> {
+        let mut package_json = PackageJson {
+            package_manager: Some("pnpm@8.6.0+sha256.abc123".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            PackageManager::Pnpm.get_pinned_version(&package_json)?,
+            Some("8.6.0".parse()?)
+        );
+        // A pin for a different package manager doesn't apply
+        assert_eq!(PackageManager::Npm.get_pinned_version(&package_json)?, None);
+
+        package_json.package_manager = None;
+        assert_eq!(
+            PackageManager::Pnpm.get_pinned_version(&package_json)?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_version_other_manager() -> Result<(), Error> {
+        let repo_root = tempdir()?;
+        let repo_root = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        let package_json = PackageJson {
+            package_manager: Some("pnpm@8.6.0".to_string()),
+            ..Default::default()
+        };
+        // The pin is rejected before any package manager gets invoked
+        assert!(matches!(
+            PackageManager::Npm.check_version(&repo_root, &package_json),
+            Err(Error::ManagerMismatch {
+                detected: "npm",
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_version_output() {
+        assert_eq!(parse_version_output("8.19.4\n"), "8.19.4".parse().ok());
+        assert_eq!(
+            parse_version_output("warning: something\nv1.0.1\n"),
+            "1.0.1".parse().ok()
+        );
+        assert_eq!(parse_version_output("command not found"), None);
+        assert_eq!(parse_version_output(""), None);
+    }
+
     #[test]
     fn test_detect_multiple_package_managers() -> Result<(), Error> {
         let repo_root = tempdir()?;
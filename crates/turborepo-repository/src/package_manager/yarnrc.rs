@@ -0,0 +1,226 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    io,
+};
+
+use serde::{Deserialize, Serialize};
+use turbopath::AbsoluteSystemPath;
+
+use crate::package_manager::Error;
+
+pub const YARNRC: &str = ".yarnrc.yml";
+
+const DEFAULT_CACHE_FOLDER: &str = ".yarn/cache";
+const DEFAULT_UNPLUGGED_FOLDER: &str = ".yarn/unplugged";
+const PACKAGE_EXTENSIONS: &str = "packageExtensions";
+
+/// The subset of berry's `.yarnrc.yml` that changes how packages get
+/// installed. Other settings, such as registry auth tokens, are left out.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct YarnRc {
+    #[serde(default)]
+    pub node_linker: NodeLinker,
+    pub pnp_mode: Option<String>,
+    pub cache_folder: Option<String>,
+    pub enable_global_cache: Option<bool>,
+    pub pnp_unplugged_folder: Option<String>,
+    #[serde(default)]
+    pub package_extensions: BTreeMap<String, serde_yaml::Value>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NodeLinker {
+    #[default]
+    Pnp,
+    Pnpm,
+    NodeModules,
+}
+
+impl YarnRc {
+    /// Loads `.yarnrc.yml` from the repository root, a missing file results in
+    /// yarn's defaults.
+    pub fn load(repo_root: &AbsoluteSystemPath) -> Result<Self, Error> {
+        match repo_root.join_component(YARNRC).read() {
+            Ok(contents) => Self::from_bytes(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn from_bytes(contents: &[u8]) -> Result<Self, Error> {
+        // An empty file is parsed as null
+        let yarnrc: Option<Self> = serde_yaml::from_slice(contents)?;
+        Ok(yarnrc.unwrap_or_default())
+    }
+
+    pub fn is_pnp(&self) -> bool {
+        self.node_linker == NodeLinker::Pnp
+    }
+
+    /// Folder where yarn stores package archives relative to the repository
+    /// root. Returns `None` if the folder lives outside of the repository.
+    pub fn cache_folder(&self) -> Option<&str> {
+        repo_relative(self.cache_folder.as_deref().unwrap_or(DEFAULT_CACHE_FOLDER))
+    }
+
+    /// Folder where yarn extracts packages that can't be loaded from their
+    /// archives. Returns `None` if the folder lives outside of the repository.
+    pub fn unplugged_folder(&self) -> Option<&str> {
+        repo_relative(
+            self.pnp_unplugged_folder
+                .as_deref()
+                .unwrap_or(DEFAULT_UNPLUGGED_FOLDER),
+        )
+    }
+
+    /// Whether package archives are kept in the repository's cache folder.
+    /// Yarn 4 defaults to the global cache, but older versions don't so we
+    /// only rule out the local cache if it has been explicitly disabled.
+    pub fn uses_local_cache(&self) -> bool {
+        self.enable_global_cache != Some(true)
+    }
+
+    /// Removes `packageExtensions` entries that target packages not found in
+    /// `package_names`. All other settings are left untouched. Returns `None`
+    /// if no entries need to be removed so the original file can be used
+    /// as is.
+    pub fn prune_package_extensions(
+        contents: &[u8],
+        package_names: &HashSet<String>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let Some(mut yarnrc): Option<serde_yaml::Mapping> = serde_yaml::from_slice(contents)?
+        else {
+            return Ok(None);
+        };
+        let Some(serde_yaml::Value::Mapping(extensions)) = yarnrc.get_mut(PACKAGE_EXTENSIONS)
+        else {
+            return Ok(None);
+        };
+        let original_len = extensions.len();
+        extensions.retain(|descriptor, _| {
+            descriptor.as_str().map_or(true, |descriptor| {
+                package_names.contains(descriptor_name(descriptor))
+            })
+        });
+        if extensions.len() == original_len {
+            return Ok(None);
+        }
+        if extensions.is_empty() {
+            yarnrc.remove(PACKAGE_EXTENSIONS);
+        }
+        Ok(Some(serde_yaml::to_string(&yarnrc)?.into_bytes()))
+    }
+}
+
+// Yarn allows for paths outside of the repository, we don't attempt to handle
+// those as they won't be part of any workspace or pruned output
+fn repo_relative(path: &str) -> Option<&str> {
+    if path.starts_with('/') || path.starts_with('~') || path.starts_with("..") {
+        return None;
+    }
+    let path = path.strip_prefix("./").unwrap_or(path);
+    Some(path.trim_end_matches('/'))
+}
+
+// Extension keys are descriptors e.g. `react-dom@*` or `@babel/core@^7`,
+// the range is optional.
+fn descriptor_name(descriptor: &str) -> &str {
+    let scope_offset = usize::from(descriptor.starts_with('@'));
+    match descriptor[scope_offset..].find('@') {
+        Some(idx) => &descriptor[..idx + scope_offset],
+        None => descriptor,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test]
+    fn test_missing_settings_use_defaults() {
+        let yarnrc = YarnRc::from_bytes(b"yarnPath: .yarn/releases/yarn-3.5.1.cjs\n").unwrap();
+        assert!(yarnrc.is_pnp());
+        assert_eq!(yarnrc.cache_folder(), Some(DEFAULT_CACHE_FOLDER));
+        assert_eq!(yarnrc.unplugged_folder(), Some(DEFAULT_UNPLUGGED_FOLDER));
+        assert!(yarnrc.uses_local_cache());
+        assert_eq!(YarnRc::from_bytes(b"").unwrap(), YarnRc::default());
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        let yarnrc = YarnRc::from_bytes(
+            b"nodeLinker: node-modules\ncacheFolder: ./deps/cache/\nenableGlobalCache: \
+              true\npackageExtensions:\n  \"react-dom@*\":\n    peerDependencies:\n      react: \
+              \"*\"\n",
+        )
+        .unwrap();
+        assert_eq!(yarnrc.node_linker, NodeLinker::NodeModules);
+        assert!(!yarnrc.is_pnp());
+        assert_eq!(yarnrc.cache_folder(), Some("deps/cache"));
+        assert!(!yarnrc.uses_local_cache());
+        assert_eq!(
+            yarnrc.package_extensions.keys().collect::<Vec<_>>(),
+            vec!["react-dom@*"]
+        );
+    }
+
+    #[test]
+    fn test_unrelated_settings_ignored() {
+        let yarnrc = YarnRc::from_bytes(
+            b"pnpMode: loose\nnpmAuthToken: secret\nnpmRegistryServer: \
+              https://registry.example.com\n",
+        )
+        .unwrap();
+        assert_eq!(
+            yarnrc,
+            YarnRc {
+                pnp_mode: Some("loose".into()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test_case("/tmp/yarn-cache", None ; "absolute")]
+    #[test_case("~/.yarn/berry/cache", None ; "home")]
+    #[test_case("../cache", None ; "outside repo")]
+    #[test_case("./cache/", Some("cache") ; "relative")]
+    fn test_repo_relative(path: &str, expected: Option<&str>) {
+        assert_eq!(repo_relative(path), expected);
+    }
+
+    #[test_case("react-dom@*", "react-dom" ; "range")]
+    #[test_case("@babel/core@^7.0.0", "@babel/core" ; "scoped")]
+    #[test_case("@babel/core", "@babel/core" ; "scoped no range")]
+    #[test_case("lodash", "lodash" ; "no range")]
+    fn test_descriptor_name(descriptor: &str, expected: &str) {
+        assert_eq!(descriptor_name(descriptor), expected);
+    }
+
+    #[test]
+    fn test_prune_package_extensions() {
+        let contents = b"nodeLinker: pnp\npackageExtensions:\n  \"react-dom@*\":\n    \
+                         peerDependencies:\n      react: \"*\"\n  \"@babel/core@^7\":\n    \
+                         dependencies:\n      debug: \"*\"\n";
+        let names = HashSet::from(["react-dom".to_string()]);
+        let pruned = YarnRc::prune_package_extensions(contents, &names)
+            .unwrap()
+            .unwrap();
+        let pruned = YarnRc::from_bytes(&pruned).unwrap();
+        assert!(pruned.is_pnp());
+        assert_eq!(
+            pruned.package_extensions.keys().collect::<Vec<_>>(),
+            vec!["react-dom@*"]
+        );
+
+        let names = HashSet::from(["react-dom".to_string(), "@babel/core".to_string()]);
+        assert_eq!(
+            YarnRc::prune_package_extensions(contents, &names).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turborepo_scm::{LibGit2, SCM};
+
+// Compares hashing a package via the git binary against reading the repository
+// with libgit2. Uses this crate in the current checkout as the package.
+fn bench_package_file_hashes(c: &mut Criterion) {
+    let crate_dir = AbsoluteSystemPathBuf::try_from(env!("CARGO_MANIFEST_DIR")).unwrap();
+    let repo_root = crate_dir
+        .ancestors()
+        .find(|dir| dir.join_component(".git").exists())
+        .expect("benchmark must be run from within a git checkout")
+        .to_owned();
+    let package_path = AnchoredSystemPathBuf::new(&repo_root, &crate_dir).unwrap();
+
+    let git = SCM::new(&repo_root);
+    assert!(
+        matches!(git, SCM::Git(_)),
+        "git binary is required to compare backends"
+    );
+    let libgit2 = SCM::LibGit2(LibGit2::find(&repo_root).unwrap());
+
+    let mut group = c.benchmark_group("get_package_file_hashes");
+    for (name, scm) in [("git", &git), ("libgit2", &libgit2)] {
+        group.bench_function(BenchmarkId::new(name, package_path.as_str()), |b| {
+            b.iter(|| {
+                scm.get_package_file_hashes::<&str>(&repo_root, &package_path, &[])
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_package_file_hashes);
+criterion_main!(benches);
@@ -1,24 +1,22 @@
 use std::{backtrace::Backtrace, collections::HashSet, path::PathBuf, process::Command};
 
 use turbopath::{
-    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeUnixPath,
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+    RelativeUnixPath,
 };
 
-use crate::{Error, Git, SCM};
+use crate::{
+    package_deps::{get_package_file_hashes_from_inputs, GitHashes},
+    Error, Git, SourceControl, SCM,
+};
 
 impl SCM {
     pub fn get_current_branch(&self, path: &AbsoluteSystemPath) -> Result<String, Error> {
-        match self {
-            Self::Git(git) => git.get_current_branch(),
-            Self::Manual => Err(Error::GitRequired(path.to_owned())),
-        }
+        self.require_source_control(path)?.get_current_branch()
     }
 
     pub fn get_current_sha(&self, path: &AbsoluteSystemPath) -> Result<String, Error> {
-        match self {
-            Self::Git(git) => git.get_current_sha(),
-            Self::Manual => Err(Error::GitRequired(path.to_owned())),
-        }
+        self.require_source_control(path)?.get_current_sha()
     }
 
     pub fn changed_files(
@@ -27,10 +25,8 @@ impl SCM {
         from_commit: Option<&str>,
         to_commit: &str,
     ) -> Result<HashSet<AnchoredSystemPathBuf>, Error> {
-        match self {
-            Self::Git(git) => git.changed_files(turbo_root, from_commit, to_commit),
-            Self::Manual => Err(Error::GitRequired(turbo_root.to_owned())),
-        }
+        self.require_source_control(turbo_root)?
+            .changed_files(turbo_root, from_commit, to_commit)
     }
 
     pub fn previous_content(
@@ -38,10 +34,16 @@ impl SCM {
         from_commit: &str,
         file_path: &AbsoluteSystemPath,
     ) -> Result<Vec<u8>, Error> {
-        match self {
-            Self::Git(git) => git.previous_content(from_commit, file_path),
-            Self::Manual => Err(Error::GitRequired(file_path.to_owned())),
-        }
+        self.require_source_control(file_path)?
+            .previous_content(from_commit, file_path)
+    }
+
+    fn require_source_control(
+        &self,
+        path: &AbsoluteSystemPath,
+    ) -> Result<&dyn SourceControl, Error> {
+        self.source_control()
+            .ok_or_else(|| Error::GitRequired(path.to_owned()))
     }
 }
 
@@ -79,7 +81,11 @@ pub fn changed_files(
         .collect::<HashSet<_>>())
 }
 
-impl Git {
+impl SourceControl for Git {
+    fn root(&self) -> &AbsoluteSystemPath {
+        &self.root
+    }
+
     fn get_current_branch(&self) -> Result<String, Error> {
         let output = self.execute_git_command(&["branch", "--show-current"], "")?;
         let output = String::from_utf8(output)?;
@@ -128,6 +134,44 @@ impl Git {
         Ok(files)
     }
 
+    fn previous_content(
+        &self,
+        from_commit: &str,
+        file_path: &AbsoluteSystemPath,
+    ) -> Result<Vec<u8>, Error> {
+        let anchored_file_path = self.root.anchor(file_path)?;
+        let mut command = Command::new(self.bin.as_std_path());
+        let command = command
+            .arg("show")
+            .arg(format!("{}:{}", from_commit, anchored_file_path.as_str()))
+            .current_dir(&self.root);
+
+        let output = command.output()?;
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(Error::Git(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+                Backtrace::capture(),
+            ))
+        }
+    }
+
+    fn get_package_file_hashes(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        package_path: &AnchoredSystemPath,
+        inputs: &[&str],
+    ) -> Result<GitHashes, Error> {
+        if inputs.is_empty() {
+            self.get_package_file_hashes_from_index(turbo_root, package_path)
+        } else {
+            get_package_file_hashes_from_inputs(&self.root, turbo_root, package_path, inputs)
+        }
+    }
+}
+
+impl Git {
     fn execute_git_command(&self, args: &[&str], pathspec: &str) -> Result<Vec<u8>, Error> {
         let mut command = Command::new(self.bin.as_std_path());
         command.args(args).current_dir(&self.root);
@@ -171,29 +215,6 @@ impl Git {
         let anchored_to_turbo_root_file_path = turbo_root.anchor(&absolute_file_path)?;
         Ok(anchored_to_turbo_root_file_path)
     }
-
-    fn previous_content(
-        &self,
-        from_commit: &str,
-        file_path: &AbsoluteSystemPath,
-    ) -> Result<Vec<u8>, Error> {
-        let anchored_file_path = self.root.anchor(file_path)?;
-        let mut command = Command::new(self.bin.as_std_path());
-        let command = command
-            .arg("show")
-            .arg(format!("{}:{}", from_commit, anchored_file_path.as_str()))
-            .current_dir(&self.root);
-
-        let output = command.output()?;
-        if output.status.success() {
-            Ok(output.stdout)
-        } else {
-            Err(Error::Git(
-                String::from_utf8_lossy(&output.stderr).to_string(),
-                Backtrace::capture(),
-            ))
-        }
-    }
 }
 
 /// Finds the content of a file at a previous commit. Assumes file is in a git
@@ -4,6 +4,8 @@
 
 use std::{
     backtrace::{self, Backtrace},
+    collections::HashSet,
+    fmt,
     io::Read,
     process::{Child, Command},
 };
@@ -11,13 +13,21 @@ use std::{
 use bstr::io::BufReadExt;
 use thiserror::Error;
 use tracing::debug;
-use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, PathError, RelativeUnixPathBuf};
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+    PathError, RelativeUnixPathBuf,
+};
+
+use crate::package_deps::GitHashes;
+pub use crate::sapling::Sapling;
 
 pub mod git;
 mod hash_object;
+mod libgit2;
 mod ls_tree;
 pub mod manual;
 pub mod package_deps;
+mod sapling;
 mod status;
 
 #[derive(Debug, Error)]
@@ -26,9 +36,11 @@ pub enum Error {
     Git2(git2::Error, String, #[backtrace] backtrace::Backtrace),
     #[error("git error: {0}")]
     Git(String, #[backtrace] backtrace::Backtrace),
+    #[error("sapling error: {0}")]
+    Sapling(String, #[backtrace] backtrace::Backtrace),
     #[error(
-        "{0} is not part of a git repository. git is required for operations based on source \
-         control"
+        "{0} is not part of a git or sapling repository. source control is required for \
+         operations based on source control"
     )]
     GitRequired(AbsoluteSystemPathBuf),
     #[error(
@@ -68,6 +80,48 @@ impl Error {
     pub(crate) fn git2_error_context(error: git2::Error, error_context: String) -> Self {
         Error::Git2(error, error_context, Backtrace::capture())
     }
+
+    pub(crate) fn sapling_error(s: impl Into<String>) -> Self {
+        Error::Sapling(s.into(), Backtrace::capture())
+    }
+}
+
+/// The operations turbo needs from a source control system in order to detect
+/// changed files and hash the contents of packages.
+pub trait SourceControl: fmt::Debug {
+    /// The root of the repository, paths reported by source control are
+    /// relative to this directory.
+    fn root(&self) -> &AbsoluteSystemPath;
+
+    fn get_current_branch(&self) -> Result<String, Error>;
+
+    fn get_current_sha(&self) -> Result<String, Error>;
+
+    /// Files that differ between the working directory and `to_commit`, and
+    /// if provided, between `to_commit` and its merge base with
+    /// `from_commit`. Untracked files are included.
+    fn changed_files(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        from_commit: Option<&str>,
+        to_commit: &str,
+    ) -> Result<HashSet<AnchoredSystemPathBuf>, Error>;
+
+    fn previous_content(
+        &self,
+        from_commit: &str,
+        file_path: &AbsoluteSystemPath,
+    ) -> Result<Vec<u8>, Error>;
+
+    /// Hashes the files in a package that aren't ignored, or the files
+    /// matching `inputs` if any are provided. Hashes are keyed by their path
+    /// relative to the package.
+    fn get_package_file_hashes(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        package_path: &AnchoredSystemPath,
+        inputs: &[&str],
+    ) -> Result<GitHashes, Error>;
 }
 
 fn read_git_error_to_string<R: Read>(stderr: &mut R) -> Option<String> {
@@ -125,6 +179,9 @@ pub(crate) fn wait_for_success<R: Read, T>(
 pub struct Git {
     root: AbsoluteSystemPathBuf,
     bin: AbsoluteSystemPathBuf,
+    // Files outside of a sparse checkout are listed by ls-tree, but aren't
+    // present on disk or reported by status
+    sparse_checkout: bool,
 }
 
 #[derive(Debug, Error)]
@@ -149,10 +206,92 @@ impl Git {
         });
         let root =
             find_git_root(path_in_repo).map_err(|e| GitError::Root(path_in_repo.to_owned(), e))?;
-        Ok(Self { root, bin })
+        Ok(Self::new(root, bin))
+    }
+
+    fn new(root: AbsoluteSystemPathBuf, bin: AbsoluteSystemPathBuf) -> Self {
+        let sparse_checkout = is_sparse_checkout(&bin, &root);
+        Self {
+            root,
+            bin,
+            sparse_checkout,
+        }
+    }
+
+    /// Returns a `Git` for a repository nested inside of this one e.g. a
+    /// submodule
+    pub(crate) fn nested(&self, root: AbsoluteSystemPathBuf) -> Self {
+        Self::new(root, self.bin.clone())
+    }
+}
+
+fn is_sparse_checkout(bin: &AbsoluteSystemPath, root: &AbsoluteSystemPath) -> bool {
+    Command::new(bin.as_std_path())
+        .args(["config", "--bool", "core.sparseCheckout"])
+        .current_dir(root)
+        .output()
+        .map(|output| {
+            // git config exits with 1 if the key isn't set
+            output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "true"
+        })
+        .unwrap_or(false)
+}
+
+/// Finds the root of a repository nested within `git_root` that contains
+/// `path`, such as a submodule. Git commands run within a nested repository
+/// operate on that repository instead of the one at `git_root`.
+pub(crate) fn find_nested_repo_root(
+    git_root: &AbsoluteSystemPath,
+    path: &AbsoluteSystemPath,
+) -> Option<AbsoluteSystemPathBuf> {
+    git_root.anchor(path).ok()?;
+    path.ancestors()
+        .take_while(|dir| *dir != git_root)
+        .find(|dir| dir.join_component(".git").exists())
+        .map(|dir| dir.to_owned())
+}
+
+/// Reads the git repository directly instead of going through the git binary.
+/// This allows for hashing in environments without git installed e.g. minimal
+/// Docker images.
+#[derive(Debug)]
+pub struct LibGit2 {
+    root: AbsoluteSystemPathBuf,
+}
+
+impl LibGit2 {
+    pub fn find(path_in_repo: &AbsoluteSystemPath) -> Result<Self, Error> {
+        let repo = git2::Repository::discover(path_in_repo)
+            .map_err(|e| Error::git2_error_context(e, path_in_repo.to_string()))?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| Error::git_error(format!("{path_in_repo} is in a bare repository")))?;
+        let root = root_from_realpath(path_in_repo, &AbsoluteSystemPathBuf::try_from(workdir)?)?;
+        Ok(Self { root })
     }
 }
 
+// Source control tools report the real path of the repository root. To match
+// the git binary we walk up from the provided path instead of using the real
+// path so symlinked repositories are anchored where the user ran turbo.
+pub(crate) fn root_from_realpath(
+    path_in_repo: &AbsoluteSystemPath,
+    root: &AbsoluteSystemPath,
+) -> Result<AbsoluteSystemPathBuf, Error> {
+    let depth = root
+        .to_realpath()?
+        .anchor(path_in_repo.to_realpath()?)?
+        .components()
+        .count();
+    Ok(path_in_repo
+        .ancestors()
+        .nth(depth)
+        .ok_or_else(|| {
+            Error::git_error(format!("unable to find repository root of {path_in_repo}"))
+        })?
+        .to_owned())
+}
+
 fn find_git_root(turbo_root: &AbsoluteSystemPath) -> Result<AbsoluteSystemPathBuf, Error> {
     let rev_parse = Command::new("git")
         .args(["rev-parse", "--show-cdup"])
@@ -183,15 +322,38 @@ fn find_git_root(turbo_root: &AbsoluteSystemPath) -> Result<AbsoluteSystemPathBu
 #[derive(Debug)]
 pub enum SCM {
     Git(Git),
+    LibGit2(LibGit2),
+    Sapling(Sapling),
     Manual,
 }
 
 impl SCM {
     pub fn new(path_in_repo: &AbsoluteSystemPath) -> SCM {
-        Git::find(path_in_repo).map(SCM::Git).unwrap_or_else(|e| {
-            debug!("{}, continuing with manual hashing", e);
-            SCM::Manual
-        })
+        Git::find(path_in_repo)
+            .map(SCM::Git)
+            .or_else(|e| {
+                debug!("{}, attempting to read git repository directly", e);
+                LibGit2::find(path_in_repo).map(SCM::LibGit2)
+            })
+            .or_else(|e| {
+                debug!("{}, attempting to use sapling", e);
+                Sapling::find(path_in_repo).map(SCM::Sapling)
+            })
+            .unwrap_or_else(|e| {
+                debug!("{}, continuing with manual hashing", e);
+                SCM::Manual
+            })
+    }
+
+    /// The source control system backing this `SCM`, `None` if files are
+    /// being hashed manually.
+    pub fn source_control(&self) -> Option<&dyn SourceControl> {
+        match self {
+            SCM::Git(git) => Some(git),
+            SCM::LibGit2(git) => Some(git),
+            SCM::Sapling(sapling) => Some(sapling),
+            SCM::Manual => None,
+        }
     }
 }
 
@@ -0,0 +1,475 @@
+//! Implementations of the git operations we need that read the repository
+//! directly with libgit2. Each operation mirrors the output of the git command
+//! used by `Git` so hashes are identical regardless of the backend in use.
+
+use std::{collections::HashSet, path::Path};
+
+use git2::{
+    Diff, DiffOptions, ErrorCode, ObjectType, Oid, Repository, StatusOptions, TreeWalkMode,
+    TreeWalkResult,
+};
+use turbopath::{
+    AbsoluteSystemPath, AnchoredSystemPath, AnchoredSystemPathBuf, RelativeUnixPath,
+    RelativeUnixPathBuf,
+};
+
+use crate::{
+    find_nested_repo_root,
+    hash_object::hash_objects,
+    ls_tree::LsTree,
+    package_deps::{
+        append_submodule_hashes, get_package_file_hashes_from_inputs, remove_missing_files,
+        GitHashes,
+    },
+    Error, LibGit2, SourceControl,
+};
+
+impl SourceControl for LibGit2 {
+    fn root(&self) -> &AbsoluteSystemPath {
+        &self.root
+    }
+
+    fn get_current_branch(&self) -> Result<String, Error> {
+        let repo = self.open()?;
+        let head = repo
+            .find_reference("HEAD")
+            .map_err(|e| Error::git2_error_context(e, "HEAD".to_string()))?;
+        // Like `git branch --show-current`, a detached HEAD results in an empty string
+        Ok(head
+            .symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .unwrap_or_default()
+            .to_owned())
+    }
+
+    fn get_current_sha(&self) -> Result<String, Error> {
+        let repo = self.open()?;
+        let commit = repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .map_err(|e| Error::git2_error_context(e, "HEAD".to_string()))?;
+        Ok(commit.id().to_string())
+    }
+
+    fn changed_files(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        from_commit: Option<&str>,
+        to_commit: &str,
+    ) -> Result<HashSet<AnchoredSystemPathBuf>, Error> {
+        let repo = self.open()?;
+        let pathspec = self.root.anchor(turbo_root)?.to_unix();
+        let mut files = HashSet::new();
+
+        let to_id = resolve_commit(&repo, to_commit)?;
+        let to_tree = repo
+            .find_commit(to_id)
+            .and_then(|commit| commit.tree())
+            .map_err(|e| Error::git2_error_context(e, to_commit.to_string()))?;
+
+        // Equivalent to `git diff --name-only <to_commit>`
+        let diff = repo
+            .diff_tree_to_workdir_with_index(Some(&to_tree), Some(&mut diff_options(&pathspec)))
+            .map_err(|e| Error::git2_error_context(e, to_commit.to_string()))?;
+        self.add_files_from_diff(&mut files, turbo_root, &diff)?;
+
+        // Equivalent to `git diff --name-only <from_commit>...<to_commit>`
+        if let Some(from_commit) = from_commit {
+            let from_id = resolve_commit(&repo, from_commit)?;
+            let range = format!("{}...{}", from_commit, to_commit);
+            let merge_base_tree = repo
+                .merge_base(from_id, to_id)
+                .and_then(|merge_base| repo.find_commit(merge_base))
+                .and_then(|commit| commit.tree())
+                .map_err(|e| Error::git2_error_context(e, range.clone()))?;
+            let diff = repo
+                .diff_tree_to_tree(
+                    Some(&merge_base_tree),
+                    Some(&to_tree),
+                    Some(&mut diff_options(&pathspec)),
+                )
+                .map_err(|e| Error::git2_error_context(e, range))?;
+            self.add_files_from_diff(&mut files, turbo_root, &diff)?;
+        }
+
+        // Equivalent to `git ls-files --others --exclude-standard`
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false);
+        if !pathspec.as_str().is_empty() {
+            options.pathspec(pathspec.as_str());
+        }
+        let statuses = repo
+            .statuses(Some(&mut options))
+            .map_err(|e| Error::git2_error_context(e, turbo_root.to_string()))?;
+        for entry in statuses.iter().filter(|entry| entry.status().is_wt_new()) {
+            let path = String::from_utf8(entry.path_bytes().to_vec())?;
+            files.insert(self.reanchor_path(turbo_root, RelativeUnixPath::new(&path)?)?);
+        }
+
+        Ok(files)
+    }
+
+    fn previous_content(
+        &self,
+        from_commit: &str,
+        file_path: &AbsoluteSystemPath,
+    ) -> Result<Vec<u8>, Error> {
+        let repo = self.open()?;
+        let spec = format!("{}:{}", from_commit, self.root.anchor(file_path)?.to_unix());
+        let blob = repo
+            .revparse_single(&spec)
+            .and_then(|object| object.peel_to_blob())
+            .map_err(|e| Error::git2_error_context(e, spec))?;
+        Ok(blob.content().to_vec())
+    }
+
+    fn get_package_file_hashes(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        package_path: &AnchoredSystemPath,
+        inputs: &[&str],
+    ) -> Result<GitHashes, Error> {
+        if inputs.is_empty() {
+            self.get_package_file_hashes_from_index(turbo_root, package_path)
+        } else {
+            get_package_file_hashes_from_inputs(&self.root, turbo_root, package_path, inputs)
+        }
+    }
+}
+
+impl LibGit2 {
+    fn open(&self) -> Result<Repository, Error> {
+        Repository::open(&self.root)
+            .map_err(|e| Error::git2_error_context(e, self.root.to_string()))
+    }
+
+    fn get_package_file_hashes_from_index(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        package_path: &AnchoredSystemPath,
+    ) -> Result<GitHashes, Error> {
+        let full_pkg_path = turbo_root.resolve(package_path);
+        if let Some(nested_root) = find_nested_repo_root(&self.root, &full_pkg_path) {
+            return LibGit2 { root: nested_root }
+                .get_package_file_hashes_from_index(turbo_root, package_path);
+        }
+        let repo = self.open()?;
+        let pkg_prefix = self.root.anchor(&full_pkg_path)?.to_unix();
+        let LsTree {
+            mut hashes,
+            submodules,
+        } = self.ls_tree(&repo, &pkg_prefix)?;
+        let sparse_checkout = repo
+            .config()
+            .and_then(|config| config.get_bool("core.sparseCheckout"))
+            .unwrap_or(false);
+        if sparse_checkout {
+            remove_missing_files(&full_pkg_path, &mut hashes);
+        }
+        // Note: to_hash is *git repo relative*
+        let to_hash = self.status(&repo, &pkg_prefix, &mut hashes)?;
+        hash_objects(&self.root, &full_pkg_path, to_hash, &mut hashes)?;
+
+        for submodule in submodules {
+            let submodule_root = full_pkg_path.join_unix_path(&submodule)?;
+            // If the submodule hasn't been checked out we use its commit hash
+            if !submodule_root.join_component(".git").exists() {
+                continue;
+            }
+            let submodule_hashes = LibGit2 {
+                root: submodule_root.clone(),
+            }
+            .get_package_file_hashes_from_index(&submodule_root, AnchoredSystemPath::new("")?)?;
+            append_submodule_hashes(&submodule, submodule_hashes, &mut hashes)?;
+        }
+        Ok(hashes)
+    }
+
+    // Equivalent to `git ls-tree -r HEAD` run in the package directory
+    fn ls_tree(
+        &self,
+        repo: &Repository,
+        pkg_prefix: &RelativeUnixPathBuf,
+    ) -> Result<LsTree, Error> {
+        let mut hashes = GitHashes::new();
+        let mut submodules = Vec::new();
+        let head_tree = repo
+            .head()
+            .and_then(|head| head.peel_to_tree())
+            .map_err(|e| Error::git2_error_context(e, "HEAD".to_string()))?;
+        let tree = if pkg_prefix.as_str().is_empty() {
+            head_tree
+        } else {
+            match head_tree.get_path(Path::new(pkg_prefix.as_str())) {
+                Ok(entry) => entry
+                    .to_object(repo)
+                    .and_then(|object| object.peel_to_tree())
+                    .map_err(|e| Error::git2_error_context(e, pkg_prefix.to_string()))?,
+                // The package hasn't been committed yet
+                Err(e) if e.code() == ErrorCode::NotFound => {
+                    return Ok(LsTree { hashes, submodules })
+                }
+                Err(e) => return Err(Error::git2_error_context(e, pkg_prefix.to_string())),
+            }
+        };
+
+        let mut path_error = None;
+        let walk_result = tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            // ls-tree -r only lists blobs and submodules
+            if !matches!(entry.kind(), Some(ObjectType::Blob | ObjectType::Commit)) {
+                return TreeWalkResult::Ok;
+            }
+            let path = String::from_utf8(entry.name_bytes().to_vec())
+                .map_err(Error::from)
+                .and_then(|name| Ok(RelativeUnixPathBuf::new(format!("{dir}{name}"))?));
+            match path {
+                Ok(path) => {
+                    if entry.kind() == Some(ObjectType::Commit) {
+                        submodules.push(path.clone());
+                    }
+                    hashes.insert(path, entry.id().to_string());
+                    TreeWalkResult::Ok
+                }
+                Err(e) => {
+                    path_error = Some(e);
+                    TreeWalkResult::Abort
+                }
+            }
+        });
+        if let Some(e) = path_error {
+            return Err(e);
+        }
+        walk_result.map_err(|e| Error::git2_error_context(e, pkg_prefix.to_string()))?;
+        Ok(LsTree { hashes, submodules })
+    }
+
+    // Equivalent to `git status --untracked-files --no-renames -- .` run in the
+    // package directory. Deleted files are removed from `hashes` and the git
+    // relative paths of all other changed files are returned.
+    fn status(
+        &self,
+        repo: &Repository,
+        pkg_prefix: &RelativeUnixPathBuf,
+        hashes: &mut GitHashes,
+    ) -> Result<Vec<RelativeUnixPathBuf>, Error> {
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false)
+            // Submodules are hashed separately
+            .exclude_submodules(true)
+            .renames_head_to_index(false)
+            .renames_index_to_workdir(false);
+        if !pkg_prefix.as_str().is_empty() {
+            options.pathspec(pkg_prefix.as_str());
+        }
+        let statuses = repo
+            .statuses(Some(&mut options))
+            .map_err(|e| Error::git2_error_context(e, pkg_prefix.to_string()))?;
+
+        let mut to_hash = Vec::new();
+        for entry in statuses.iter() {
+            let path = RelativeUnixPathBuf::new(String::from_utf8(entry.path_bytes().to_vec())?)?;
+            // Pathspecs can match siblings that share the package path as a prefix
+            let Ok(package_relative_path) = path.strip_prefix(pkg_prefix) else {
+                continue;
+            };
+            let status = entry.status();
+            if status.is_index_deleted() || status.is_wt_deleted() {
+                hashes.remove(&package_relative_path);
+            } else {
+                to_hash.push(path);
+            }
+        }
+        Ok(to_hash)
+    }
+
+    fn add_files_from_diff(
+        &self,
+        files: &mut HashSet<AnchoredSystemPathBuf>,
+        turbo_root: &AbsoluteSystemPath,
+        diff: &Diff,
+    ) -> Result<(), Error> {
+        for delta in diff.deltas() {
+            let Some(path) = delta.new_file().path().or_else(|| delta.old_file().path()) else {
+                continue;
+            };
+            let path = path
+                .to_str()
+                .ok_or_else(|| Error::git_error(format!("invalid utf8 path {}", path.display())))?;
+            files.insert(self.reanchor_path(turbo_root, RelativeUnixPath::new(path)?)?);
+        }
+        Ok(())
+    }
+
+    fn reanchor_path(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        path: &RelativeUnixPath,
+    ) -> Result<AnchoredSystemPathBuf, Error> {
+        let absolute_file_path = self.root.join_unix_path(path)?;
+        Ok(turbo_root.anchor(&absolute_file_path)?)
+    }
+}
+
+fn resolve_commit(repo: &Repository, rev: &str) -> Result<Oid, Error> {
+    repo.revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .map(|commit| commit.id())
+        .map_err(|e| Error::git2_error_context(e, rev.to_string()))
+}
+
+fn diff_options(pathspec: &RelativeUnixPathBuf) -> DiffOptions {
+    let mut options = DiffOptions::new();
+    if !pathspec.as_str().is_empty() {
+        options.pathspec(pathspec.as_str());
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, process::Command};
+
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeUnixPathBuf};
+
+    use crate::{LibGit2, SCM};
+
+    fn tmp_dir() -> (tempfile::TempDir, AbsoluteSystemPathBuf) {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = AbsoluteSystemPathBuf::try_from(tmp_dir.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+        (tmp_dir, dir)
+    }
+
+    fn require_git_cmd(repo_root: &AbsoluteSystemPathBuf, args: &[&str]) {
+        let mut cmd = Command::new("git");
+        cmd.args(args).current_dir(repo_root);
+        assert!(cmd.output().unwrap().status.success());
+    }
+
+    // Creates a repository with a mix of committed, modified, deleted and
+    // untracked files in a package and at the root
+    fn setup_repository(repo_root: &AbsoluteSystemPathBuf) {
+        let cmds: &[&[&str]] = &[
+            &["init", "."],
+            &["config", "--local", "user.name", "test"],
+            &["config", "--local", "user.email", "test@example.com"],
+        ];
+        for cmd in cmds {
+            require_git_cmd(repo_root, cmd);
+        }
+        for (path, contents) in [
+            ("package.json", "{}"),
+            ("my-pkg/package.json", "{}"),
+            ("my-pkg/committed-file", "committed bytes"),
+            ("my-pkg/modified-file", "original bytes"),
+            ("my-pkg/deleted-file", "delete me"),
+            ("my-pkg/dir/nested-file", "nested"),
+            ("my-pkg-sibling/file", "sibling"),
+            (".gitignore", "ignored-file\n"),
+        ] {
+            let file = repo_root
+                .join_unix_path(RelativeUnixPathBuf::new(path).unwrap())
+                .unwrap();
+            file.ensure_dir().unwrap();
+            file.create_with_contents(contents).unwrap();
+        }
+        require_git_cmd(repo_root, &["add", "."]);
+        require_git_cmd(repo_root, &["commit", "-m", "initial"]);
+
+        let pkg = repo_root.join_component("my-pkg");
+        pkg.join_component("modified-file")
+            .create_with_contents("modified bytes")
+            .unwrap();
+        pkg.join_component("deleted-file").remove().unwrap();
+        pkg.join_component("untracked-file")
+            .create_with_contents("untracked bytes")
+            .unwrap();
+        pkg.join_component("ignored-file")
+            .create_with_contents("ignored bytes")
+            .unwrap();
+        repo_root
+            .join_component("my-pkg-sibling")
+            .join_component("untracked-file")
+            .create_with_contents("sibling bytes")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_package_hashes_match_git() {
+        let (_tmp, repo_root) = tmp_dir();
+        setup_repository(&repo_root);
+        let git = SCM::new(&repo_root);
+        assert!(matches!(git, SCM::Git(_)), "expected git, found {:?}", git);
+        let libgit2 = SCM::LibGit2(LibGit2::find(&repo_root).unwrap());
+
+        for package in ["my-pkg", "my-pkg-sibling", ""] {
+            let package_path = AnchoredSystemPathBuf::from_raw(package).unwrap();
+            let expected = git
+                .get_package_file_hashes::<&str>(&repo_root, &package_path, &[])
+                .unwrap();
+            let actual = libgit2
+                .get_package_file_hashes::<&str>(&repo_root, &package_path, &[])
+                .unwrap();
+            assert_eq!(actual, expected, "package: '{package}'");
+        }
+    }
+
+    #[test]
+    fn test_changed_files_match_git() {
+        let (_tmp, repo_root) = tmp_dir();
+        setup_repository(&repo_root);
+        require_git_cmd(&repo_root, &["checkout", "-b", "feature"]);
+        require_git_cmd(&repo_root, &["add", "."]);
+        require_git_cmd(&repo_root, &["commit", "-m", "feature"]);
+        repo_root
+            .join_component("new-root-file")
+            .create_with_contents("new")
+            .unwrap();
+
+        let git = SCM::new(&repo_root);
+        let libgit2 = SCM::LibGit2(LibGit2::find(&repo_root).unwrap());
+        for (turbo_root, from_commit) in [
+            (repo_root.clone(), Some("HEAD~1")),
+            (repo_root.clone(), None),
+            (repo_root.join_component("my-pkg"), Some("HEAD~1")),
+        ] {
+            let expected = git.changed_files(&turbo_root, from_commit, "HEAD").unwrap();
+            let actual = libgit2
+                .changed_files(&turbo_root, from_commit, "HEAD")
+                .unwrap();
+            assert_eq!(
+                sorted(actual),
+                sorted(expected),
+                "{turbo_root} {from_commit:?}"
+            );
+        }
+
+        assert_eq!(
+            libgit2.get_current_branch(&repo_root).unwrap(),
+            git.get_current_branch(&repo_root).unwrap()
+        );
+        assert_eq!(
+            libgit2.get_current_sha(&repo_root).unwrap(),
+            git.get_current_sha(&repo_root).unwrap()
+        );
+        let file = repo_root.join_components(&["my-pkg", "modified-file"]);
+        assert_eq!(
+            libgit2.previous_content("HEAD~1", &file).unwrap(),
+            b"original bytes".to_vec()
+        );
+    }
+
+    fn sorted(files: HashSet<AnchoredSystemPathBuf>) -> Vec<String> {
+        let mut files = files.into_iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+}
@@ -4,7 +4,7 @@ use itertools::{Either, Itertools};
 use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, PathError, RelativeUnixPathBuf};
 
-use crate::{hash_object::hash_objects, Error, Git, SCM};
+use crate::{find_nested_repo_root, hash_object::hash_objects, ls_tree::LsTree, Error, Git, SCM};
 
 pub type GitHashes = HashMap<RelativeUnixPathBuf, String>;
 
@@ -28,26 +28,29 @@ impl SCM {
         package_path: &AnchoredSystemPath,
         inputs: &[S],
     ) -> Result<GitHashes, Error> {
-        match self {
-            SCM::Manual => crate::manual::get_package_file_hashes_from_processing_gitignore(
+        let Some(scm) = self.source_control() else {
+            return crate::manual::get_package_file_hashes_from_processing_gitignore(
                 turbo_root,
                 package_path,
                 inputs,
-            ),
-            SCM::Git(git) => git
-                .get_package_file_hashes(turbo_root, package_path, inputs)
-                .or_else(|e| {
-                    debug!(
-                        "failed to use git to hash files: {}. Falling back to manual",
-                        e
-                    );
-                    crate::manual::get_package_file_hashes_from_processing_gitignore(
-                        turbo_root,
-                        package_path,
-                        inputs,
-                    )
-                }),
-        }
+            );
+        };
+        let inputs = inputs
+            .iter()
+            .map(|input| input.as_ref())
+            .collect::<Vec<_>>();
+        scm.get_package_file_hashes(turbo_root, package_path, &inputs)
+            .or_else(|e| {
+                debug!(
+                    "failed to use source control to hash files: {}. Falling back to manual",
+                    e
+                );
+                crate::manual::get_package_file_hashes_from_processing_gitignore(
+                    turbo_root,
+                    package_path,
+                    &inputs,
+                )
+            })
     }
 
     pub fn hash_files(
@@ -55,9 +58,9 @@ impl SCM {
         turbo_root: &AbsoluteSystemPath,
         files: impl Iterator<Item = impl AsRef<AnchoredSystemPath>>,
     ) -> Result<GitHashes, Error> {
-        match self {
-            SCM::Manual => crate::manual::hash_files(turbo_root, files, false),
-            SCM::Git(git) => git.hash_files(turbo_root, files),
+        match self.source_control() {
+            Some(scm) => hash_files(scm.root(), turbo_root, files),
+            None => crate::manual::hash_files(turbo_root, files, false),
         }
     }
 
@@ -74,114 +77,155 @@ impl SCM {
 }
 
 impl Git {
-    fn get_package_file_hashes<S: AsRef<str>>(
-        &self,
-        turbo_root: &AbsoluteSystemPath,
-        package_path: &AnchoredSystemPath,
-        inputs: &[S],
-    ) -> Result<GitHashes, Error> {
-        if inputs.is_empty() {
-            self.get_package_file_hashes_from_index(turbo_root, package_path)
-        } else {
-            self.get_package_file_hashes_from_inputs(turbo_root, package_path, inputs)
-        }
-    }
-
-    fn get_package_file_hashes_from_index(
+    pub(crate) fn get_package_file_hashes_from_index(
         &self,
         turbo_root: &AbsoluteSystemPath,
         package_path: &AnchoredSystemPath,
     ) -> Result<GitHashes, Error> {
         let full_pkg_path = turbo_root.resolve(package_path);
+        if let Some(nested_root) = find_nested_repo_root(&self.root, &full_pkg_path) {
+            return self
+                .nested(nested_root)
+                .get_package_file_hashes_from_index(turbo_root, package_path);
+        }
         let git_to_pkg_path = self.root.anchor(&full_pkg_path)?;
         let pkg_prefix = git_to_pkg_path.to_unix();
-        let mut hashes = self.git_ls_tree(&full_pkg_path)?;
+        let LsTree {
+            mut hashes,
+            submodules,
+        } = self.git_ls_tree(&full_pkg_path)?;
+        if self.sparse_checkout {
+            remove_missing_files(&full_pkg_path, &mut hashes);
+        }
         // Note: to_hash is *git repo relative*
         let to_hash = self.append_git_status(&full_pkg_path, &pkg_prefix, &mut hashes)?;
         hash_objects(&self.root, &full_pkg_path, to_hash, &mut hashes)?;
-        Ok(hashes)
-    }
 
-    fn hash_files(
-        &self,
-        process_relative_to: &AbsoluteSystemPath,
-        files: impl Iterator<Item = impl AsRef<AnchoredSystemPath>>,
-    ) -> Result<GitHashes, Error> {
-        let mut hashes = GitHashes::new();
-        let to_hash = files
-            .map(|f| {
-                Ok(self
-                    .root
-                    .anchor(process_relative_to.resolve(f.as_ref()))?
-                    .to_unix())
-            })
-            .collect::<Result<Vec<_>, PathError>>()?;
-        // Note: to_hash is *git repo relative*
-        hash_objects(&self.root, process_relative_to, to_hash, &mut hashes)?;
+        for submodule in submodules {
+            let submodule_root = full_pkg_path.join_unix_path(&submodule)?;
+            // If the submodule hasn't been checked out we use its commit hash
+            if !submodule_root.join_component(".git").exists() {
+                continue;
+            }
+            let submodule_hashes = self
+                .nested(submodule_root.clone())
+                .get_package_file_hashes_from_index(
+                    &submodule_root,
+                    AnchoredSystemPath::new("")?,
+                )?;
+            append_submodule_hashes(&submodule, submodule_hashes, &mut hashes)?;
+        }
         Ok(hashes)
     }
+}
 
-    fn get_package_file_hashes_from_inputs<S: AsRef<str>>(
-        &self,
-        turbo_root: &AbsoluteSystemPath,
-        package_path: &AnchoredSystemPath,
-        inputs: &[S],
-    ) -> Result<GitHashes, Error> {
-        let full_pkg_path = turbo_root.resolve(package_path);
-        let package_unix_path_buf = package_path.to_unix();
-        let package_unix_path = package_unix_path_buf.as_str();
+// Files outside of a sparse checkout are still listed in the tree, but
+// they're treated as absent as they aren't on disk to be used by any task.
+pub(crate) fn remove_missing_files(pkg_path: &AbsoluteSystemPath, hashes: &mut GitHashes) {
+    hashes.retain(|path, _| {
+        pkg_path
+            .join_unix_path(path)
+            .map_or(false, |path| path.symlink_metadata().is_ok())
+    });
+}
 
-        let mut inputs = inputs
-            .iter()
-            .map(|s| s.as_ref().to_string())
-            .collect::<Vec<String>>();
-        // Add in package.json and turbo.json to input patterns. Both file paths are
-        // relative to pkgPath
-        //
-        // - package.json is an input because if the `scripts` in the package.json
-        //   change (i.e. the tasks that turbo executes), we want a cache miss, since
-        //   any existing cache could be invalid.
-        // - turbo.json because it's the definition of the tasks themselves. The root
-        //   turbo.json is similarly included in the global hash. This file may not
-        //   exist in the workspace, but that is ok, because it will get ignored
-        //   downstream.
-        inputs.push("package.json".to_string());
-        inputs.push("turbo.json".to_string());
-
-        // The input patterns are relative to the package.
-        // However, we need to change the globbing to be relative to the repo root.
-        // Prepend the package path to each of the input patterns.
-        //
-        // FIXME: we don't yet error on absolute unix paths being passed in as inputs,
-        // and instead tack them on as if they were relative paths. This should be an
-        // error further upstream, but since we haven't pulled the switch yet,
-        // we need to mimic the Go behavior here and trim leading `/`
-        // characters.
-        let (inclusions, exclusions): (Vec<String>, Vec<String>) =
-            inputs.into_iter().partition_map(|raw_glob| {
-                if let Some(exclusion) = raw_glob.strip_prefix('!') {
-                    Either::Right([package_unix_path, exclusion.trim_start_matches('/')].join("/"))
-                } else {
-                    Either::Left([package_unix_path, raw_glob.trim_start_matches('/')].join("/"))
-                }
-            });
-        let files = globwalk::globwalk(
-            turbo_root,
-            &inclusions,
-            &exclusions,
-            globwalk::WalkType::Files,
-        )?;
-        let to_hash = files
-            .iter()
-            .map(|entry| {
-                let path = self.root.anchor(entry)?.to_unix();
-                Ok(path)
-            })
-            .collect::<Result<Vec<_>, Error>>()?;
-        let mut hashes = GitHashes::new();
-        hash_objects(&self.root, &full_pkg_path, to_hash, &mut hashes)?;
-        Ok(hashes)
+// Replaces the entry for a submodule's commit with the hashes of the files in
+// the submodule.
+pub(crate) fn append_submodule_hashes(
+    submodule: &RelativeUnixPathBuf,
+    submodule_hashes: GitHashes,
+    hashes: &mut GitHashes,
+) -> Result<(), Error> {
+    hashes.remove(submodule);
+    for (path, hash) in submodule_hashes {
+        hashes.insert(
+            RelativeUnixPathBuf::new(format!("{submodule}/{path}"))?,
+            hash,
+        );
     }
+    Ok(())
+}
+
+// Hashes the given files, `git_root` is used to match the paths that git would
+// output so hashes are the same between git backends.
+pub(crate) fn hash_files(
+    git_root: &AbsoluteSystemPath,
+    process_relative_to: &AbsoluteSystemPath,
+    files: impl Iterator<Item = impl AsRef<AnchoredSystemPath>>,
+) -> Result<GitHashes, Error> {
+    let mut hashes = GitHashes::new();
+    let to_hash = files
+        .map(|f| {
+            Ok(git_root
+                .anchor(&process_relative_to.resolve(f.as_ref()))?
+                .to_unix())
+        })
+        .collect::<Result<Vec<_>, PathError>>()?;
+    // Note: to_hash is *git repo relative*
+    hash_objects(git_root, process_relative_to, to_hash, &mut hashes)?;
+    Ok(hashes)
+}
+
+pub(crate) fn get_package_file_hashes_from_inputs<S: AsRef<str>>(
+    git_root: &AbsoluteSystemPath,
+    turbo_root: &AbsoluteSystemPath,
+    package_path: &AnchoredSystemPath,
+    inputs: &[S],
+) -> Result<GitHashes, Error> {
+    let full_pkg_path = turbo_root.resolve(package_path);
+    let package_unix_path_buf = package_path.to_unix();
+    let package_unix_path = package_unix_path_buf.as_str();
+
+    let mut inputs = inputs
+        .iter()
+        .map(|s| s.as_ref().to_string())
+        .collect::<Vec<String>>();
+    // Add in package.json and turbo.json to input patterns. Both file paths are
+    // relative to pkgPath
+    //
+    // - package.json is an input because if the `scripts` in the package.json
+    //   change (i.e. the tasks that turbo executes), we want a cache miss, since
+    //   any existing cache could be invalid.
+    // - turbo.json because it's the definition of the tasks themselves. The root
+    //   turbo.json is similarly included in the global hash. This file may not
+    //   exist in the workspace, but that is ok, because it will get ignored
+    //   downstream.
+    inputs.push("package.json".to_string());
+    inputs.push("turbo.json".to_string());
+
+    // The input patterns are relative to the package.
+    // However, we need to change the globbing to be relative to the repo root.
+    // Prepend the package path to each of the input patterns.
+    //
+    // FIXME: we don't yet error on absolute unix paths being passed in as inputs,
+    // and instead tack them on as if they were relative paths. This should be an
+    // error further upstream, but since we haven't pulled the switch yet,
+    // we need to mimic the Go behavior here and trim leading `/`
+    // characters.
+    let (inclusions, exclusions): (Vec<String>, Vec<String>) =
+        inputs.into_iter().partition_map(|raw_glob| {
+            if let Some(exclusion) = raw_glob.strip_prefix('!') {
+                Either::Right([package_unix_path, exclusion.trim_start_matches('/')].join("/"))
+            } else {
+                Either::Left([package_unix_path, raw_glob.trim_start_matches('/')].join("/"))
+            }
+        });
+    let files = globwalk::globwalk(
+        turbo_root,
+        &inclusions,
+        &exclusions,
+        globwalk::WalkType::Files,
+    )?;
+    let to_hash = files
+        .iter()
+        .map(|entry| {
+            let path = git_root.anchor(entry)?.to_unix();
+            Ok(path)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let mut hashes = GitHashes::new();
+    hash_objects(git_root, &full_pkg_path, to_hash, &mut hashes)?;
+    Ok(hashes)
 }
 
 #[cfg(test)]
@@ -191,7 +235,7 @@ mod tests {
     use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeUnixPathBuf};
 
     use super::*;
-    use crate::{manual::get_package_file_hashes_from_processing_gitignore, SCM};
+    use crate::{manual::get_package_file_hashes_from_processing_gitignore, SourceControl, SCM};
 
     fn tmp_dir() -> (tempfile::TempDir, AbsoluteSystemPathBuf) {
         let tmp_dir = tempfile::tempdir().unwrap();
@@ -343,7 +387,7 @@ mod tests {
                 "bfe53d766e64d78f80050b73cd1c88095bc70abb",
             ),
         ]);
-        let hashes = git.get_package_file_hashes::<&str>(&repo_root, &package_path, &[])?;
+        let hashes = git.get_package_file_hashes(&repo_root, &package_path, &[])?;
         assert_eq!(hashes, all_expected);
 
         // add the new root file as an option
@@ -402,6 +446,139 @@ mod tests {
         Ok(())
     }
 
+    // Both git backends should agree on the hashes of the package
+    fn backend_hashes(
+        repo_root: &AbsoluteSystemPathBuf,
+        package_path: &AnchoredSystemPath,
+    ) -> GitHashes {
+        let git = SCM::Git(crate::Git::find(repo_root).unwrap());
+        let libgit2 = SCM::LibGit2(crate::LibGit2::find(repo_root).unwrap());
+        let hashes = git
+            .get_package_file_hashes::<&str>(repo_root, package_path, &[])
+            .unwrap();
+        let libgit2_hashes = libgit2
+            .get_package_file_hashes::<&str>(repo_root, package_path, &[])
+            .unwrap();
+        assert_eq!(hashes, libgit2_hashes);
+        hashes
+    }
+
+    #[test]
+    fn test_get_package_deps_submodule() {
+        let (_tmp, tmp_root) = tmp_dir();
+        let submodule_repo = tmp_root.join_component("lib");
+        submodule_repo.create_dir_all().unwrap();
+        setup_repository(&submodule_repo);
+        submodule_repo
+            .join_component("committed-file")
+            .create_with_contents("committed bytes")
+            .unwrap();
+        commit_all(&submodule_repo);
+
+        let repo_root = tmp_root.join_component("repo");
+        repo_root.create_dir_all().unwrap();
+        setup_repository(&repo_root);
+        repo_root
+            .join_component("root-file")
+            .create_with_contents("root bytes")
+            .unwrap();
+        require_git_cmd(
+            &repo_root,
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                submodule_repo.as_str(),
+                "lib",
+            ],
+        );
+        commit_all(&repo_root);
+
+        let submodule_root = repo_root.join_component("lib");
+        submodule_root
+            .join_component("uncommitted-file")
+            .create_with_contents("uncommitted bytes")
+            .unwrap();
+
+        let hashes = backend_hashes(&repo_root, AnchoredSystemPath::new("").unwrap());
+        assert!(!hashes.contains_key(&RelativeUnixPathBuf::new("lib").unwrap()));
+        assert_eq!(
+            hashes.get(&RelativeUnixPathBuf::new("lib/committed-file").unwrap()),
+            Some(&"3a29e62ea9ba15c4a4009d1f605d391cdd262033".to_string())
+        );
+        assert!(hashes.contains_key(&RelativeUnixPathBuf::new("lib/uncommitted-file").unwrap()));
+        assert!(hashes.contains_key(&RelativeUnixPathBuf::new("root-file").unwrap()));
+
+        // Packages inside of the submodule are hashed by the submodule
+        let pkg_path = repo_root.anchor(&submodule_root).unwrap();
+        let hashes = backend_hashes(&repo_root, &pkg_path);
+        assert_eq!(
+            hashes
+                .keys()
+                .map(|path| path.as_str())
+                .sorted()
+                .collect::<Vec<_>>(),
+            vec!["committed-file", "uncommitted-file"]
+        );
+    }
+
+    #[test]
+    fn test_get_package_deps_worktree() {
+        let (_tmp, tmp_root) = tmp_dir();
+        let repo_root = tmp_root.join_component("repo");
+        let my_pkg_dir = repo_root.join_component("my-pkg");
+        my_pkg_dir.create_dir_all().unwrap();
+        setup_repository(&repo_root);
+        my_pkg_dir
+            .join_component("committed-file")
+            .create_with_contents("committed bytes")
+            .unwrap();
+        commit_all(&repo_root);
+
+        let worktree_root = tmp_root.join_component("worktree");
+        require_git_cmd(&repo_root, &["worktree", "add", worktree_root.as_str()]);
+        worktree_root
+            .join_components(&["my-pkg", "uncommitted-file"])
+            .create_with_contents("uncommitted bytes")
+            .unwrap();
+
+        let pkg_path = AnchoredSystemPathBuf::from_raw("my-pkg").unwrap();
+        let hashes = backend_hashes(&worktree_root, &pkg_path);
+        assert_eq!(
+            hashes
+                .keys()
+                .map(|path| path.as_str())
+                .sorted()
+                .collect::<Vec<_>>(),
+            vec!["committed-file", "uncommitted-file"]
+        );
+    }
+
+    #[test]
+    fn test_get_package_deps_sparse_checkout() {
+        let (_tmp, repo_root) = tmp_dir();
+        setup_repository(&repo_root);
+        for dir in ["included", "excluded"] {
+            let file = repo_root.join_components(&[dir, "committed-file"]);
+            file.ensure_dir().unwrap();
+            file.create_with_contents("committed bytes").unwrap();
+        }
+        commit_all(&repo_root);
+        require_git_cmd(&repo_root, &["sparse-checkout", "set", "included"]);
+        assert!(!repo_root.join_component("excluded").exists());
+
+        let hashes = backend_hashes(&repo_root, AnchoredSystemPath::new("").unwrap());
+        assert_eq!(
+            hashes
+                .keys()
+                .map(|path| path.as_str())
+                .sorted()
+                .collect::<Vec<_>>(),
+            vec!["included/committed-file"]
+        );
+    }
+
     fn to_hash_map(pairs: &[(&str, &str)]) -> GitHashes {
         HashMap::from_iter(
             pairs
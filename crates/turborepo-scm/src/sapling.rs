@@ -0,0 +1,391 @@
+//! Support for Sapling and Mercurial repositories. Sapling is derived from
+//! Mercurial and the two share the commands and revset syntax we rely on, so
+//! the same implementation drives whichever binary is available.
+//!
+//! File hashes are computed the same way as they are for git so a package's
+//! hash doesn't depend on the source control in use.
+
+use std::{borrow::Cow, collections::HashSet, process::Command};
+
+use tracing::debug;
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+    RelativeUnixPath, RelativeUnixPathBuf,
+};
+
+use crate::{
+    hash_object::hash_objects,
+    package_deps::{get_package_file_hashes_from_inputs, GitHashes},
+    root_from_realpath, Error, SourceControl,
+};
+
+// Binaries are tried in order, `sl` is able to operate on Mercurial
+// repositories, but not the other way around.
+const BINARIES: &[&str] = &["sl", "hg"];
+
+#[derive(Debug)]
+pub struct Sapling {
+    root: AbsoluteSystemPathBuf,
+    bin: AbsoluteSystemPathBuf,
+}
+
+impl Sapling {
+    pub fn find(path_in_repo: &AbsoluteSystemPath) -> Result<Self, Error> {
+        let mut last_error = None;
+        for name in BINARIES {
+            let bin = match which::which(name) {
+                Ok(bin) => AbsoluteSystemPathBuf::try_from(bin.as_path())?,
+                Err(e) => {
+                    last_error = Some(e.into());
+                    continue;
+                }
+            };
+            match Self::find_root(&bin, path_in_repo) {
+                Ok(root) => return Ok(Self { root, bin }),
+                Err(e) => {
+                    debug!("{name} unable to find repository: {e}");
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| Error::sapling_error("no sapling binary found")))
+    }
+
+    fn find_root(
+        bin: &AbsoluteSystemPath,
+        path_in_repo: &AbsoluteSystemPath,
+    ) -> Result<AbsoluteSystemPathBuf, Error> {
+        let output = execute(bin, path_in_repo, &["root"])?;
+        let root = String::from_utf8(output)?;
+        let root = AbsoluteSystemPathBuf::new(root.trim_end_matches('\n'))?;
+        root_from_realpath(path_in_repo, &root)
+    }
+
+    fn execute_command(&self, args: &[&str]) -> Result<Vec<u8>, Error> {
+        execute(&self.bin, &self.root, args)
+    }
+
+    // Runs `status` and reports each path along with its status code
+    fn status(
+        &self,
+        args: &[&str],
+        pattern: Option<String>,
+    ) -> Result<Vec<(u8, RelativeUnixPathBuf)>, Error> {
+        let mut status_args = vec!["status", "--print0"];
+        status_args.extend(args);
+        if let Some(pattern) = &pattern {
+            status_args.extend(["--", pattern.as_str()]);
+        }
+        let output = self.execute_command(&status_args)?;
+        parse_status(&output)
+    }
+
+    fn reanchor_path(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        path: &RelativeUnixPath,
+    ) -> Result<AnchoredSystemPathBuf, Error> {
+        let absolute_file_path = self.root.join_unix_path(path)?;
+        Ok(turbo_root.anchor(&absolute_file_path)?)
+    }
+
+    fn get_package_file_hashes_from_index(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        package_path: &AnchoredSystemPath,
+    ) -> Result<GitHashes, Error> {
+        let full_pkg_path = turbo_root.resolve(package_path);
+        let pattern = path_pattern(&self.root.anchor(&full_pkg_path)?.to_unix());
+
+        // Tracked files, including those that have been added but not committed
+        let mut files_args = vec!["files", "--print0"];
+        if let Some(pattern) = &pattern {
+            files_args.extend(["--", pattern.as_str()]);
+        }
+        let output = self.execute_command(&files_args)?;
+        let mut to_hash = split_nul(&output)
+            .map(|path| Ok(RelativeUnixPathBuf::new(String::from_utf8(path.to_vec())?)?))
+            .collect::<Result<HashSet<_>, Error>>()?;
+
+        // Files that are tracked but have been deleted aren't on disk to be hashed and
+        // untracked files that aren't ignored need to be included
+        for (status, path) in self.status(&["--deleted", "--unknown"], pattern)? {
+            match status {
+                b'!' => {
+                    to_hash.remove(&path);
+                }
+                _ => {
+                    to_hash.insert(path);
+                }
+            }
+        }
+
+        let mut hashes = GitHashes::new();
+        hash_objects(
+            &self.root,
+            &full_pkg_path,
+            to_hash.into_iter().collect(),
+            &mut hashes,
+        )?;
+        Ok(hashes)
+    }
+}
+
+impl SourceControl for Sapling {
+    fn root(&self) -> &AbsoluteSystemPath {
+        &self.root
+    }
+
+    // Sapling doesn't have branches, the closest equivalent is the active bookmark
+    fn get_current_branch(&self) -> Result<String, Error> {
+        let output = self.execute_command(&["log", "-r", ".", "-T", "{activebookmark}"])?;
+        let output = String::from_utf8(output)?;
+        Ok(output.trim().to_owned())
+    }
+
+    fn get_current_sha(&self) -> Result<String, Error> {
+        let output = self.execute_command(&["log", "-r", ".", "-T", "{node}"])?;
+        let output = String::from_utf8(output)?;
+        Ok(output.trim().to_owned())
+    }
+
+    fn changed_files(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        from_commit: Option<&str>,
+        to_commit: &str,
+    ) -> Result<HashSet<AnchoredSystemPathBuf>, Error> {
+        let pattern = path_pattern(&self.root.anchor(turbo_root)?.to_unix());
+        let to_commit = revision(to_commit);
+        let mut files = HashSet::new();
+
+        // Changes in the working directory relative to `to_commit`, including
+        // untracked files
+        let changes = self.status(&["-mardu", "--rev", &to_commit], pattern.clone())?;
+        for (_, path) in changes {
+            files.insert(self.reanchor_path(turbo_root, &path)?);
+        }
+
+        // Equivalent to `git diff <from_commit>...<to_commit>`
+        if let Some(from_commit) = from_commit {
+            let merge_base = format!("ancestor({}, {})", revision(from_commit), to_commit);
+            let changes = self.status(
+                &["-mar", "--rev", &merge_base, "--rev", &to_commit],
+                pattern,
+            )?;
+            for (_, path) in changes {
+                files.insert(self.reanchor_path(turbo_root, &path)?);
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn previous_content(
+        &self,
+        from_commit: &str,
+        file_path: &AbsoluteSystemPath,
+    ) -> Result<Vec<u8>, Error> {
+        let anchored_file_path = self.root.anchor(file_path)?.to_unix();
+        let pattern = format!("path:{}", anchored_file_path);
+        self.execute_command(&["cat", "-r", &revision(from_commit), "--", &pattern])
+    }
+
+    fn get_package_file_hashes(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        package_path: &AnchoredSystemPath,
+        inputs: &[&str],
+    ) -> Result<GitHashes, Error> {
+        if inputs.is_empty() {
+            self.get_package_file_hashes_from_index(turbo_root, package_path)
+        } else {
+            get_package_file_hashes_from_inputs(&self.root, turbo_root, package_path, inputs)
+        }
+    }
+}
+
+fn execute(
+    bin: &AbsoluteSystemPath,
+    cwd: &AbsoluteSystemPath,
+    args: &[&str],
+) -> Result<Vec<u8>, Error> {
+    let output = Command::new(bin.as_std_path())
+        .args(args)
+        .current_dir(cwd)
+        // Disables user configuration that changes output e.g. aliases or relative paths
+        .env("HGPLAIN", "1")
+        .output()?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(Error::sapling_error(
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ))
+    }
+}
+
+// Revisions given to `--filter` are usually written for git, the working
+// directory's parent is `.` instead of `HEAD`.
+fn revision(rev: &str) -> Cow<str> {
+    match rev.strip_prefix("HEAD") {
+        Some(suffix) => Cow::Owned(format!(".{suffix}")),
+        None => Cow::Borrowed(rev),
+    }
+}
+
+// Patterns are interpreted as globs relative to the working directory by
+// default, `path:` matches the literal path and everything beneath it.
+fn path_pattern(path: &RelativeUnixPathBuf) -> Option<String> {
+    (!path.as_str().is_empty()).then(|| format!("path:{}", path))
+}
+
+fn split_nul(output: &[u8]) -> impl Iterator<Item = &[u8]> {
+    output
+        .split(|b| *b == b'\0')
+        .filter(|path| !path.is_empty())
+}
+
+// Entries are formatted as `<status> <path>`
+fn parse_status(output: &[u8]) -> Result<Vec<(u8, RelativeUnixPathBuf)>, Error> {
+    split_nul(output)
+        .map(|entry| {
+            let [status, b' ', path @ ..] = entry else {
+                return Err(Error::sapling_error(format!(
+                    "unexpected status output: {}",
+                    String::from_utf8_lossy(entry)
+                )));
+            };
+            let status = *status;
+            let path = RelativeUnixPathBuf::new(String::from_utf8(path.to_vec())?)?;
+            Ok((status, path))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use test_case::test_case;
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeUnixPathBuf};
+
+    use super::*;
+
+    #[test_case("HEAD", "." ; "head")]
+    #[test_case("HEAD^1", ".^1" ; "parent")]
+    #[test_case("HEAD~2", ".~2" ; "ancestor")]
+    #[test_case("main", "main" ; "bookmark")]
+    fn test_revision(rev: &str, expected: &str) {
+        assert_eq!(revision(rev), expected);
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let output = b"M a/modified\0? a/untracked\0! deleted\0";
+        let statuses = parse_status(output).unwrap();
+        assert_eq!(
+            statuses,
+            vec![
+                (b'M', RelativeUnixPathBuf::new("a/modified").unwrap()),
+                (b'?', RelativeUnixPathBuf::new("a/untracked").unwrap()),
+                (b'!', RelativeUnixPathBuf::new("deleted").unwrap()),
+            ]
+        );
+    }
+
+    fn tmp_dir() -> (tempfile::TempDir, AbsoluteSystemPathBuf) {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let dir = AbsoluteSystemPathBuf::try_from(tmp_dir.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+        (tmp_dir, dir)
+    }
+
+    fn require_cmd(bin: &str, repo_root: &AbsoluteSystemPathBuf, args: &[&str]) {
+        let output = Command::new(bin)
+            .args(["--config", "ui.username=test <test@example.com>"])
+            .args(args)
+            .current_dir(repo_root)
+            .env("HGPLAIN", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    // Exercises whichever of the supported binaries is installed, run with
+    // `cargo test -- --ignored` on a machine that has `sl` or `hg`
+    #[test]
+    #[ignore = "requires sl or hg to be installed"]
+    fn test_sapling_change_detection() {
+        let bin = BINARIES
+            .iter()
+            .find(|bin| which::which(bin).is_ok())
+            .expect("neither sl nor hg is installed");
+        let (_tmp, repo_root) = tmp_dir();
+        require_cmd(bin, &repo_root, &["init", "."]);
+        let sapling = Sapling::find(&repo_root).unwrap();
+        assert_eq!(sapling.root, repo_root);
+
+        let pkg_dir = repo_root.join_component("my-pkg");
+        pkg_dir.create_dir_all().unwrap();
+        pkg_dir
+            .join_component("committed-file")
+            .create_with_contents("committed bytes")
+            .unwrap();
+        pkg_dir
+            .join_component("deleted-file")
+            .create_with_contents("deleted bytes")
+            .unwrap();
+        require_cmd(bin, &repo_root, &["commit", "-A", "-m", "first"]);
+        let first = sapling.get_current_sha().unwrap();
+
+        pkg_dir
+            .join_component("committed-file")
+            .create_with_contents("changed bytes")
+            .unwrap();
+        require_cmd(bin, &repo_root, &["commit", "-m", "second"]);
+        pkg_dir
+            .join_component("deleted-file")
+            .remove_file()
+            .unwrap();
+        pkg_dir
+            .join_component("uncommitted-file")
+            .create_with_contents("uncommitted bytes")
+            .unwrap();
+
+        let pkg_path = AnchoredSystemPathBuf::from_raw("my-pkg").unwrap();
+        let hashes = sapling
+            .get_package_file_hashes(&repo_root, &pkg_path, &[])
+            .unwrap();
+        let mut keys = hashes.keys().map(|path| path.as_str()).collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, vec!["committed-file", "uncommitted-file"]);
+        // Hashes match those computed for git
+        assert_eq!(
+            hashes[&RelativeUnixPathBuf::new("uncommitted-file").unwrap()],
+            git2::Oid::hash_object(git2::ObjectType::Blob, b"uncommitted bytes")
+                .unwrap()
+                .to_string()
+        );
+
+        let mut changed = sapling
+            .changed_files(&repo_root, Some(&first), "HEAD")
+            .unwrap()
+            .into_iter()
+            .map(|path| path.to_string())
+            .collect::<Vec<_>>();
+        changed.sort();
+        let expected = ["committed-file", "deleted-file", "uncommitted-file"]
+            .iter()
+            .map(|file| pkg_path.as_path().join(file).to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(changed, expected);
+
+        let content = sapling
+            .previous_content(&first, &pkg_dir.join_component("committed-file"))
+            .unwrap();
+        assert_eq!(content, b"committed bytes");
+    }
+}
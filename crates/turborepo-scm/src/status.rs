@@ -20,6 +20,8 @@ impl Git {
                 "status",
                 "--untracked-files",
                 "--no-renames",
+                // Submodules are hashed separately
+                "--ignore-submodules=all",
                 "-z",
                 "--",
                 ".",
@@ -2,6 +2,7 @@ mod color_selector;
 mod logs;
 mod output;
 mod prefixed;
+mod redact;
 
 use std::{borrow::Cow, env, f64::consts::PI, time::Duration};
 
@@ -15,6 +16,7 @@ pub use crate::{
     logs::{replay_logs, LogWriter},
     output::{OutputClient, OutputClientBehavior, OutputSink, OutputWriter},
     prefixed::{PrefixedUI, PrefixedWriter},
+    redact::Redactor,
 };
 
 #[derive(Debug, Error)]
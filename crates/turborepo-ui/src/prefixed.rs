@@ -6,7 +6,7 @@ use std::{
 use console::{Style, StyledObject};
 use tracing::error;
 
-use crate::UI;
+use crate::{Redactor, UI};
 
 /// Writes messages with different prefixes, depending on log level. Note that
 /// this does output the prefix when message is empty, unlike the Go
@@ -96,6 +96,7 @@ enum Command {
 pub struct PrefixedWriter<W> {
     prefix: String,
     writer: W,
+    redactor: Redactor,
 }
 
 impl<W> Debug for PrefixedWriter<W> {
@@ -109,7 +110,17 @@ impl<W> Debug for PrefixedWriter<W> {
 impl<W: Write> PrefixedWriter<W> {
     pub fn new(ui: UI, prefix: StyledObject<impl Display>, writer: W) -> Self {
         let prefix = ui.apply(prefix).to_string();
-        Self { prefix, writer }
+        Self {
+            prefix,
+            writer,
+            redactor: Redactor::default(),
+        }
+    }
+
+    /// Masks any secrets known to `redactor` before writing
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = redactor;
+        self
     }
 }
 
@@ -117,6 +128,13 @@ impl<W: Write> Write for PrefixedWriter<W> {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         self.writer.write_all(self.prefix.as_bytes())?;
 
+        if !self.redactor.is_empty() {
+            // Redacting changes the length of the output, so we write it all at once and
+            // report the whole buffer as written
+            self.writer.write_all(&self.redactor.redact_bytes(buf))?;
+            return Ok(buf.len());
+        }
+
         // We do end up writing more bytes than this to the underlying writer, but we
         // cannot report this to the callers as the amount of bytes we report
         // written must be less than or equal to the number of bytes in the buffer.
@@ -179,4 +197,20 @@ mod test {
         writer.write_all(b"cool!").unwrap();
         assert_eq!(String::from_utf8(buffer).unwrap(), expected);
     }
+
+    #[test]
+    fn test_prefixed_writer_redacts_secrets() {
+        let mut buffer = Vec::new();
+        let mut writer = PrefixedWriter::new(
+            UI::new(true),
+            crate::BOLD.apply_to("foo#build: "),
+            &mut buffer,
+        )
+        .with_redactor(Redactor::new(["hunter2".to_string()]));
+        writer.write_all(b"connecting with hunter2\n").unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "foo#build: connecting with *****\n"
+        );
+    }
 }
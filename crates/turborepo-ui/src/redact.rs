@@ -0,0 +1,88 @@
+use std::borrow::Cow;
+
+/// Masks secret values, e.g. the values of sensitive environment variables,
+/// before they're written to output.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    // Sorted longest first so that a secret containing another secret is
+    // redacted as a whole
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    pub const PLACEHOLDER: &'static str = "*****";
+
+    pub fn new(secrets: impl IntoIterator<Item = String>) -> Self {
+        let mut secrets = secrets
+            .into_iter()
+            .filter(|secret| !secret.is_empty())
+            .collect::<Vec<_>>();
+        secrets.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        secrets.dedup();
+        Self { secrets }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty()
+    }
+
+    /// Returns `text` with every occurrence of a secret replaced by
+    /// `Redactor::PLACEHOLDER`
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut redacted = Cow::Borrowed(text);
+        for secret in &self.secrets {
+            if redacted.contains(secret.as_str()) {
+                redacted = Cow::Owned(redacted.replace(secret.as_str(), Self::PLACEHOLDER));
+            }
+        }
+        redacted
+    }
+
+    /// Like `redact`, but for output that isn't guaranteed to be valid UTF-8
+    pub fn redact_bytes<'a>(&self, bytes: &'a [u8]) -> Cow<'a, [u8]> {
+        let mut redacted = Cow::Borrowed(bytes);
+        for secret in &self.secrets {
+            let secret = secret.as_bytes();
+            let Some(first) = find(&redacted, secret) else {
+                continue;
+            };
+
+            let mut replaced = Vec::with_capacity(redacted.len());
+            let mut rest = &redacted[..];
+            let mut next = Some(first);
+            while let Some(index) = next {
+                replaced.extend_from_slice(&rest[..index]);
+                replaced.extend_from_slice(Self::PLACEHOLDER.as_bytes());
+                rest = &rest[index + secret.len()..];
+                next = find(rest, secret);
+            }
+            replaced.extend_from_slice(rest);
+            redacted = Cow::Owned(replaced);
+        }
+        redacted
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(&[], "no secrets here", "no secrets here" ; "no secrets")]
+    #[test_case(&["hunter2"], "password is hunter2", "password is *****" ; "single secret")]
+    #[test_case(&["abc"], "abcabc-abc", "**********-*****" ; "repeated secret")]
+    #[test_case(&["token", "token-123"], "using token-123", "using *****" ; "overlapping secrets")]
+    #[test_case(&[""], "empty secrets are ignored", "empty secrets are ignored" ; "empty secret")]
+    fn test_redact(secrets: &[&str], input: &str, expected: &str) {
+        let redactor = Redactor::new(secrets.iter().map(|s| s.to_string()));
+        assert_eq!(redactor.redact(input), expected);
+        assert_eq!(redactor.redact_bytes(input.as_bytes()), expected.as_bytes());
+    }
+}
@@ -22,6 +22,16 @@ export interface BaseSchema {
      */
     [script: string]: Pipeline;
   };
+
+  /**
+   * A list of tags for this workspace. Tags group workspaces so they can be
+   * selected together, e.g. `--filter=tag:ui`.
+   *
+   * Documentation: https://turbo.build/repo/docs/core-concepts/monorepos/filtering#filter-by-tag
+   *
+   * @defaultValue []
+   */
+  tags?: Array<string>;
 }
 
 export interface WorkspaceSchema extends BaseSchema {
@@ -103,6 +113,17 @@ export interface RootSchema extends BaseSchema {
    */
   globalDotEnv?: null | Array<AnchoredUnixPath>;
 
+  /**
+   * Checks that the installed package manager matches the version in the root
+   * `package.json`'s `packageManager` field before running tasks. `"warn"` prints
+   * a warning when they differ and `"error"` stops the run.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#packagemanagercheck
+   *
+   * @defaultValue "off"
+   */
+  packageManagerCheck?: "off" | "warn" | "error";
+
   /**
    * Configuration options that control how turbo interfaces with the remote cache.
    *
@@ -111,6 +132,14 @@ export interface RootSchema extends BaseSchema {
    * @defaultValue `{}`
    */
   remoteCache?: RemoteCache;
+
+  /**
+   * Rules for which tagged workspaces may depend on each other. Violations
+   * are reported by `turbo boundaries`.
+   *
+   * @defaultValue null
+   */
+  boundaries?: Boundaries;
 }
 
 export interface Pipeline {
@@ -179,6 +208,26 @@ export interface Pipeline {
    */
   dotEnv?: null | Array<AnchoredUnixPath>;
 
+  /**
+   * Load the variables defined in the `dotEnv` files into the task's environment.
+   * Variables that are already set in the environment take precedence.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#loadDotEnv
+   *
+   * @defaultValue false
+   */
+  loadDotEnv?: boolean;
+
+  /**
+   * A list of environment variables whose values are redacted from the task's
+   * logs and from run summaries, e.g. `DATABASE_PASSWORD`.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#secretEnv
+   *
+   * @defaultValue []
+   */
+  secretEnv?: Array<string>;
+
   /**
    * The set of glob patterns indicating a task's cacheable filesystem outputs.
    *
@@ -263,6 +312,49 @@ export interface RemoteCache {
   signature?: boolean;
 }
 
+export interface Boundaries {
+  /**
+   * Check boundaries before running any tasks and stop the run if they
+   * are violated.
+   *
+   * @defaultValue false
+   */
+  enforceOnRun?: boolean;
+
+  /**
+   * Rules keyed by the tag of the workspace being checked.
+   *
+   * @defaultValue `{}`
+   */
+  // eslint-disable-next-line @typescript-eslint/consistent-indexed-object-style -- it's more readable to specify a name for the key
+  tags?: {
+    [tag: string]: TagRule;
+  };
+}
+
+export interface TagRule {
+  /**
+   * If present, every dependency must have at least one of these tags.
+   *
+   * @defaultValue null
+   */
+  allow?: Array<string>;
+
+  /**
+   * Dependencies with any of these tags are forbidden.
+   *
+   * @defaultValue []
+   */
+  deny?: Array<string>;
+
+  /**
+   * Check all transitive dependencies instead of only direct ones.
+   *
+   * @defaultValue false
+   */
+  transitive?: boolean;
+}
+
 export type OutputMode =
   | "full"
   | "hash-only"
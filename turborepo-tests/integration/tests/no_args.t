@@ -9,13 +9,16 @@ Make sure exit code is 2 when no args are passed
   
   Commands:
     bin         Get the path to the Turbo binary
+    boundaries  Check workspace dependencies against the boundaries defined in turbo.json
     completion  Generate the autocompletion script for the specified shell
     daemon      Runs the Turborepo background daemon
     generate    Generate a new app / package
     link        Link your local directory to a Vercel organization and enable remote caching
     login       Login to your Vercel account
+    lockfile    Inspect the external dependencies resolved by your lockfile
     logout      Logout to your Vercel account
     prune       Prepare a subset of your monorepo
+    query       Query the package and task graphs of your monorepo
     run         Run tasks across projects in your monorepo
     unlink      Unlink the current directory from your Vercel organization and disable Remote Caching
   
@@ -61,6 +64,7 @@ Make sure exit code is 2 when no args are passed
         --only                           Only executes the tasks specified, does not execute parent tasks
         --parallel                       Execute all tasks in parallel
         --profile <PROFILE>              File to write turbo's performance profile output into. You can load the file up in chrome://tracing to see which parts of your build were slow
+        --pty                            Run tasks in a pseudo-terminal so that they keep colors and interactive features. When there is a single persistent task, stdin is forwarded to it. Only supported on unix systems
         --remote-only [<BOOL>]           Ignore the local filesystem cache for all tasks. Only allow reading and caching artifacts using the remote cache [env: TURBO_REMOTE_ONLY=] [default: false] [possible values: true, false]
         --scope <SCOPE>                  Specify package(s) to act as entry points for task execution. Supports globs
         --since <SINCE>                  Limit/Set scope to changed packages since a mergebase. This uses the git diff ${target_branch}... mechanism to identify which packages have changed